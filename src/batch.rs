@@ -0,0 +1,288 @@
+use std::collections::HashSet;
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+
+use crate::format::FormatResult;
+
+/// Files written during a session, waiting to be formatted when it stops.
+/// Stored under the system temp directory as NUL-terminated paths, since a
+/// file name may contain a newline, in a directory only its owner can use.
+pub struct Ledger {
+    path: PathBuf,
+}
+
+impl Ledger {
+    /// Ledger for a session, `None` if the session id is empty
+    pub fn for_session(session_id: &str) -> Option<Self> {
+        if session_id.is_empty() {
+            return None;
+        }
+
+        // Session ids are UUIDs, but keep anything else from escaping the
+        // directory. Other bytes are %-escaped so distinct ids stay distinct.
+        let mut name = String::new();
+        for b in session_id.bytes() {
+            if b.is_ascii_alphanumeric() || b == b'-' || b == b'_' {
+                name.push(char::from(b));
+            } else {
+                name.push_str(&format!("%{:02X}", b));
+            }
+        }
+
+        Some(Self {
+            path: env::temp_dir()
+                .join("ralph-hook-fmt")
+                .join(format!("{}.paths", name)),
+        })
+    }
+
+    /// Add a file to be formatted later
+    pub fn record(&self, file_path: &Path) -> io::Result<()> {
+        if let Some(dir) = self.path.parent() {
+            private_dir(dir)?;
+        }
+
+        let mut entry = path_bytes(file_path);
+        entry.push(b'\0');
+
+        // A single append per path, so concurrent hooks don't interleave
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?
+            .write_all(&entry)
+    }
+
+    /// Remove the ledger and return its files, in the order they were first
+    /// recorded and without duplicates
+    pub fn take(&self) -> Vec<PathBuf> {
+        if self
+            .path
+            .parent()
+            .is_none_or(|dir| private_dir(dir).is_err())
+        {
+            return Vec::new();
+        }
+
+        // Move the ledger aside first: paths recorded meanwhile start a new
+        // ledger instead of being deleted unread
+        let taken = self.path.with_extension(format!("taken-{}", process::id()));
+        if fs::rename(&self.path, &taken).is_err() {
+            return Vec::new();
+        }
+        let content = fs::read(&taken).unwrap_or_default();
+        let _ = fs::remove_file(&taken);
+
+        let mut seen = HashSet::new();
+        content
+            .split(|b| *b == b'\0')
+            .filter(|entry| !entry.is_empty())
+            .map(path_from_bytes)
+            .filter(|file_path| seen.insert(file_path.clone()))
+            .collect()
+    }
+}
+
+/// Create `dir` for the owner alone. An existing directory must not be a
+/// symlink and must belong to us, which `chmod` succeeding shows, or
+/// another user could plant a ledger of paths to format.
+#[cfg(unix)]
+fn private_dir(dir: &Path) -> io::Result<()> {
+    use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+
+    match fs::DirBuilder::new().mode(0o700).create(dir) {
+        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {}
+        result => return result,
+    }
+
+    let metadata = fs::symlink_metadata(dir)?;
+    if !metadata.is_dir() || metadata.permissions().mode() & 0o022 != 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!("{} is writable by others", dir.display()),
+        ));
+    }
+    fs::set_permissions(dir, fs::Permissions::from_mode(0o700))
+}
+
+#[cfg(not(unix))]
+fn private_dir(dir: &Path) -> io::Result<()> {
+    fs::create_dir_all(dir)
+}
+
+#[cfg(unix)]
+fn path_bytes(path: &Path) -> Vec<u8> {
+    use std::os::unix::ffi::OsStrExt;
+    path.as_os_str().as_bytes().to_vec()
+}
+
+#[cfg(not(unix))]
+fn path_bytes(path: &Path) -> Vec<u8> {
+    path.to_string_lossy().into_owned().into_bytes()
+}
+
+#[cfg(unix)]
+fn path_from_bytes(bytes: &[u8]) -> PathBuf {
+    use std::os::unix::ffi::OsStrExt;
+    PathBuf::from(std::ffi::OsStr::from_bytes(bytes))
+}
+
+#[cfg(not(unix))]
+fn path_from_bytes(bytes: &[u8]) -> PathBuf {
+    PathBuf::from(String::from_utf8_lossy(bytes).into_owned())
+}
+
+/// One message covering a whole batch: files grouped by the formatter that
+/// handled them, followed by anything that could not be formatted.
+/// Paths are shown relative to `cwd` when they are inside it.
+pub fn summarize(results: &[(PathBuf, FormatResult)], cwd: Option<&Path>) -> String {
+    let display = |file_path: &Path| {
+        cwd.and_then(|cwd| file_path.strip_prefix(cwd).ok())
+            .unwrap_or(file_path)
+            .display()
+            .to_string()
+    };
+
+    let mut groups: Vec<(&str, Vec<String>)> = Vec::new();
+    let mut failures = Vec::new();
+
+    for (file_path, result) in results {
+        match (&result.formatter, result.formatted) {
+            (Some(formatter), true) => match groups.iter_mut().find(|(f, _)| f == formatter) {
+                Some((_, files)) => files.push(display(file_path)),
+                None => groups.push((formatter, vec![display(file_path)])),
+            },
+            _ => failures.push(format!("{}: {}", display(file_path), result.message)),
+        }
+    }
+
+    let formatted: usize = groups.iter().map(|(_, files)| files.len()).sum();
+    let mut lines = vec![format!(
+        "[ralph-hook-fmt] Formatted {} of {} file{}",
+        formatted,
+        results.len(),
+        if results.len() == 1 { "" } else { "s" }
+    )];
+
+    for (formatter, files) in &groups {
+        lines.push(format!("{}: {}", formatter, files.join(", ")));
+    }
+    if !failures.is_empty() {
+        lines.push("Not formatted:".to_string());
+        lines.extend(failures);
+    }
+
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ledger_in(dir: &Path) -> Ledger {
+        Ledger {
+            path: dir.join("session.paths"),
+        }
+    }
+
+    #[test]
+    fn test_session_id_is_sanitized() {
+        let ledger = Ledger::for_session("../../etc/passwd").unwrap();
+        assert_eq!(
+            ledger.path.file_name().unwrap().to_str(),
+            Some("%2E%2E%2F%2E%2E%2Fetc%2Fpasswd.paths")
+        );
+        assert_ne!(
+            Ledger::for_session("a/b").unwrap().path,
+            Ledger::for_session("a_b").unwrap().path
+        );
+        assert!(Ledger::for_session("").is_none());
+    }
+
+    #[test]
+    fn test_take_dedupes_in_order() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let ledger = ledger_in(temp_dir.path());
+
+        for file in ["/p/b.rs", "/p/a.rs", "/p/b.rs"] {
+            ledger.record(Path::new(file)).unwrap();
+        }
+
+        assert_eq!(
+            ledger.take(),
+            vec![PathBuf::from("/p/b.rs"), PathBuf::from("/p/a.rs")]
+        );
+        // The ledger is cleared once taken
+        assert!(ledger.take().is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_ledger_directory_is_private() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let dir = temp_dir.path().join("ralph-hook-fmt");
+        let ledger = ledger_in(&dir);
+
+        ledger.record(Path::new("/p/a.rs")).unwrap();
+        let mode = fs::metadata(&dir).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o700);
+
+        // A ledger in a directory others can write to is not trusted
+        fs::set_permissions(&dir, fs::Permissions::from_mode(0o777)).unwrap();
+        assert!(ledger.record(Path::new("/p/b.rs")).is_err());
+        assert!(ledger.take().is_empty());
+    }
+
+    #[test]
+    fn test_take_leaves_no_files_behind() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let ledger = ledger_in(temp_dir.path());
+
+        ledger.record(Path::new("/p/a.rs")).unwrap();
+        assert_eq!(ledger.take(), vec![PathBuf::from("/p/a.rs")]);
+        ledger.record(Path::new("/p/b.rs")).unwrap();
+        assert_eq!(ledger.take(), vec![PathBuf::from("/p/b.rs")]);
+        assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_newline_in_file_name() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let ledger = ledger_in(temp_dir.path());
+
+        ledger.record(Path::new("/p/a\nb.rs")).unwrap();
+        ledger.record(Path::new("/p/c.rs")).unwrap();
+
+        assert_eq!(
+            ledger.take(),
+            vec![PathBuf::from("/p/a\nb.rs"), PathBuf::from("/p/c.rs")]
+        );
+    }
+
+    #[test]
+    fn test_summarize_groups_by_formatter() {
+        let results = vec![
+            (PathBuf::from("/p/a.ts"), FormatResult::success("prettier")),
+            (
+                PathBuf::from("/p/A.java"),
+                FormatResult::success("spotless (Gradle)"),
+            ),
+            (PathBuf::from("/p/b.ts"), FormatResult::success("prettier")),
+            (PathBuf::from("/p/c.xyz"), FormatResult::unsupported("xyz")),
+        ];
+
+        assert_eq!(
+            summarize(&results, Some(Path::new("/p"))),
+            "[ralph-hook-fmt] Formatted 3 of 4 files\n\
+             prettier: a.ts, b.ts\n\
+             spotless (Gradle): A.java\n\
+             Not formatted:\n\
+             c.xyz: Unsupported file extension: xyz"
+        );
+    }
+}
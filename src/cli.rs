@@ -0,0 +1,471 @@
+use std::ffi::OsString;
+use std::path::PathBuf;
+
+pub const USAGE: &str = "\
+Format files with the project's own formatter.
+
+Usage: ralph-hook-fmt [COMMAND] [OPTIONS]
+
+Commands:
+  hook    Run as a Claude Code hook, reading the payload on stdin (default)
+  fmt     Format the given files and directories
+  check   List files that are not formatted, without writing them
+  stdin   Format stdin to stdout as if it were the contents of a path
+  explain Show how the formatter for a file is chosen
+  doctor  Show the formatter for every supported language in a directory
+  help    Print this message
+
+Options:
+  -h, --help     Print help
+  -V, --version  Print version
+
+Run 'ralph-hook-fmt <COMMAND> --help' for the options of a command.
+";
+
+pub const HOOK_USAGE: &str = "\
+Run as a Claude Code hook, reading the payload on stdin.
+
+Usage: ralph-hook-fmt [hook] [OPTIONS]
+
+Options:
+      --project-only    Only use formatters installed in the project
+      --debug           Report what happened in systemMessage
+      --pre-tool-use    Format Write content before it is written
+      --batch           Record edited files and format them on Stop
+      --on-stop         Format the files recorded in batch mode
+      --block-on-error  Block with diagnostics when a formatter hits a syntax error
+      --input <FILE>    Read the payload from FILE instead of stdin
+  -h, --help            Print help
+
+Unknown options are ignored with a warning on stderr, so a newer hooks.json
+never makes the hook fail the tool call.
+";
+
+pub const FMT_USAGE: &str = "\
+Format the given files and directories. Directories are searched
+recursively for files with a supported extension.
+
+Usage: ralph-hook-fmt fmt [OPTIONS] <PATH>...
+
+Options:
+      --check         Only report files that are not formatted
+      --project-only  Only use formatters installed in the project
+  -h, --help          Print help
+
+Exit status is 0 if every file was formatted or had nothing to format,
+1 if a path is missing or a formatter failed, 2 on a usage error.
+";
+
+pub const CHECK_USAGE: &str = "\
+List files that are not formatted, without writing them. Files are run
+through the same formatter `fmt` would use and compared with their contents.
+
+Usage: ralph-hook-fmt check [OPTIONS] <PATH>...
+
+Options:
+      --diff          Show what formatting would change
+      --project-only  Only use formatters installed in the project
+  -h, --help          Print help
+
+Exit status is 0 if every file is formatted or has nothing to format,
+1 if a file is not formatted, a path is missing or a formatter failed,
+2 on a usage error.
+";
+
+pub const STDIN_USAGE: &str = "\
+Format source read from stdin and print the result on stdout. The formatter
+and project configuration are picked as if the source were the contents of
+the --path file, which does not need to exist.
+
+Usage: ralph-hook-fmt stdin --path <PATH> [OPTIONS] < FILE
+
+Options:
+      --path <PATH>   File the source belongs to
+      --project-only  Only use formatters installed in the project
+  -h, --help          Print help
+
+When no formatter applies, the source is printed unchanged. If the formatter
+fails, nothing is printed and the exit status is 1.
+";
+
+pub const EXPLAIN_USAGE: &str = "\
+Show how the formatter for a file is chosen: the project root it is
+resolved from and every candidate formatter, in priority order, with the
+reason it was selected or skipped.
+
+Usage: ralph-hook-fmt explain [OPTIONS] <FILE>
+
+Options:
+      --project-only  Only use formatters installed in the project
+      --json          Print JSON
+  -h, --help          Print help
+";
+
+pub const DOCTOR_USAGE: &str = "\
+Show the project root and formatter, with its version, for every supported
+language, as seen from DIR (default: the current directory).
+
+Usage: ralph-hook-fmt doctor [OPTIONS] [DIR]
+
+Options:
+      --project-only  Only use formatters installed in the project
+      --json          Print JSON
+  -h, --help          Print help
+";
+
+/// Options for hook mode
+#[derive(Debug, Default, PartialEq)]
+pub struct HookOptions {
+    pub debug: bool,
+    pub project_only: bool,
+    pub pre_tool_use: bool,
+    pub batch: bool,
+    pub on_stop: bool,
+    pub block_on_error: bool,
+    /// Replay a payload from a file
+    pub input: Option<PathBuf>,
+    /// Arguments that were not understood and are ignored
+    pub warnings: Vec<String>,
+}
+
+/// Options for the `fmt` and `check` commands
+#[derive(Debug, Default, PartialEq)]
+pub struct FmtOptions {
+    pub project_only: bool,
+    /// Report unformatted files instead of formatting them
+    pub check: bool,
+    /// With `check`, print what formatting would change
+    pub diff: bool,
+    pub paths: Vec<PathBuf>,
+}
+
+/// Options for the `stdin` command
+#[derive(Debug, Default, PartialEq)]
+pub struct StdinOptions {
+    pub project_only: bool,
+    /// Where the source would live, for formatter and config lookup
+    pub path: PathBuf,
+}
+
+/// Options for the `explain` and `doctor` commands
+#[derive(Debug, Default, PartialEq)]
+pub struct ExplainOptions {
+    pub project_only: bool,
+    pub json: bool,
+    /// File to explain, or directory to examine
+    pub path: PathBuf,
+}
+
+/// What the command line asked for
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Hook(HookOptions),
+    Fmt(FmtOptions),
+    Stdin(StdinOptions),
+    Explain(ExplainOptions),
+    Doctor(ExplainOptions),
+    Help(&'static str),
+    Version,
+}
+
+/// Parse the arguments after the program name.
+/// Without a command the arguments are hook options, as in `hooks.json`.
+pub fn parse(args: impl IntoIterator<Item = OsString>) -> Result<Command, String> {
+    let mut args: Vec<OsString> = args.into_iter().collect();
+
+    let command = args.first().and_then(|arg| arg.to_str()).unwrap_or("");
+    match command {
+        "fmt" => parse_fmt(args.split_off(1), FmtOptions::default(), FMT_USAGE),
+        "check" => {
+            let options = FmtOptions {
+                check: true,
+                ..FmtOptions::default()
+            };
+            parse_fmt(args.split_off(1), options, CHECK_USAGE)
+        }
+        "stdin" => parse_stdin(args.split_off(1)),
+        "explain" => parse_explain(args.split_off(1), false),
+        "doctor" => parse_explain(args.split_off(1), true),
+        "hook" => parse_hook(args.split_off(1), HOOK_USAGE),
+        "help" => Ok(Command::Help(USAGE)),
+        _ => parse_hook(args, USAGE),
+    }
+}
+
+/// `usage` is printed for --help
+fn parse_hook(args: Vec<OsString>, usage: &'static str) -> Result<Command, String> {
+    let mut options = HookOptions::default();
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        let flag = arg.to_str().unwrap_or("");
+        match flag {
+            "-h" | "--help" => return Ok(Command::Help(usage)),
+            "-V" | "--version" => return Ok(Command::Version),
+            "--debug" => options.debug = true,
+            "--project-only" => options.project_only = true,
+            "--pre-tool-use" => options.pre_tool_use = true,
+            "--batch" => options.batch = true,
+            "--on-stop" => options.on_stop = true,
+            "--block-on-error" => options.block_on_error = true,
+            "--input" => match args.next() {
+                Some(file) => options.input = Some(PathBuf::from(file)),
+                None => options.warnings.push("--input needs a file".to_string()),
+            },
+            _ => match flag.strip_prefix("--input=") {
+                Some(file) => options.input = Some(PathBuf::from(file)),
+                None => options.warnings.push(format!(
+                    "ignoring unexpected argument '{}'",
+                    arg.to_string_lossy()
+                )),
+            },
+        }
+    }
+
+    Ok(Command::Hook(options))
+}
+
+/// Shared by `fmt` and `check`, which start from different `options`
+fn parse_fmt(
+    args: Vec<OsString>,
+    mut options: FmtOptions,
+    usage: &'static str,
+) -> Result<Command, String> {
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        match arg.to_str().unwrap_or("") {
+            "-h" | "--help" => return Ok(Command::Help(usage)),
+            "-V" | "--version" => return Ok(Command::Version),
+            "--project-only" => options.project_only = true,
+            "--check" => options.check = true,
+            "--diff" => options.diff = true,
+            // Everything after -- is a path
+            "--" => options.paths.extend(args.by_ref().map(PathBuf::from)),
+            flag if flag.starts_with('-') && flag.len() > 1 => {
+                return Err(format!("unexpected argument '{}'", flag));
+            }
+            _ => options.paths.push(PathBuf::from(arg)),
+        }
+    }
+
+    if options.paths.is_empty() {
+        let command = if options.check { "check" } else { "fmt" };
+        return Err(format!("{} needs at least one path", command));
+    }
+    if options.diff && !options.check {
+        return Err("--diff only applies to check".to_string());
+    }
+
+    Ok(Command::Fmt(options))
+}
+
+fn parse_stdin(args: Vec<OsString>) -> Result<Command, String> {
+    let mut project_only = false;
+    let mut path = None;
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        let flag = arg.to_str().unwrap_or("");
+        match flag {
+            "-h" | "--help" => return Ok(Command::Help(STDIN_USAGE)),
+            "-V" | "--version" => return Ok(Command::Version),
+            "--project-only" => project_only = true,
+            "--path" => path = Some(PathBuf::from(args.next().ok_or("--path needs a file")?)),
+            _ => match flag.strip_prefix("--path=") {
+                Some(file) => path = Some(PathBuf::from(file)),
+                None => return Err(format!("unexpected argument '{}'", arg.to_string_lossy())),
+            },
+        }
+    }
+
+    let path = path.ok_or("stdin needs --path")?;
+    Ok(Command::Stdin(StdinOptions { project_only, path }))
+}
+
+/// Shared by `explain`, which needs a file, and `doctor`, which takes an
+/// optional directory
+fn parse_explain(args: Vec<OsString>, doctor: bool) -> Result<Command, String> {
+    let mut options = ExplainOptions::default();
+    let mut path = None;
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        match arg.to_str().unwrap_or("") {
+            "-h" | "--help" if doctor => return Ok(Command::Help(DOCTOR_USAGE)),
+            "-h" | "--help" => return Ok(Command::Help(EXPLAIN_USAGE)),
+            "-V" | "--version" => return Ok(Command::Version),
+            "--project-only" => options.project_only = true,
+            "--json" => options.json = true,
+            "--" => path = args.next().map(PathBuf::from),
+            flag if flag.starts_with('-') && flag.len() > 1 => {
+                return Err(format!("unexpected argument '{}'", flag));
+            }
+            _ if path.is_some() => {
+                return Err(format!("unexpected argument '{}'", arg.to_string_lossy()));
+            }
+            _ => path = Some(PathBuf::from(arg)),
+        }
+    }
+
+    if doctor {
+        options.path = path.unwrap_or_else(|| PathBuf::from("."));
+        Ok(Command::Doctor(options))
+    } else {
+        options.path = path.ok_or("explain needs a file")?;
+        Ok(Command::Explain(options))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_args(args: &[&str]) -> Result<Command, String> {
+        parse(args.iter().map(OsString::from))
+    }
+
+    #[test]
+    fn test_no_command_is_hook_mode() {
+        assert_eq!(
+            parse_args(&["--project-only", "--debug"]),
+            Ok(Command::Hook(HookOptions {
+                debug: true,
+                project_only: true,
+                ..HookOptions::default()
+            }))
+        );
+        assert_eq!(parse_args(&[]), Ok(Command::Hook(HookOptions::default())));
+    }
+
+    #[test]
+    fn test_hook_input_file() {
+        let expected = Ok(Command::Hook(HookOptions {
+            input: Some(PathBuf::from("payload.json")),
+            ..HookOptions::default()
+        }));
+        assert_eq!(parse_args(&["hook", "--input", "payload.json"]), expected);
+        assert_eq!(parse_args(&["--input=payload.json"]), expected);
+        assert_eq!(
+            parse_args(&["--input"]),
+            Ok(Command::Hook(HookOptions {
+                warnings: vec!["--input needs a file".to_string()],
+                ..HookOptions::default()
+            }))
+        );
+    }
+
+    #[test]
+    fn test_fmt_paths() {
+        assert_eq!(
+            parse_args(&["fmt", "src/a.rs", "--project-only", "--", "-b.ts"]),
+            Ok(Command::Fmt(FmtOptions {
+                project_only: true,
+                paths: vec![PathBuf::from("src/a.rs"), PathBuf::from("-b.ts")],
+                ..FmtOptions::default()
+            }))
+        );
+        assert!(parse_args(&["fmt"]).is_err());
+        assert!(parse_args(&["fmt", "--write", "a.rs"]).is_err());
+    }
+
+    #[test]
+    fn test_check() {
+        let expected = Ok(Command::Fmt(FmtOptions {
+            check: true,
+            paths: vec![PathBuf::from("src")],
+            ..FmtOptions::default()
+        }));
+        assert_eq!(parse_args(&["check", "src"]), expected);
+        assert_eq!(parse_args(&["fmt", "--check", "src"]), expected);
+        assert_eq!(
+            parse_args(&["check"]),
+            Err("check needs at least one path".to_string())
+        );
+        assert!(parse_args(&["fmt", "--diff", "src"]).is_err());
+        assert_eq!(
+            parse_args(&["check", "--help"]),
+            Ok(Command::Help(CHECK_USAGE))
+        );
+    }
+
+    #[test]
+    fn test_stdin_path() {
+        assert_eq!(
+            parse_args(&["stdin", "--path", "src/foo.ts", "--project-only"]),
+            Ok(Command::Stdin(StdinOptions {
+                project_only: true,
+                path: PathBuf::from("src/foo.ts"),
+            }))
+        );
+        assert_eq!(
+            parse_args(&["stdin", "--path=a.rs"]),
+            Ok(Command::Stdin(StdinOptions {
+                project_only: false,
+                path: PathBuf::from("a.rs"),
+            }))
+        );
+        assert_eq!(
+            parse_args(&["stdin"]),
+            Err("stdin needs --path".to_string())
+        );
+    }
+
+    #[test]
+    fn test_explain_and_doctor() {
+        assert_eq!(
+            parse_args(&["explain", "--json", "src/a.rs"]),
+            Ok(Command::Explain(ExplainOptions {
+                project_only: false,
+                json: true,
+                path: PathBuf::from("src/a.rs"),
+            }))
+        );
+        assert_eq!(
+            parse_args(&["explain"]),
+            Err("explain needs a file".to_string())
+        );
+        assert!(parse_args(&["explain", "a.rs", "b.rs"]).is_err());
+        assert_eq!(
+            parse_args(&["doctor", "--project-only"]),
+            Ok(Command::Doctor(ExplainOptions {
+                project_only: true,
+                json: false,
+                path: PathBuf::from("."),
+            }))
+        );
+        assert_eq!(
+            parse_args(&["doctor", "-h"]),
+            Ok(Command::Help(DOCTOR_USAGE))
+        );
+    }
+
+    #[test]
+    fn test_help_and_version() {
+        assert_eq!(parse_args(&["--help"]), Ok(Command::Help(USAGE)));
+        assert_eq!(
+            parse_args(&["hook", "--help"]),
+            Ok(Command::Help(HOOK_USAGE))
+        );
+        assert_eq!(parse_args(&["help"]), Ok(Command::Help(USAGE)));
+        assert_eq!(parse_args(&["fmt", "-h"]), Ok(Command::Help(FMT_USAGE)));
+        assert_eq!(parse_args(&["-V"]), Ok(Command::Version));
+    }
+
+    #[test]
+    fn test_unknown_argument() {
+        // The hook must not fail the tool call over a flag it does not know
+        assert_eq!(
+            parse_args(&["--frobnicate", "--debug"]),
+            Ok(Command::Hook(HookOptions {
+                debug: true,
+                warnings: vec!["ignoring unexpected argument '--frobnicate'".to_string()],
+                ..HookOptions::default()
+            }))
+        );
+        assert_eq!(
+            parse_args(&["explain", "--frobnicate", "a.rs"]),
+            Err("unexpected argument '--frobnicate'".to_string())
+        );
+    }
+}
@@ -0,0 +1,250 @@
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{self, Path, PathBuf};
+use std::process::ExitCode;
+
+use crate::cli::{ExplainOptions, FmtOptions, StdinOptions};
+use crate::explain::{self, Versions};
+use crate::format::{FormatResult, check_file, format_content, format_files, is_supported};
+use crate::json::Value;
+
+/// Directories never searched when formatting a directory
+const SKIPPED_DIRS: [&str; 2] = ["node_modules", "target"];
+
+/// `fmt`: format files and directories, one line of output per file.
+/// With `check`, only files that are not formatted are listed.
+pub fn fmt(options: &FmtOptions) -> ExitCode {
+    let mut failed = false;
+
+    // Paths as given, for output, and in absolute form for project lookups
+    let mut shown: Vec<PathBuf> = Vec::new();
+    let mut files: Vec<PathBuf> = Vec::new();
+
+    for path in &options.paths {
+        let found = if path.is_dir() {
+            collect_files(path)
+        } else if path.exists() {
+            vec![path.clone()]
+        } else {
+            eprintln!("{}: No such file or directory", path.display());
+            failed = true;
+            continue;
+        };
+
+        for file in found {
+            let absolute = fs::canonicalize(&file).unwrap_or_else(|_| file.clone());
+            if !files.contains(&absolute) {
+                shown.push(file);
+                files.push(absolute);
+            }
+        }
+    }
+
+    if options.check {
+        let results: Vec<FormatResult> = files
+            .iter()
+            .map(|file| check_file(file, options.project_only))
+            .collect();
+        return if report_check(&shown, &results, options.diff) || failed {
+            ExitCode::FAILURE
+        } else {
+            ExitCode::SUCCESS
+        };
+    }
+
+    let results = format_files(&files, options.project_only);
+
+    for (file, result) in shown.iter().zip(&results) {
+        match (&result.formatter, result.formatted) {
+            (Some(formatter), true) if result.changes.is_some() => {
+                println!("{}: reformatted with {}", file.display(), formatter)
+            }
+            (Some(formatter), true) => println!("{}: unchanged ({})", file.display(), formatter),
+            (Some(_), false) => {
+                eprintln!("{}: {}", file.display(), result.message.trim_end());
+                failed = true;
+            }
+            (None, _) => println!("{}: {}", file.display(), result.message),
+        }
+    }
+
+    if failed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+/// `stdin`: format stdin as if it were the contents of `--path`
+pub fn stdin(options: &StdinOptions) -> ExitCode {
+    let mut content = String::new();
+    if let Err(e) = io::stdin().read_to_string(&mut content) {
+        eprintln!("Failed to read stdin: {}", e);
+        return ExitCode::FAILURE;
+    }
+
+    // Project roots are found from the hint, so it has to be absolute
+    let hint = path::absolute(&options.path).unwrap_or_else(|_| options.path.clone());
+    let result = format_content(&hint, &content, options.project_only);
+
+    let output = match (result.output, result.formatter) {
+        (Some(output), _) => output,
+        // The formatter failed: print nothing rather than a half result
+        (None, Some(_)) => {
+            eprintln!("{}", result.message.trim_end());
+            return ExitCode::FAILURE;
+        }
+        (None, None) => {
+            eprintln!("{}", result.message);
+            content
+        }
+    };
+
+    match io::stdout().write_all(output.as_bytes()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(_) => ExitCode::FAILURE,
+    }
+}
+
+/// `explain`: how the formatter for one file is chosen
+pub fn explain(options: &ExplainOptions) -> ExitCode {
+    let file_path = absolute(&options.path);
+    let report = explain::explain(&file_path, options.project_only, &mut Versions::default());
+
+    if options.json {
+        println!("{}", report.to_json().to_pretty_string("  "));
+    } else {
+        println!("{}", report.to_text());
+    }
+    ExitCode::SUCCESS
+}
+
+/// `doctor`: the formatter for every supported language in a directory
+pub fn doctor(options: &ExplainOptions) -> ExitCode {
+    let dir = absolute(&options.path);
+    if !dir.is_dir() {
+        eprintln!("{}: Not a directory", options.path.display());
+        return ExitCode::FAILURE;
+    }
+    let reports = explain::doctor(&dir, options.project_only);
+
+    if options.json {
+        let json = Value::Object(vec![
+            (
+                "directory".to_string(),
+                Value::String(dir.to_string_lossy().into_owned()),
+            ),
+            ("projectOnly".to_string(), Value::Bool(options.project_only)),
+            (
+                "languages".to_string(),
+                Value::Array(reports.iter().map(|r| r.to_json()).collect()),
+            ),
+        ]);
+        println!("{}", json.to_pretty_string("  "));
+    } else {
+        println!("Directory: {}", dir.display());
+        if let Some(config) = reports.first().and_then(|r| r.config.as_ref()) {
+            println!("Config:    {}", config.display());
+        }
+        if options.project_only {
+            println!("Only formatters installed in the project are used");
+        }
+        for report in &reports {
+            println!("\n{}", report.to_summary());
+        }
+    }
+    ExitCode::SUCCESS
+}
+
+/// Absolute, canonical when it exists, so project lookups see every ancestor
+fn absolute(path: &Path) -> PathBuf {
+    fs::canonicalize(path)
+        .or_else(|_| path::absolute(path))
+        .unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// List unformatted files and failures, returning whether there were any
+fn report_check(shown: &[PathBuf], results: &[FormatResult], diff: bool) -> bool {
+    let mut failed = false;
+
+    for (file, result) in shown.iter().zip(results) {
+        match (&result.formatter, result.formatted, &result.changes) {
+            (Some(formatter), true, Some(changes)) => {
+                println!("{}: would be reformatted by {}", file.display(), formatter);
+                if diff {
+                    println!("{}", changes.trim_end());
+                }
+                failed = true;
+            }
+            (Some(_), false, _) => {
+                eprintln!("{}: {}", file.display(), result.message.trim_end());
+                failed = true;
+            }
+            _ => {}
+        }
+    }
+
+    failed
+}
+
+/// Supported files under `dir`, in a stable order. Hidden entries and
+/// dependency or build directories are skipped.
+fn collect_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+
+    let Ok(entries) = fs::read_dir(dir) else {
+        return files;
+    };
+    let mut entries: Vec<_> = entries.filter_map(Result::ok).collect();
+    entries.sort_by_key(|entry| entry.file_name());
+
+    for entry in entries {
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if name.starts_with('.') {
+            continue;
+        }
+
+        let path = entry.path();
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        if file_type.is_dir() {
+            if !SKIPPED_DIRS.contains(&name.as_ref()) {
+                files.extend(collect_files(&path));
+            }
+        } else if file_type.is_file() && is_supported(&path) {
+            files.push(path);
+        }
+    }
+
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_collect_files() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        for file in [
+            "b.rs",
+            "a/c.py",
+            "a/notes.txt",
+            ".git/config.json",
+            "node_modules/x/index.js",
+            "package-lock.json",
+        ] {
+            let path = root.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "").unwrap();
+        }
+
+        assert_eq!(
+            collect_files(root),
+            vec![root.join("a/c.py"), root.join("b.rs")]
+        );
+    }
+}
@@ -0,0 +1,655 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::glob::Glob;
+use crate::json::Value;
+use crate::toml;
+use crate::tool::{Custom, Formatter, Tool};
+
+/// Name of the project configuration file
+pub const CONFIG_FILE: &str = ".ralph-hook-fmt.toml";
+
+/// Keys for `[languages.<key>]` tables, and the languages they configure
+const LANGUAGES: [(&str, &str); 17] = [
+    ("javascript", "JavaScript/TypeScript"),
+    ("rust", "Rust"),
+    ("python", "Python"),
+    ("java", "Java"),
+    ("go", "Go"),
+    ("json", "JSON"),
+    ("yaml", "YAML"),
+    ("toml", "TOML"),
+    ("html", "HTML"),
+    ("vue", "Vue"),
+    ("css", "CSS"),
+    ("scss", "SCSS"),
+    ("less", "Less"),
+    ("markdown", "Markdown"),
+    ("mdx", "MDX"),
+    ("graphql", "GraphQL"),
+    ("handlebars", "Handlebars"),
+];
+
+/// Settings for a language or extension. Unset fields fall back to the
+/// language's settings, then to the built-in behavior.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Rules {
+    pub enabled: Option<bool>,
+    /// Formatters to try, in order
+    pub formatters: Option<Vec<Choice>>,
+    pub project_only: Option<bool>,
+}
+
+/// A formatter named in a `formatters` list
+#[derive(Debug, Clone, PartialEq)]
+pub enum Choice {
+    /// One built-in tool, or several run in sequence (`"goimports + gofumpt"`)
+    Tools(Vec<Tool>),
+    /// A `[formatters.<name>]` table
+    Custom(String),
+}
+
+/// Directory a custom formatter runs from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Cwd {
+    /// The directory holding the config file
+    Root,
+    /// The directory holding the file being formatted
+    File,
+}
+
+/// The `[skip]` table: which files are treated as generated or vendored.
+/// Unset fields keep the built-in detection.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Skip {
+    /// Whether files with a generated-code header are skipped
+    pub generated: Option<bool>,
+    /// Directory names whose contents are skipped
+    pub directories: Option<Vec<String>>,
+}
+
+/// A `[formatters.<name>]` table: a command run once per file
+#[derive(Debug, Clone, PartialEq)]
+pub struct CustomFormatter {
+    pub name: String,
+    /// Program and arguments, with `{file}`, `{root}` and `{ext}` placeholders
+    pub command: Vec<String>,
+    /// Files it applies to, relative to the config directory. Empty if it
+    /// only runs where a `formatters` list names it.
+    pub files: Vec<Glob>,
+    pub cwd: Cwd,
+    pub success_codes: Vec<i32>,
+}
+
+impl CustomFormatter {
+    /// The formatter for `file_path`, with `{root}` being `root`
+    pub fn formatter(&self, root: &Path, file_path: &Path) -> Formatter {
+        let custom = Custom {
+            args: self.command[1..].to_vec(),
+            root: root.to_path_buf(),
+            success_codes: self.success_codes.clone(),
+        };
+
+        // Relative program paths are relative to the config directory
+        let program = PathBuf::from(self.command[0].replace("{root}", &root.to_string_lossy()));
+        let program = if program.components().count() > 1 && program.is_relative() {
+            root.join(program)
+        } else {
+            program
+        };
+
+        let cwd = match self.cwd {
+            Cwd::Root => Some(root),
+            Cwd::File => file_path.parent(),
+        };
+        Formatter::custom(&self.name, program, custom).in_dir(cwd)
+    }
+}
+
+/// A parsed `.ralph-hook-fmt.toml`
+#[derive(Debug)]
+pub struct Config {
+    pub path: PathBuf,
+    /// By language name, as reported in results
+    languages: Vec<(&'static str, Rules)>,
+    /// By file extension, without the dot
+    extensions: Vec<(String, Rules)>,
+    formatters: Vec<CustomFormatter>,
+    pub skip: Skip,
+    /// Files added to the built-in protected files, with why, relative to
+    /// the config directory
+    protected: Vec<(Glob, String)>,
+    /// `sort` in `[package_json]`: sort package.json keys with
+    /// sort-package-json instead of only re-indenting
+    pub sort_package_json: bool,
+}
+
+impl Config {
+    /// The config file nearest to `file_path`, if any
+    pub fn find(file_path: &Path) -> Result<Option<Config>, String> {
+        let Some(path) = file_path
+            .ancestors()
+            .skip(1)
+            .map(|dir| dir.join(CONFIG_FILE))
+            .find(|path| path.is_file())
+        else {
+            return Ok(None);
+        };
+
+        let content =
+            fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Config::parse(&content, path.clone())
+            .map(Some)
+            .map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    fn parse(content: &str, path: PathBuf) -> Result<Config, String> {
+        let mut config = Config {
+            path,
+            languages: Vec::new(),
+            extensions: Vec::new(),
+            formatters: Vec::new(),
+            skip: Skip::default(),
+            protected: Vec::new(),
+            sort_package_json: false,
+        };
+
+        let document = toml::parse(content)?;
+        // Custom formatters first, so `formatters` lists can name them
+        if let Some(value) = document.get("formatters") {
+            for (name, value) in entries(value, "[formatters]")? {
+                if Tool::from_key(name).is_some() {
+                    return Err(format!(
+                        "[formatters.{}] would shadow the built-in formatter, pick another name",
+                        name
+                    ));
+                }
+                config.formatters.push(parse_custom(name, value)?);
+            }
+        }
+
+        for (key, value) in entries(&document, "the top level")? {
+            match key.as_str() {
+                "formatters" => {}
+                "skip" => config.skip = parse_skip(value)?,
+                "package_json" => {
+                    for (key, value) in entries(value, "[package_json]")? {
+                        match key.as_str() {
+                            "sort" => {
+                                config.sort_package_json = value.as_bool().ok_or_else(|| {
+                                    "sort in [package_json] must be a boolean".to_string()
+                                })?
+                            }
+                            _ => return Err(format!("unknown key '{}' in [package_json]", key)),
+                        }
+                    }
+                }
+                "protected" => {
+                    for (pattern, value) in entries(value, "[protected]")? {
+                        let glob =
+                            Glob::new(pattern).map_err(|e| format!("{} in [protected]", e))?;
+                        let reason = value.as_str().ok_or_else(|| {
+                            format!("'{}' in [protected] must be a string reason", pattern)
+                        })?;
+                        config.protected.push((glob, reason.to_string()));
+                    }
+                }
+                "languages" => {
+                    for (name, value) in entries(value, "[languages]")? {
+                        let Some((_, language)) = LANGUAGES.iter().find(|(key, _)| key == name)
+                        else {
+                            let known: Vec<&str> = LANGUAGES.iter().map(|(key, _)| *key).collect();
+                            return Err(format!(
+                                "unknown language '{}', expected one of: {}",
+                                name,
+                                known.join(", ")
+                            ));
+                        };
+                        let table = format!("[languages.{}]", name);
+                        config
+                            .languages
+                            .push((language, parse_rules(value, &table, &config.formatters)?));
+                    }
+                }
+                "extensions" => {
+                    for (ext, value) in entries(value, "[extensions]")? {
+                        let table = format!("[extensions.{}]", ext);
+                        let ext = ext.trim_start_matches('.').to_string();
+                        config
+                            .extensions
+                            .push((ext, parse_rules(value, &table, &config.formatters)?));
+                    }
+                }
+                _ => return Err(format!("unknown key '{}'", key)),
+            }
+        }
+
+        Ok(config)
+    }
+
+    /// Directory holding the config file
+    pub fn root(&self) -> &Path {
+        self.path.parent().unwrap_or(Path::new("."))
+    }
+
+    /// A custom formatter by name
+    pub fn custom(&self, name: &str) -> Option<&CustomFormatter> {
+        self.formatters.iter().find(|custom| custom.name == name)
+    }
+
+    /// Custom formatters whose `files` globs match `file_path`
+    pub fn matching(&self, file_path: &Path) -> Vec<&CustomFormatter> {
+        let Ok(relative) = file_path.strip_prefix(self.root()) else {
+            return Vec::new();
+        };
+        self.formatters
+            .iter()
+            .filter(|custom| {
+                custom
+                    .files
+                    .iter()
+                    .any(|glob| glob.matches(relative, false))
+            })
+            .collect()
+    }
+
+    /// Why `file_path` is protected, if a `[protected]` pattern matches it
+    pub fn protected_reason(&self, file_path: &Path) -> Option<&str> {
+        let relative = file_path.strip_prefix(self.root()).ok()?;
+        self.protected
+            .iter()
+            .find(|(glob, _)| glob.matches(relative, false))
+            .map(|(_, reason)| reason.as_str())
+    }
+
+    /// Settings for a file of `language` with extension `ext`: the
+    /// extension's settings, then the language's
+    pub fn rules(&self, language: Option<&str>, ext: &str) -> Rules {
+        let find = |rules: Option<&Rules>| rules.cloned().unwrap_or_default();
+        let by_ext = find(
+            self.extensions
+                .iter()
+                .find(|(e, _)| e == ext)
+                .map(|(_, r)| r),
+        );
+        let by_language = find(
+            self.languages
+                .iter()
+                .find(|(l, _)| Some(*l) == language)
+                .map(|(_, r)| r),
+        );
+
+        Rules {
+            enabled: by_ext.enabled.or(by_language.enabled),
+            formatters: by_ext.formatters.or(by_language.formatters),
+            project_only: by_ext.project_only.or(by_language.project_only),
+        }
+    }
+}
+
+fn entries<'a>(value: &'a Value, table: &str) -> Result<&'a [(String, Value)], String> {
+    match value {
+        Value::Object(entries) => Ok(entries),
+        _ => Err(format!("{} must be a table", table)),
+    }
+}
+
+fn parse_rules(value: &Value, table: &str, customs: &[CustomFormatter]) -> Result<Rules, String> {
+    let mut rules = Rules::default();
+
+    for (key, value) in entries(value, table)? {
+        let expected = |kind: &str| format!("{} in {} must be {}", key, table, kind);
+        match key.as_str() {
+            "enabled" => {
+                rules.enabled = Some(value.as_bool().ok_or_else(|| expected("a boolean"))?)
+            }
+            "project_only" => {
+                rules.project_only = Some(value.as_bool().ok_or_else(|| expected("a boolean"))?)
+            }
+            "formatter" => {
+                let name = value.as_str().ok_or_else(|| expected("a string"))?;
+                rules.formatters = Some(vec![parse_choice(name, customs)?]);
+            }
+            "formatters" => {
+                let Value::Array(names) = value else {
+                    return Err(expected("an array of strings"));
+                };
+                let formatters = names
+                    .iter()
+                    .map(|name| {
+                        name.as_str()
+                            .ok_or_else(|| expected("an array of strings"))
+                            .and_then(|name| parse_choice(name, customs))
+                    })
+                    .collect::<Result<_, _>>()?;
+                rules.formatters = Some(formatters);
+            }
+            _ => return Err(format!("unknown key '{}' in {}", key, table)),
+        }
+    }
+
+    Ok(rules)
+}
+
+/// A custom formatter's name, or built-in names joined with `+` to run in
+/// sequence
+fn parse_choice(name: &str, customs: &[CustomFormatter]) -> Result<Choice, String> {
+    if customs.iter().any(|custom| custom.name == name) {
+        return Ok(Choice::Custom(name.to_string()));
+    }
+
+    name.split('+')
+        .map(|part| {
+            Tool::from_key(part.trim()).ok_or_else(|| {
+                let known: Vec<&str> = Tool::ALL
+                    .iter()
+                    .map(|tool| tool.key())
+                    .chain(customs.iter().map(|custom| custom.name.as_str()))
+                    .collect();
+                format!(
+                    "unknown formatter '{}', expected one of: {}",
+                    part.trim(),
+                    known.join(", ")
+                )
+            })
+        })
+        .collect::<Result<_, _>>()
+        .map(Choice::Tools)
+}
+
+fn parse_skip(value: &Value) -> Result<Skip, String> {
+    let mut skip = Skip::default();
+
+    for (key, value) in entries(value, "[skip]")? {
+        let expected = |kind: &str| format!("{} in [skip] must be {}", key, kind);
+        match key.as_str() {
+            "generated" => {
+                skip.generated = Some(value.as_bool().ok_or_else(|| expected("a boolean"))?)
+            }
+            "directories" => {
+                skip.directories =
+                    Some(strings(value).ok_or_else(|| expected("an array of strings"))?)
+            }
+            _ => return Err(format!("unknown key '{}' in [skip]", key)),
+        }
+    }
+
+    Ok(skip)
+}
+
+fn parse_custom(name: &str, value: &Value) -> Result<CustomFormatter, String> {
+    let table = format!("[formatters.{}]", name);
+    let mut custom = CustomFormatter {
+        name: name.to_string(),
+        command: Vec::new(),
+        files: Vec::new(),
+        cwd: Cwd::Root,
+        success_codes: vec![0],
+    };
+
+    for (key, value) in entries(value, &table)? {
+        let expected = |kind: &str| format!("{} in {} must be {}", key, table, kind);
+        match key.as_str() {
+            // A string is split on whitespace; use an array for arguments
+            // with spaces
+            "command" => {
+                custom.command = match value {
+                    Value::String(command) => {
+                        command.split_whitespace().map(str::to_string).collect()
+                    }
+                    Value::Array(_) => {
+                        strings(value).ok_or_else(|| expected("a string or an array of strings"))?
+                    }
+                    _ => return Err(expected("a string or an array of strings")),
+                }
+            }
+            "files" => {
+                custom.files = strings(value)
+                    .ok_or_else(|| expected("an array of strings"))?
+                    .iter()
+                    .map(|pattern| Glob::new(pattern).map_err(|e| format!("{} in {}", e, table)))
+                    .collect::<Result<_, _>>()?;
+            }
+            "cwd" => {
+                custom.cwd = match value.as_str() {
+                    Some("root") => Cwd::Root,
+                    Some("file") => Cwd::File,
+                    _ => return Err(expected("\"root\" or \"file\"")),
+                }
+            }
+            "success_codes" => {
+                let Value::Array(codes) = value else {
+                    return Err(expected("an array of integers"));
+                };
+                custom.success_codes = codes
+                    .iter()
+                    .map(|code| match code {
+                        Value::Number(n) => n.parse().ok(),
+                        _ => None,
+                    })
+                    .collect::<Option<_>>()
+                    .ok_or_else(|| expected("an array of integers"))?;
+            }
+            _ => return Err(format!("unknown key '{}' in {}", key, table)),
+        }
+    }
+
+    if custom.command.is_empty() {
+        return Err(format!("{} needs a command", table));
+    }
+    Ok(custom)
+}
+
+/// An array of strings
+fn strings(value: &Value) -> Option<Vec<String>> {
+    match value {
+        Value::Array(items) => items
+            .iter()
+            .map(|item| item.as_str().map(str::to_string))
+            .collect(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(content: &str) -> Result<Config, String> {
+        Config::parse(content, PathBuf::from(CONFIG_FILE))
+    }
+
+    #[test]
+    fn test_extension_overrides_language() {
+        let config = parse(
+            "[languages.javascript]\n\
+             formatters = [\"biome\", \"prettier\"]\n\
+             project_only = true\n\
+             \n\
+             [extensions.mjs]\n\
+             formatter = \"prettier\"\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.rules(Some("JavaScript/TypeScript"), "mjs"),
+            Rules {
+                enabled: None,
+                formatters: Some(vec![Choice::Tools(vec![Tool::Prettier])]),
+                project_only: Some(true),
+            }
+        );
+        assert_eq!(
+            config.rules(Some("JavaScript/TypeScript"), "ts").formatters,
+            Some(vec![
+                Choice::Tools(vec![Tool::Biome]),
+                Choice::Tools(vec![Tool::Prettier])
+            ])
+        );
+        assert_eq!(config.rules(Some("Python"), "py"), Rules::default());
+    }
+
+    #[test]
+    fn test_combined_and_disabled() {
+        let config = parse(
+            "languages.go.formatter = \"goimports + gofumpt\"\n\
+             extensions.\".ipynb\".enabled = false\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.rules(Some("Go"), "go").formatters,
+            Some(vec![Choice::Tools(vec![Tool::Goimports, Tool::Gofumpt])])
+        );
+        assert_eq!(config.rules(None, "ipynb").enabled, Some(false));
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        assert!(
+            parse("[languages.pyhton]\n")
+                .unwrap_err()
+                .starts_with("unknown language 'pyhton', expected one of: javascript, rust")
+        );
+        assert!(
+            parse("languages.python.formatter = \"blak\"\n")
+                .unwrap_err()
+                .starts_with("unknown formatter 'blak'")
+        );
+        assert_eq!(
+            parse("[languages.go]\nenabled = \"no\"\n").unwrap_err(),
+            "enabled in [languages.go] must be a boolean"
+        );
+        assert_eq!(
+            parse("[languages.go]\nformater = \"gofmt\"\n").unwrap_err(),
+            "unknown key 'formater' in [languages.go]"
+        );
+        assert_eq!(
+            parse("[languages.go\n").unwrap_err(),
+            "line 1: expected ']'"
+        );
+    }
+
+    #[test]
+    fn test_custom_formatters() {
+        let config = Config::parse(
+            "[formatters.sqlfmt]\n\
+             command = \"scripts/sqlfmt --write {file}\"\n\
+             files = [\"*.sql\", \"db/**/*.psql\"]\n\
+             cwd = \"file\"\n\
+             success_codes = [0, 1]\n\
+             \n\
+             [formatters.house]\n\
+             command = [\"house-fmt\", \"--style\", \"{root}/style file\", \"{file}\"]\n\
+             \n\
+             [languages.go]\n\
+             formatters = [\"house\", \"gofmt\"]\n",
+            PathBuf::from("/p/.ralph-hook-fmt.toml"),
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.rules(Some("Go"), "go").formatters,
+            Some(vec![
+                Choice::Custom("house".to_string()),
+                Choice::Tools(vec![Tool::Gofmt])
+            ])
+        );
+
+        let names = |file: &str| -> Vec<String> {
+            config
+                .matching(Path::new(file))
+                .iter()
+                .map(|custom| custom.name.clone())
+                .collect()
+        };
+        assert_eq!(names("/p/a/b.sql"), ["sqlfmt"]);
+        assert_eq!(names("/p/db/x/b.psql"), ["sqlfmt"]);
+        assert!(names("/p/b.psql").is_empty());
+        assert!(names("/elsewhere/b.sql").is_empty());
+
+        let sqlfmt = config.custom("sqlfmt").unwrap();
+        assert_eq!(sqlfmt.success_codes, [0, 1]);
+        let formatter = sqlfmt.formatter(config.root(), Path::new("/p/a/b.sql"));
+        assert_eq!(formatter.name, "sqlfmt");
+        let step = &formatter.steps[0];
+        assert_eq!(step.program, Path::new("/p/scripts/sqlfmt"));
+        assert!(!step.global);
+        assert_eq!(step.cwd.as_deref(), Some(Path::new("/p/a")));
+
+        let house = config.custom("house").unwrap();
+        let step = &house
+            .formatter(config.root(), Path::new("/p/main.go"))
+            .steps[0];
+        assert!(step.global);
+        assert_eq!(step.cwd.as_deref(), Some(Path::new("/p")));
+    }
+
+    #[test]
+    fn test_invalid_custom_formatters() {
+        assert_eq!(
+            parse("[formatters.x]\nfiles = [\"*.x\"]\n").unwrap_err(),
+            "[formatters.x] needs a command"
+        );
+        assert_eq!(
+            parse("[formatters.prettier]\ncommand = \"p\"\n").unwrap_err(),
+            "[formatters.prettier] would shadow the built-in formatter, pick another name"
+        );
+        assert_eq!(
+            parse("[formatters.x]\ncommand = \"x\"\ncwd = \"home\"\n").unwrap_err(),
+            "cwd in [formatters.x] must be \"root\" or \"file\""
+        );
+        assert_eq!(
+            parse("[formatters.x]\ncommand = \"x\"\nsuccess_codes = [\"0\"]\n").unwrap_err(),
+            "success_codes in [formatters.x] must be an array of integers"
+        );
+        assert_eq!(
+            parse("[formatters.x]\ncommand = \"x\"\nfiles = [\"{a\"]\n").unwrap_err(),
+            "unbalanced braces in '{a' in [formatters.x]"
+        );
+    }
+
+    #[test]
+    fn test_skip() {
+        let config = parse("[skip]\ngenerated = false\ndirectories = [\"third_party\"]\n").unwrap();
+        assert_eq!(
+            config.skip,
+            Skip {
+                generated: Some(false),
+                directories: Some(vec!["third_party".to_string()]),
+            }
+        );
+        assert_eq!(parse("").unwrap().skip, Skip::default());
+        assert_eq!(
+            parse("[skip]\nvendored = false\n").unwrap_err(),
+            "unknown key 'vendored' in [skip]"
+        );
+    }
+
+    #[test]
+    fn test_protected() {
+        let config = parse(
+            "[protected]\n\"schema.graphql\" = \"generated by the API build\"\n\"db/*.lock.json\" = \"written by migrate\"\n",
+        )
+        .unwrap();
+        let root = config.root().to_path_buf();
+        assert_eq!(
+            config.protected_reason(&root.join("api/schema.graphql")),
+            Some("generated by the API build")
+        );
+        assert!(
+            config
+                .protected_reason(&root.join("db/users.lock.json"))
+                .is_some()
+        );
+        assert!(
+            config
+                .protected_reason(&root.join("users.lock.json"))
+                .is_none()
+        );
+        assert_eq!(
+            parse("[protected]\n\"a.json\" = true\n").unwrap_err(),
+            "'a.json' in [protected] must be a string reason"
+        );
+    }
+}
@@ -0,0 +1,475 @@
+use std::fmt;
+
+/// At most this many diagnostics are reported, the first errors matter most
+const MAX_DIAGNOSTICS: usize = 10;
+
+/// Longer messages are cut to this many characters
+const MAX_MESSAGE_CHARS: usize = 200;
+
+/// A syntax error reported by a formatter
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub file: String,
+    pub line: u32,
+    pub column: u32,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}: {}",
+            self.file, self.line, self.column, self.message
+        )
+    }
+}
+
+/// How a formatter prints syntax errors
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Style {
+    /// `error: msg` then ` --> file:line:col`
+    Rustc,
+    /// `error: Failed to parse file:line:col: msg`
+    Ruff,
+    /// `error: cannot format file: Cannot parse: line:col: code`
+    Black,
+    /// `file:line:col: msg`
+    Go,
+    /// `[error] file: SyntaxError: msg (line:col)`
+    Prettier,
+    /// `file:line:col category ━━━` then `× msg`
+    Biome,
+    /// `× msg` then `╭─[file:line:col]`
+    Oxc,
+}
+
+impl Style {
+    fn for_formatter(name: &str) -> Option<Self> {
+        match name {
+            "rustfmt" | "cargo fmt" => Some(Style::Rustc),
+            "ruff" => Some(Style::Ruff),
+            "black" => Some(Style::Black),
+            "gofmt" | "gofumpt" | "goimports" => Some(Style::Go),
+            "prettier" => Some(Style::Prettier),
+            "biome" => Some(Style::Biome),
+            "oxfmt" => Some(Style::Oxc),
+            _ => None,
+        }
+    }
+}
+
+/// Parse a formatter's error output into diagnostics.
+/// `formatter` is the name from the format result; for chained formatters
+/// ("goimports + gofumpt") the patterns of every step are tried.
+pub fn parse(formatter: &str, stderr: &str) -> Vec<Diagnostic> {
+    let stderr = strip_ansi(stderr);
+
+    let mut styles: Vec<Style> = Vec::new();
+    for style in formatter.split(" + ").filter_map(Style::for_formatter) {
+        if !styles.contains(&style) {
+            styles.push(style);
+        }
+    }
+
+    let mut diagnostics: Vec<Diagnostic> = styles
+        .into_iter()
+        .flat_map(|style| parse_style(style, &stderr))
+        .collect();
+    diagnostics.dedup();
+    diagnostics
+}
+
+/// Diagnostics as one per line, capped in number and length.
+/// `None` if the output had nothing recognizable as a syntax error.
+pub fn report(formatter: &str, stderr: &str) -> Option<String> {
+    let diagnostics = parse(formatter, stderr);
+    if diagnostics.is_empty() {
+        return None;
+    }
+
+    let mut lines: Vec<String> = diagnostics
+        .iter()
+        .take(MAX_DIAGNOSTICS)
+        .map(|diagnostic| truncate(&diagnostic.to_string(), MAX_MESSAGE_CHARS))
+        .collect();
+    if diagnostics.len() > MAX_DIAGNOSTICS {
+        lines.push(format!(
+            "... and {} more",
+            diagnostics.len() - MAX_DIAGNOSTICS
+        ));
+    }
+
+    Some(lines.join("\n"))
+}
+
+fn parse_style(style: Style, stderr: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut pending_message: Option<String> = None;
+    let mut pending_location: Option<(String, u32, u32)> = None;
+
+    for line in stderr.lines() {
+        let trimmed = line.trim();
+
+        match style {
+            Style::Rustc => {
+                // The message only counts with a location on the next line
+                let message = pending_message.take();
+                if let Some(location) = trimmed.strip_prefix("--> ") {
+                    if let (Some(message), Some((file, line, column, _))) =
+                        (message, split_location(location))
+                    {
+                        diagnostics.push(Diagnostic {
+                            file,
+                            line,
+                            column,
+                            message,
+                        });
+                    }
+                } else if trimmed.starts_with("error[internal]") {
+                    // rustfmt's own complaints, such as exceeding max_width
+                } else if let Some(message) = trimmed.strip_prefix("error") {
+                    // error: msg, or error[E0000]: msg
+                    if let Some((_, message)) = message.split_once(": ") {
+                        pending_message = Some(message.to_string());
+                    }
+                }
+            }
+            Style::Ruff => {
+                if let Some((_, rest)) = trimmed.split_once("Failed to parse ") {
+                    if let Some((file, line, column, message)) = split_location(rest) {
+                        diagnostics.push(Diagnostic {
+                            file,
+                            line,
+                            column,
+                            message,
+                        });
+                    }
+                }
+            }
+            Style::Black => {
+                if let Some(diagnostic) = parse_black(trimmed) {
+                    diagnostics.push(diagnostic);
+                }
+            }
+            Style::Go => {
+                if let Some((file, line, column, message)) = split_location(trimmed) {
+                    if !message.is_empty() {
+                        diagnostics.push(Diagnostic {
+                            file,
+                            line,
+                            column,
+                            message,
+                        });
+                    }
+                }
+            }
+            Style::Prettier => {
+                if let Some(diagnostic) = parse_prettier(trimmed) {
+                    diagnostics.push(diagnostic);
+                }
+            }
+            Style::Biome | Style::Oxc => {
+                let location = match style {
+                    // file:line:col parse ━━━━━━
+                    Style::Biome if trimmed.contains('━') => {
+                        trimmed.split_whitespace().next().and_then(split_location)
+                    }
+                    // ╭─[file:line:col]
+                    Style::Oxc => trimmed
+                        .split_once("─[")
+                        .and_then(|(_, rest)| rest.strip_suffix(']'))
+                        .and_then(split_location),
+                    _ => None,
+                };
+                let message = trimmed
+                    .strip_prefix('×')
+                    .or_else(|| trimmed.strip_prefix('✖'))
+                    .map(|message| message.trim().to_string());
+
+                if let Some((file, line, column, _)) = location {
+                    match pending_message.take() {
+                        Some(message) => diagnostics.push(Diagnostic {
+                            file,
+                            line,
+                            column,
+                            message,
+                        }),
+                        None => pending_location = Some((file, line, column)),
+                    }
+                } else if let Some(message) = message {
+                    match pending_location.take() {
+                        Some((file, line, column)) => diagnostics.push(Diagnostic {
+                            file,
+                            line,
+                            column,
+                            message,
+                        }),
+                        None => pending_message = Some(message),
+                    }
+                }
+            }
+        }
+    }
+
+    diagnostics
+}
+
+/// `error: cannot format a.py: Cannot parse for target version Python 3.12: 1:6: x = (`
+fn parse_black(line: &str) -> Option<Diagnostic> {
+    let rest = line.strip_prefix("error: cannot format ")?;
+    let (file, rest) = rest.split_once(": Cannot parse")?;
+
+    let parts: Vec<&str> = rest.split(": ").collect();
+    let position = parts
+        .iter()
+        .position(|part| parse_line_column(part).is_some())?;
+    let (line, column) = parse_line_column(parts[position])?;
+
+    let reason = format!("Cannot parse{}", parts[..position].join(": "));
+    let code = parts[position + 1..].join(": ");
+    let message = if code.is_empty() {
+        reason
+    } else {
+        format!("{}: {}", reason, code.trim())
+    };
+
+    Some(Diagnostic {
+        file: file.to_string(),
+        line,
+        column,
+        message,
+    })
+}
+
+/// `[error] a.ts: SyntaxError: ';' expected. (1:9)`
+fn parse_prettier(line: &str) -> Option<Diagnostic> {
+    let rest = line.strip_prefix("[error] ")?;
+    let (file, message) = rest.split_once(": ")?;
+    let (message, position) = message.strip_suffix(')')?.rsplit_once(" (")?;
+    let (line, column) = parse_line_column(position)?;
+
+    Some(Diagnostic {
+        file: file.to_string(),
+        line,
+        column,
+        message: message.to_string(),
+    })
+}
+
+/// `line:col`
+fn parse_line_column(s: &str) -> Option<(u32, u32)> {
+    let (line, column) = s.trim().split_once(':')?;
+    Some((line.parse().ok()?, column.parse().ok()?))
+}
+
+/// Split `file:line:col: rest` (or `file:line:col`) at the first
+/// `:line:col` position, so file names containing colons still work
+fn split_location(s: &str) -> Option<(String, u32, u32, String)> {
+    for (i, _) in s.match_indices(':').filter(|(i, _)| *i > 0) {
+        let after = &s[i + 1..];
+        let line_len = after.bytes().take_while(u8::is_ascii_digit).count();
+        if line_len == 0 || after.as_bytes().get(line_len) != Some(&b':') {
+            continue;
+        }
+
+        let after_line = &after[line_len + 1..];
+        let column_len = after_line.bytes().take_while(u8::is_ascii_digit).count();
+        let rest = &after_line[column_len..];
+        if column_len == 0 || !(rest.is_empty() || rest.starts_with(':')) {
+            continue;
+        }
+
+        let line = after[..line_len].parse().ok()?;
+        let column = after_line[..column_len].parse().ok()?;
+        let rest = rest.trim_start_matches(':').trim().to_string();
+        return Some((s[..i].to_string(), line, column, rest));
+    }
+    None
+}
+
+/// Remove ANSI color codes and terminal hyperlinks
+fn strip_ansi(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '\u{1b}' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            // CSI: parameters, then a final byte in @..~
+            Some('[') => {
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        break;
+                    }
+                }
+            }
+            // OSC: ends with BEL or ESC \
+            Some(']') => {
+                while let Some(c) = chars.next() {
+                    if c == '\u{7}' {
+                        break;
+                    }
+                    if c == '\u{1b}' && chars.peek() == Some(&'\\') {
+                        chars.next();
+                        break;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    out
+}
+
+fn truncate(s: &str, max_chars: usize) -> String {
+    match s.char_indices().nth(max_chars) {
+        Some((end, _)) => format!("{}...", &s[..end]),
+        None => s.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn first(formatter: &str, stderr: &str) -> String {
+        parse(formatter, stderr)
+            .first()
+            .map(ToString::to_string)
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn test_rustfmt() {
+        let stderr = "error: expected one of `!`, `(`, `;`, found `}`\n \
+                      --> /p/src/main.rs:3:1\n  |\n3 | }\n  | ^ unexpected token\n\n";
+        assert_eq!(
+            first("cargo fmt", stderr),
+            "/p/src/main.rs:3:1: expected one of `!`, `(`, `;`, found `}`"
+        );
+    }
+
+    #[test]
+    fn test_rustfmt_internal_errors_are_not_syntax_errors() {
+        let stderr = "error[internal]: line formatted, but exceeded maximum width (maximum: 100 (see `max_width` option), found: 104)\n \
+                      --> /p/src/main.rs:2:2:101\n  |\n2 |     let x = 1;\n  |\n\n\
+                      warning: rustfmt has failed to format. See previous 1 errors.\n\n";
+        assert!(parse("rustfmt", stderr).is_empty());
+        assert!(report("cargo fmt", stderr).is_none());
+
+        // An error without a location right after it is not a diagnostic
+        let stderr =
+            "error: could not find `Cargo.toml`\nwarning: unused\n --> /p/src/main.rs:1:1\n";
+        assert!(parse("cargo fmt", stderr).is_empty());
+    }
+
+    #[test]
+    fn test_ruff() {
+        let stderr = "error: Failed to parse a.py:1:9: Expected an expression\n";
+        assert_eq!(first("ruff", stderr), "a.py:1:9: Expected an expression");
+    }
+
+    #[test]
+    fn test_black() {
+        let stderr = "error: cannot format /p/a.py: Cannot parse for target version Python 3.12: 1:6: x = (\n\
+                      Oh no! 💥 💔 💥\n1 file failed to reformat.\n";
+        assert_eq!(
+            first("black", stderr),
+            "/p/a.py:1:6: Cannot parse for target version Python 3.12: x = ("
+        );
+        assert_eq!(
+            first("black", "error: cannot format a.py: Cannot parse: 2:0: )"),
+            "a.py:2:0: Cannot parse: )"
+        );
+    }
+
+    #[test]
+    fn test_go() {
+        let stderr = "main.go:4:2: expected '}', found 'EOF'\nmain.go:5:1: missing return\n";
+        let diagnostics = parse("goimports + gofumpt", stderr);
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(
+            diagnostics[0].to_string(),
+            "main.go:4:2: expected '}', found 'EOF'"
+        );
+    }
+
+    #[test]
+    fn test_prettier() {
+        let stderr = "[error] src/a.ts: SyntaxError: ';' expected. (1:9)\n\
+                      [error] > 1 | const x y\n[error]     |         ^\n";
+        assert_eq!(
+            parse("prettier", stderr),
+            vec![Diagnostic {
+                file: "src/a.ts".to_string(),
+                line: 1,
+                column: 9,
+                message: "SyntaxError: ';' expected.".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_biome() {
+        let stderr = "src/a.ts:1:9 parse ━━━━━━━━━━━━━━━━━━━━\n\n  \
+                      × expected `;` but instead found `y`\n\n  > 1 │ const x y\n";
+        assert_eq!(
+            first("biome", stderr),
+            "src/a.ts:1:9: expected `;` but instead found `y`"
+        );
+    }
+
+    #[test]
+    fn test_oxfmt() {
+        let stderr = "\n  × Expected a semicolon or an implicit semicolon after a statement\n   \
+                      ╭─[src/a.ts:1:9]\n 1 │ const x y\n   ·        ┬\n   ╰────\n";
+        assert_eq!(
+            first("oxfmt", stderr),
+            "src/a.ts:1:9: Expected a semicolon or an implicit semicolon after a statement"
+        );
+    }
+
+    #[test]
+    fn test_ansi_codes_are_stripped() {
+        let stderr = "\u{1b}[1m\u{1b}[31merror\u{1b}[0m: Failed to parse \
+                      \u{1b}]8;;file:///a.py\u{7}a.py\u{1b}]8;;\u{7}:1:9: Expected an expression";
+        assert_eq!(first("ruff", stderr), "a.py:1:9: Expected an expression");
+    }
+
+    #[test]
+    fn test_unknown_formatter_or_output() {
+        assert!(parse("spotless (Gradle)", "a.java:1:1: error").is_empty());
+        assert!(report("ruff", "ruff: command failed").is_none());
+    }
+
+    #[test]
+    fn test_report_is_truncated() {
+        let stderr: String = (1..=15)
+            .map(|i| format!("main.go:{}:1: {}\n", i, "x".repeat(300)))
+            .collect();
+        let report = report("gofmt", &stderr).unwrap();
+        let lines: Vec<&str> = report.lines().collect();
+        assert_eq!(lines.len(), 11);
+        assert!(lines[0].ends_with("..."));
+        assert!(lines[0].chars().count() <= MAX_MESSAGE_CHARS + 3);
+        assert_eq!(lines[10], "... and 5 more");
+    }
+
+    #[test]
+    fn test_split_location() {
+        assert_eq!(
+            split_location("C:/p/a.go:3:5: bad"),
+            Some(("C:/p/a.go".to_string(), 3, 5, "bad".to_string()))
+        );
+        assert_eq!(
+            split_location("a.rs:10:2"),
+            Some(("a.rs".to_string(), 10, 2, String::new()))
+        );
+        assert_eq!(split_location("no location here"), None);
+    }
+}
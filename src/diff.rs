@@ -0,0 +1,334 @@
+/// Diffs larger than this are summarized as changed line ranges instead
+const MAX_DIFF_BYTES: usize = 4000;
+
+/// Lines of unchanged context around each hunk
+const CONTEXT_LINES: usize = 1;
+
+/// Give up on a minimal diff after this many edits and treat the remaining
+/// region as replaced wholesale
+const MAX_EDIT_DISTANCE: usize = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Edit {
+    Equal,
+    Delete,
+    Insert,
+}
+
+/// Describe how `before` became `after`: a compact unified diff, or a list of
+/// changed line ranges when the diff would be too large to be useful.
+/// Returns `None` if the contents are identical.
+pub fn describe_changes(before: &str, after: &str, label: &str) -> Option<String> {
+    if before == after {
+        return None;
+    }
+
+    let old: Vec<&str> = before.split_inclusive('\n').collect();
+    let new: Vec<&str> = after.split_inclusive('\n').collect();
+    let edits = diff_lines(&old, &new);
+
+    let diff = unified_diff(&old, &new, &edits, label);
+    if diff.len() <= MAX_DIFF_BYTES {
+        return Some(diff);
+    }
+
+    let ranges: Vec<String> = changed_ranges(&edits)
+        .into_iter()
+        .map(|(start, end)| {
+            if start == end {
+                start.to_string()
+            } else {
+                format!("{}-{}", start, end)
+            }
+        })
+        .collect();
+
+    Some(format!(
+        "{}: {} lines now, reformatted lines {}",
+        label,
+        new.len(),
+        ranges.join(", ")
+    ))
+}
+
+/// Myers' O(ND) diff over lines, after trimming the common prefix and suffix
+fn diff_lines(old: &[&str], new: &[&str]) -> Vec<Edit> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let a = &old[prefix..old.len() - suffix];
+    let b = &new[prefix..new.len() - suffix];
+
+    let mut edits = vec![Edit::Equal; prefix];
+    edits.extend(myers(a, b));
+    edits.extend(std::iter::repeat_n(Edit::Equal, suffix));
+    edits
+}
+
+fn myers(a: &[&str], b: &[&str]) -> Vec<Edit> {
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let max = n + m;
+    if max == 0 {
+        return Vec::new();
+    }
+
+    // v[k + max] holds the furthest x reached on diagonal k. Before each
+    // step d, the diagonals -d..=d are snapshotted for the backtrack.
+    let mut v = vec![0isize; 2 * max as usize + 1];
+    let mut trace: Vec<Vec<isize>> = Vec::new();
+    let idx = |k: isize| (k + max) as usize;
+
+    'search: for d in 0..=max {
+        if d as usize > MAX_EDIT_DISTANCE {
+            // Too different to be worth a minimal diff
+            let mut edits = vec![Edit::Delete; a.len()];
+            edits.extend(std::iter::repeat_n(Edit::Insert, b.len()));
+            return edits;
+        }
+
+        trace.push(v[idx(-d)..=idx(d)].to_vec());
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && v[idx(k - 1)] < v[idx(k + 1)]) {
+                v[idx(k + 1)]
+            } else {
+                v[idx(k - 1)] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[idx(k)] = x;
+            if x >= n && y >= m {
+                break 'search;
+            }
+        }
+    }
+
+    // Walk the trace backwards to recover the edit script
+    let mut edits = Vec::new();
+    let (mut x, mut y) = (n, m);
+    for (d, snapshot) in trace.iter().enumerate().rev() {
+        let d = d as isize;
+        let k = x - y;
+        let at = |k: isize| snapshot[(k + d) as usize];
+
+        let prev_k = if k == -d || (k != d && at(k - 1) < at(k + 1)) {
+            k + 1
+        } else {
+            k - 1
+        };
+        let prev_x = if d == 0 { 0 } else { at(prev_k) };
+        let prev_y = prev_x - prev_k;
+
+        while x > prev_x && y > prev_y {
+            edits.push(Edit::Equal);
+            x -= 1;
+            y -= 1;
+        }
+        if d > 0 {
+            edits.push(if x == prev_x {
+                Edit::Insert
+            } else {
+                Edit::Delete
+            });
+        }
+        x = prev_x;
+        y = prev_y;
+    }
+
+    edits.reverse();
+    edits
+}
+
+/// Render edits as unified diff hunks with a few lines of context
+fn unified_diff(old: &[&str], new: &[&str], edits: &[Edit], label: &str) -> String {
+    let mut out = format!("--- {}\n+++ {}\n", label, label);
+
+    // Old and new line index at every edit
+    let mut positions = Vec::with_capacity(edits.len());
+    let (mut i, mut j) = (0, 0);
+    for edit in edits {
+        positions.push((i, j));
+        match edit {
+            Edit::Equal => {
+                i += 1;
+                j += 1;
+            }
+            Edit::Delete => i += 1,
+            Edit::Insert => j += 1,
+        }
+    }
+
+    let mut start = 0;
+    while start < edits.len() {
+        // Find the next change
+        let Some(first) = edits[start..].iter().position(|e| *e != Edit::Equal) else {
+            break;
+        };
+        let first = start + first;
+
+        // Extend the hunk while changes are within 2 * context of each other
+        let mut last = first;
+        let mut cursor = first;
+        while cursor < edits.len() {
+            if edits[cursor] != Edit::Equal {
+                last = cursor;
+            } else if cursor - last > 2 * CONTEXT_LINES {
+                break;
+            }
+            cursor += 1;
+        }
+
+        let hunk_start = first.saturating_sub(CONTEXT_LINES);
+        let hunk_end = (last + 1 + CONTEXT_LINES).min(edits.len());
+
+        let (old_start, new_start) = positions[hunk_start];
+        let old_count = edits[hunk_start..hunk_end]
+            .iter()
+            .filter(|e| **e != Edit::Insert)
+            .count();
+        let new_count = edits[hunk_start..hunk_end]
+            .iter()
+            .filter(|e| **e != Edit::Delete)
+            .count();
+
+        out.push_str(&format!(
+            "@@ -{} +{} @@\n",
+            hunk_range(old_start, old_count),
+            hunk_range(new_start, new_count)
+        ));
+
+        for (edit, &(i, j)) in edits[hunk_start..hunk_end]
+            .iter()
+            .zip(&positions[hunk_start..hunk_end])
+        {
+            let (marker, line) = match edit {
+                Edit::Equal => (' ', old[i]),
+                Edit::Delete => ('-', old[i]),
+                Edit::Insert => ('+', new[j]),
+            };
+            out.push(marker);
+            out.push_str(line);
+            if !line.ends_with('\n') {
+                out.push_str("\n\\ No newline at end of file\n");
+            }
+        }
+
+        start = hunk_end;
+    }
+
+    out
+}
+
+fn hunk_range(start: usize, count: usize) -> String {
+    match count {
+        0 => format!("{},0", start),
+        1 => format!("{}", start + 1),
+        _ => format!("{},{}", start + 1, count),
+    }
+}
+
+/// 1-based, inclusive line ranges of the new file that were added or changed.
+/// Lines that were only removed are reported at the line now following them.
+fn changed_ranges(edits: &[Edit]) -> Vec<(usize, usize)> {
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    let mark = |ranges: &mut Vec<(usize, usize)>, line: usize| match ranges.last_mut() {
+        Some(range) if range.1 + 1 >= line => range.1 = range.1.max(line),
+        _ => ranges.push((line, line)),
+    };
+
+    let mut line = 0;
+    let mut pending_delete = false;
+    for edit in edits {
+        match edit {
+            Edit::Equal => {
+                if pending_delete {
+                    mark(&mut ranges, line + 1);
+                    pending_delete = false;
+                }
+                line += 1;
+            }
+            Edit::Insert => {
+                line += 1;
+                mark(&mut ranges, line);
+                pending_delete = false;
+            }
+            Edit::Delete => pending_delete = true,
+        }
+    }
+    if pending_delete {
+        mark(&mut ranges, line.max(1));
+    }
+
+    ranges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identical_is_none() {
+        assert!(describe_changes("a\nb\n", "a\nb\n", "f.rs").is_none());
+    }
+
+    #[test]
+    fn test_unified_diff_single_change() {
+        let before = "a\nb\nc\nd\ne\n";
+        let after = "a\nb\nC\nd\ne\n";
+        assert_eq!(
+            describe_changes(before, after, "f.rs").unwrap(),
+            "--- f.rs\n+++ f.rs\n@@ -2,3 +2,3 @@\n b\n-c\n+C\n d\n"
+        );
+    }
+
+    #[test]
+    fn test_unified_diff_separate_hunks() {
+        let before = "1\n2\n3\n4\n5\n6\n7\n8\n";
+        let after = "one\n2\n3\n4\n5\n6\n7\neight\n";
+        assert_eq!(
+            describe_changes(before, after, "f").unwrap(),
+            "--- f\n+++ f\n@@ -1,2 +1,2 @@\n-1\n+one\n 2\n@@ -7,2 +7,2 @@\n 7\n-8\n+eight\n"
+        );
+    }
+
+    #[test]
+    fn test_unified_diff_missing_newline() {
+        let diff = describe_changes("fn main(){}", "fn main() {}\n", "f.rs").unwrap();
+        assert_eq!(
+            diff,
+            "--- f.rs\n+++ f.rs\n@@ -1 +1 @@\n-fn main(){}\n\\ No newline at end of file\n+fn main() {}\n"
+        );
+    }
+
+    #[test]
+    fn test_myers_is_minimal() {
+        let old = ["a\n", "b\n", "c\n", "a\n", "b\n", "b\n", "a\n"];
+        let new = ["c\n", "b\n", "a\n", "b\n", "a\n", "c\n"];
+        let edits = diff_lines(&old, &new);
+        let changes = edits.iter().filter(|e| **e != Edit::Equal).count();
+        assert_eq!(changes, 5);
+    }
+
+    #[test]
+    fn test_large_diff_is_summarized() {
+        let before: String = (0..500).map(|i| format!("x={}\n", i)).collect();
+        let after: String = (0..500).map(|i| format!("x = {}\n", i)).collect();
+        let summary = describe_changes(&before, &after, "big.py").unwrap();
+        assert_eq!(summary, "big.py: 500 lines now, reformatted lines 1-500");
+    }
+
+    #[test]
+    fn test_changed_ranges() {
+        let old = ["a\n", "b\n", "c\n", "d\n", "e\n"];
+        let new = ["a\n", "B\n", "c\n", "e\n", "f\n"];
+        let edits = diff_lines(&old, &new);
+        assert_eq!(changed_ranges(&edits), vec![(2, 2), (4, 5)]);
+    }
+}
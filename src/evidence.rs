@@ -0,0 +1,436 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::json::{self, Value};
+use crate::toml;
+use crate::tool::Tool;
+
+/// JavaScript formatters, in the order they win when evidence is tied
+const JAVASCRIPT_TOOLS: [Tool; 4] = [Tool::Oxfmt, Tool::Biome, Tool::Prettier, Tool::Dprint];
+
+/// npm package that provides each JavaScript formatter
+fn javascript_package(tool: Tool) -> &'static str {
+    match tool {
+        Tool::Biome => "@biomejs/biome",
+        tool => tool.binary(),
+    }
+}
+
+/// Whether `name` is a config file of the JavaScript formatter `tool`
+fn is_javascript_config(tool: Tool, name: &str) -> bool {
+    match tool {
+        Tool::Oxfmt => matches!(name, ".oxfmtrc.json" | ".oxfmtrc.jsonc"),
+        Tool::Biome => matches!(name, "biome.json" | "biome.jsonc"),
+        Tool::Prettier => {
+            name == ".prettierrc"
+                || name.starts_with(".prettierrc.")
+                || name.starts_with("prettier.config.")
+        }
+        Tool::Dprint => matches!(
+            name,
+            "dprint.json" | ".dprint.json" | "dprint.jsonc" | ".dprint.jsonc"
+        ),
+        _ => false,
+    }
+}
+
+/// JavaScript formatters the project around `file_path` says it uses,
+/// strongest evidence first: config files (and package.json's `prettier`
+/// key), then `devDependencies` and `dependencies`.
+///
+/// Directories are searched from the file upwards, stopping at the first
+/// one with any evidence or at the repository root.
+pub fn javascript_evidence(file_path: &Path) -> Vec<Tool> {
+    for dir in file_path.ancestors().skip(1) {
+        let names: Vec<String> = fs::read_dir(dir)
+            .map(|entries| {
+                entries
+                    .flatten()
+                    .map(|entry| entry.file_name().to_string_lossy().into_owned())
+                    .collect()
+            })
+            .unwrap_or_default();
+        let package = fs::read_to_string(dir.join("package.json"))
+            .ok()
+            .and_then(|content| json::parse(&content));
+
+        let configured = JAVASCRIPT_TOOLS.into_iter().filter(|tool| {
+            names.iter().any(|name| is_javascript_config(*tool, name))
+                || (*tool == Tool::Prettier
+                    && package
+                        .as_ref()
+                        .is_some_and(|p| p.get("prettier").is_some()))
+        });
+        let depended_on = JAVASCRIPT_TOOLS.into_iter().filter(|tool| {
+            package
+                .as_ref()
+                .is_some_and(|p| depends_on(p, javascript_package(*tool)))
+        });
+
+        let mut tools: Vec<Tool> = Vec::new();
+        for tool in configured.chain(depended_on) {
+            if !tools.contains(&tool) {
+                tools.push(tool);
+            }
+        }
+        if !tools.is_empty() || dir.join(".git").exists() {
+            return tools;
+        }
+    }
+
+    Vec::new()
+}
+
+/// Whether a package.json lists `name` in `devDependencies` or
+/// `dependencies`
+fn depends_on(package: &Value, name: &str) -> bool {
+    ["devDependencies", "dependencies"].iter().any(|section| {
+        package
+            .get(section)
+            .and_then(|deps| deps.get(name))
+            .is_some()
+    })
+}
+
+/// The `tools` some package.json in `package_dirs` depends on, in order
+pub fn declared_dependencies(package_dirs: &[PathBuf], tools: &[Tool]) -> Vec<Tool> {
+    let packages: Vec<Value> = package_dirs
+        .iter()
+        .filter_map(|dir| fs::read_to_string(dir.join("package.json")).ok())
+        .filter_map(|content| json::parse(&content))
+        .collect();
+
+    tools
+        .iter()
+        .copied()
+        .filter(|tool| {
+            packages
+                .iter()
+                .any(|package| depends_on(package, javascript_package(*tool)))
+        })
+        .collect()
+}
+
+/// Python formatters, in the order they win when evidence is tied
+const PYTHON_TOOLS: [Tool; 4] = [Tool::Ruff, Tool::Black, Tool::Autopep8, Tool::Yapf];
+
+/// How the files in a directory configure a Python tool
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PythonConfig {
+    /// Settings for its formatter
+    Formatter,
+    /// Ruff settings with no `format` section, which may only be for its
+    /// linter
+    LintOnly,
+}
+
+/// How the files in `dir` configure the Python formatter `tool`: its
+/// `[tool.*]` table in pyproject.toml, ruff.toml or `.ruff.toml` for Ruff,
+/// and `.style.yapf` or setup.cfg's `[yapf]` section for YAPF
+fn python_config(tool: Tool, dir: &Path, pyproject: Option<&Value>) -> Option<PythonConfig> {
+    let table = pyproject
+        .and_then(|p| p.get("tool"))
+        .and_then(|t| t.get(tool.binary()));
+
+    match tool {
+        Tool::Ruff => {
+            let ruff_toml = [".ruff.toml", "ruff.toml"]
+                .iter()
+                .map(|name| dir.join(name))
+                .find(|path| path.exists())
+                .map(|path| {
+                    fs::read_to_string(path)
+                        .ok()
+                        .and_then(|content| toml::parse(&content).ok())
+                        .unwrap_or(Value::Object(Vec::new()))
+                });
+            let settings = ruff_toml.as_ref().or(table)?;
+            Some(if settings.get("format").is_some() {
+                PythonConfig::Formatter
+            } else {
+                PythonConfig::LintOnly
+            })
+        }
+        Tool::Yapf => {
+            let configured = table.is_some()
+                || dir.join(".style.yapf").exists()
+                || fs::read_to_string(dir.join("setup.cfg"))
+                    .is_ok_and(|content| content.lines().any(|l| l.trim() == "[yapf]"));
+            configured.then_some(PythonConfig::Formatter)
+        }
+        _ => table.map(|_| PythonConfig::Formatter),
+    }
+}
+
+/// Python formatters the project around `file_path` says it uses,
+/// strongest evidence first: formatter config files, lint-only Ruff
+/// config, then the development dependencies pyproject.toml declares.
+///
+/// Directories are searched like [`javascript_evidence`] does.
+pub fn python_evidence(file_path: &Path) -> Vec<Tool> {
+    for dir in file_path.ancestors().skip(1) {
+        let pyproject = fs::read_to_string(dir.join("pyproject.toml"))
+            .ok()
+            .and_then(|content| toml::parse(&content).ok());
+        let requirements = pyproject.as_ref().map(dev_requirements).unwrap_or_default();
+
+        // A lint-only Ruff config says less than another formatter's config
+        let configs: Vec<(Tool, PythonConfig)> = PYTHON_TOOLS
+            .into_iter()
+            .filter_map(|tool| Some((tool, python_config(tool, dir, pyproject.as_ref())?)))
+            .collect();
+        let configured: Vec<Tool> = [PythonConfig::Formatter, PythonConfig::LintOnly]
+            .iter()
+            .flat_map(|kind| {
+                configs
+                    .iter()
+                    .filter(move |(_, config)| config == kind)
+                    .map(|(tool, _)| *tool)
+            })
+            .collect();
+        let depended_on: Vec<Tool> = PYTHON_TOOLS
+            .into_iter()
+            .filter(|tool| requirements.iter().any(|name| name == tool.binary()))
+            .collect();
+
+        let tools = ranked(&configured, &depended_on);
+        if !tools.is_empty() || dir.join(".git").exists() {
+            return tools;
+        }
+    }
+
+    Vec::new()
+}
+
+/// Normalized names of the development requirements in a pyproject.toml:
+/// optional dependencies, dependency groups, and the dev dependencies of
+/// Poetry, PDM and uv
+fn dev_requirements(pyproject: &Value) -> Vec<String> {
+    let at = |path: &[&str]| path.iter().try_fold(pyproject, |value, key| value.get(key));
+    let mut names = Vec::new();
+
+    // Lists of requirement strings, keyed by group
+    let grouped = [
+        at(&["project", "optional-dependencies"]),
+        at(&["dependency-groups"]),
+        at(&["tool", "pdm", "dev-dependencies"]),
+    ];
+    for groups in grouped.into_iter().flatten() {
+        if let Value::Object(groups) = groups {
+            for (_, requirements) in groups {
+                names.extend(requirement_names(requirements));
+            }
+        }
+    }
+    if let Some(requirements) = at(&["tool", "uv", "dev-dependencies"]) {
+        names.extend(requirement_names(requirements));
+    }
+
+    // Poetry's tables keyed by package name
+    let poetry_groups: Vec<&Value> = match at(&["tool", "poetry", "group"]) {
+        Some(Value::Object(groups)) => groups
+            .iter()
+            .filter_map(|(_, group)| group.get("dependencies"))
+            .collect(),
+        _ => Vec::new(),
+    };
+    for dependencies in at(&["tool", "poetry", "dev-dependencies"])
+        .into_iter()
+        .chain(poetry_groups)
+    {
+        if let Value::Object(dependencies) = dependencies {
+            names.extend(dependencies.iter().map(|(name, _)| normalize_package(name)));
+        }
+    }
+
+    names
+}
+
+/// Package names of an array of requirement strings like `black>=24`
+fn requirement_names(requirements: &Value) -> Vec<String> {
+    let Value::Array(items) = requirements else {
+        return Vec::new();
+    };
+    items
+        .iter()
+        .filter_map(Value::as_str)
+        .map(|requirement| {
+            let end = requirement
+                .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.')))
+                .unwrap_or(requirement.len());
+            normalize_package(&requirement[..end])
+        })
+        .collect()
+}
+
+/// A Python package name compared the way pip does: lowercase, with runs
+/// of `-`, `_` and `.` as one `-`
+pub fn normalize_package(name: &str) -> String {
+    let mut normalized = String::with_capacity(name.len());
+    for c in name.trim().chars() {
+        if matches!(c, '-' | '_' | '.') {
+            if !normalized.ends_with('-') {
+                normalized.push('-');
+            }
+        } else {
+            normalized.push(c.to_ascii_lowercase());
+        }
+    }
+    normalized
+}
+
+/// `evidence` first, then the rest of `defaults`, without repeats
+pub fn ranked(evidence: &[Tool], defaults: &[Tool]) -> Vec<Tool> {
+    let mut tools: Vec<Tool> = Vec::new();
+    for tool in evidence.iter().chain(defaults) {
+        if !tools.contains(tool) {
+            tools.push(*tool);
+        }
+    }
+    tools
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn setup(files: &[(&str, &str)]) -> TempDir {
+        let temp_dir = TempDir::new().unwrap();
+        fs::create_dir(temp_dir.path().join(".git")).unwrap();
+        for (path, content) in files {
+            let path = temp_dir.path().join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+        temp_dir
+    }
+
+    #[test]
+    fn test_config_files_beat_dependencies() {
+        let temp_dir = setup(&[
+            (
+                "package.json",
+                r#"{"devDependencies": {"oxfmt": "1", "prettier": "3"}}"#,
+            ),
+            ("biome.jsonc", "{}"),
+        ]);
+        assert_eq!(
+            javascript_evidence(&temp_dir.path().join("src/a.ts")),
+            vec![Tool::Biome, Tool::Oxfmt, Tool::Prettier]
+        );
+
+        let temp_dir = setup(&[
+            ("package.json", r#"{"prettier": {"semi": false}}"#),
+            ("prettier.config.mjs", ""),
+        ]);
+        assert_eq!(
+            javascript_evidence(&temp_dir.path().join("a.js")),
+            vec![Tool::Prettier]
+        );
+    }
+
+    #[test]
+    fn test_nearest_directory_with_evidence_wins() {
+        let temp_dir = setup(&[
+            (
+                "package.json",
+                r#"{"devDependencies": {"@biomejs/biome": "1"}}"#,
+            ),
+            ("packages/a/package.json", r#"{"name": "a"}"#),
+            ("packages/b/package.json", r#"{"name": "b"}"#),
+            ("packages/b/dprint.json", "{}"),
+        ]);
+        let root = temp_dir.path();
+        assert_eq!(
+            javascript_evidence(&root.join("packages/a/index.js")),
+            vec![Tool::Biome]
+        );
+        assert_eq!(
+            javascript_evidence(&root.join("packages/b/index.js")),
+            vec![Tool::Dprint]
+        );
+        assert_eq!(
+            ranked(&[Tool::Dprint], &[Tool::Oxfmt, Tool::Dprint]),
+            vec![Tool::Dprint, Tool::Oxfmt]
+        );
+    }
+
+    #[test]
+    fn test_python_config_beats_dependencies() {
+        let temp_dir = setup(&[(
+            "pyproject.toml",
+            "[project]\nname = \"a\"\n\n[dependency-groups]\ndev = [\"Ruff>=0.5\", {include-group = \"lint\"}]\nlint = [\"yapf\"]\n\n[[tool.mypy.overrides]]\nmodule = \"a\"\n\n[tool.black]\nline-length = 100\n",
+        )]);
+        assert_eq!(
+            python_evidence(&temp_dir.path().join("src/a.py")),
+            vec![Tool::Black, Tool::Ruff, Tool::Yapf]
+        );
+
+        let temp_dir = setup(&[
+            (
+                "setup.cfg",
+                "[metadata]\nname = a\n\n[yapf]\nbased_on_style = pep8\n",
+            ),
+            (".ruff.toml", "[format]\nquote-style = \"single\"\n"),
+        ]);
+        assert_eq!(
+            python_evidence(&temp_dir.path().join("a.py")),
+            vec![Tool::Ruff, Tool::Yapf]
+        );
+    }
+
+    #[test]
+    fn test_lint_only_ruff_loses_to_black() {
+        let temp_dir = setup(&[(
+            "pyproject.toml",
+            "[tool.ruff]\nselect = [\"E\", \"F\"]\n\n[tool.black]\nline-length = 100\n",
+        )]);
+        assert_eq!(
+            python_evidence(&temp_dir.path().join("a.py")),
+            vec![Tool::Black, Tool::Ruff]
+        );
+
+        let temp_dir = setup(&[(
+            "pyproject.toml",
+            "[tool.ruff.format]\nquote-style = \"single\"\n\n[tool.black]\n",
+        )]);
+        assert_eq!(
+            python_evidence(&temp_dir.path().join("a.py")),
+            vec![Tool::Ruff, Tool::Black]
+        );
+
+        let temp_dir = setup(&[
+            ("ruff.toml", "[lint]\nselect = [\"E\"]\n"),
+            ("pyproject.toml", "[tool.black]\n"),
+        ]);
+        assert_eq!(
+            python_evidence(&temp_dir.path().join("a.py")),
+            vec![Tool::Black, Tool::Ruff]
+        );
+    }
+
+    #[test]
+    fn test_python_dev_dependencies() {
+        let temp_dir = setup(&[(
+            "pyproject.toml",
+            "[tool.poetry.group.dev.dependencies]\nAutoPEP8 = \"^2\"\n",
+        )]);
+        assert_eq!(
+            python_evidence(&temp_dir.path().join("a.py")),
+            vec![Tool::Autopep8]
+        );
+
+        let temp_dir = setup(&[
+            (
+                "pyproject.toml",
+                "[tool.uv]\ndev-dependencies = [\"black[jupyter]==24.1\"]\n",
+            ),
+            ("pkg/pyproject.toml", "[project]\nname = \"pkg\"\n"),
+        ]);
+        assert_eq!(
+            python_evidence(&temp_dir.path().join("pkg/a.py")),
+            vec![Tool::Black]
+        );
+    }
+}
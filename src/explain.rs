@@ -0,0 +1,458 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::config::Config;
+use crate::format::{Plan, plan_with_env};
+use crate::json::Value;
+use crate::package_json::SAFE_MODE;
+use crate::project::{
+    env_var, find_cargo_root, find_deno_root, find_go_root, find_java_root, find_node_root,
+    find_project_root, find_python_root,
+};
+use crate::tool::{Formatter, Step, Tool};
+
+/// One file per language probed by `doctor`
+const DOCTOR_EXTENSIONS: [&str; 17] = [
+    "ts", "rs", "py", "java", "go", "json", "yaml", "toml", "html", "vue", "css", "scss", "less",
+    "md", "mdx", "graphql", "hbs",
+];
+
+/// Why a candidate formatter would or would not run
+#[derive(Debug, Clone, PartialEq)]
+pub enum Status {
+    /// The first installed candidate, used for the file
+    Selected,
+    /// Installed, used if an earlier candidate fails and lets it try
+    Available,
+    /// Not installed
+    Missing(String),
+    /// Excluded by --project-only
+    ProjectOnly,
+}
+
+/// A formatter that could format the file
+#[derive(Debug)]
+pub struct Candidate {
+    pub name: String,
+    pub status: Status,
+    /// Programs that would run, for installed candidates
+    pub programs: Vec<PathBuf>,
+    pub version: Option<String>,
+}
+
+/// How a file's formatter is chosen
+#[derive(Debug)]
+pub struct Report {
+    pub file: PathBuf,
+    pub language: Option<String>,
+    pub project_root: Option<PathBuf>,
+    /// Config file that adjusted resolution
+    pub config: Option<PathBuf>,
+    /// Why no formatter applies at all, e.g. an unsupported extension
+    pub note: Option<String>,
+    pub candidates: Vec<Candidate>,
+}
+
+/// Tool versions, looked up once per program
+#[derive(Default)]
+pub struct Versions(HashMap<PathBuf, Option<String>>);
+
+impl Versions {
+    fn of(&mut self, step: &Step) -> Option<String> {
+        let program = step.locate().ok()?;
+        self.0
+            .entry(program)
+            .or_insert_with(|| step.version())
+            .clone()
+    }
+}
+
+/// Walk through formatter resolution for `file_path`
+pub fn explain(file_path: &Path, project_only: bool, versions: &mut Versions) -> Report {
+    explain_with_env(file_path, project_only, versions, &env_var)
+}
+
+/// [`explain`] with environment variables read through `var`
+fn explain_with_env(
+    file_path: &Path,
+    project_only: bool,
+    versions: &mut Versions,
+    var: &dyn Fn(&str) -> Option<PathBuf>,
+) -> Report {
+    let mut report = Report {
+        file: file_path.to_path_buf(),
+        language: None,
+        project_root: None,
+        config: Config::find(file_path).ok().flatten().map(|c| c.path),
+        note: None,
+        candidates: Vec::new(),
+    };
+
+    // Notebook cells go through the Python chain
+    let (language, chain, full_chain) = match plan_with_env(file_path, project_only, var) {
+        Plan::Chain(language, chain) => match plan_with_env(file_path, false, var) {
+            Plan::Chain(_, full_chain) => (language, chain, full_chain),
+            Plan::Done(result) => {
+                report.note = Some(result.message);
+                return report;
+            }
+            _ => (language, chain, Vec::new()),
+        },
+        // Cells are skipped when Python is disabled, ignored or protected
+        Plan::Notebook => {
+            let cell = file_path.with_extension("py");
+            match (
+                plan_with_env(&cell, project_only, var),
+                plan_with_env(&cell, false, var),
+            ) {
+                (Plan::Chain(_, chain), Plan::Chain(_, full_chain)) => {
+                    ("Jupyter Notebook".to_string(), chain, full_chain)
+                }
+                (Plan::Done(result), _) | (_, Plan::Done(result)) => {
+                    report.note = Some(result.message);
+                    return report;
+                }
+                _ => ("Jupyter Notebook".to_string(), Vec::new(), Vec::new()),
+            }
+        }
+        Plan::PackageJson(note) => {
+            report.project_root = find_node_root(file_path);
+            report.language = Some("package.json".to_string());
+            if let Some(note) = note {
+                report.candidates.push(Candidate {
+                    name: Tool::SortPackageJson.name().to_string(),
+                    status: Status::Missing(note),
+                    programs: Vec::new(),
+                    version: None,
+                });
+            }
+            report.candidates.push(Candidate {
+                name: SAFE_MODE.to_string(),
+                status: Status::Selected,
+                programs: Vec::new(),
+                version: None,
+            });
+            return report;
+        }
+        Plan::Done(result) => {
+            report.note = Some(result.message);
+            return report;
+        }
+    };
+
+    report.project_root = project_root(&language, file_path);
+    report.language = Some(language.clone());
+
+    let mut selected = false;
+    for formatter in &chain {
+        let located: Result<Vec<PathBuf>, String> =
+            formatter.steps.iter().map(Step::locate).collect();
+
+        let candidate = match located {
+            Ok(programs) => {
+                let status = if selected {
+                    Status::Available
+                } else {
+                    selected = true;
+                    Status::Selected
+                };
+                let versions: Vec<String> = formatter
+                    .steps
+                    .iter()
+                    .filter_map(|step| versions.of(step))
+                    .collect();
+                Candidate {
+                    name: formatter.name.clone(),
+                    status,
+                    programs,
+                    version: (!versions.is_empty()).then(|| versions.join(" + ")),
+                }
+            }
+            Err(reason) => candidate(formatter, Status::Missing(reason)),
+        };
+        report.candidates.push(candidate);
+    }
+
+    // Global tools --project-only left out
+    for formatter in full_chain.iter().filter(|f| !chain.contains(f)) {
+        report
+            .candidates
+            .push(candidate(formatter, Status::ProjectOnly));
+    }
+
+    if report.candidates.is_empty() {
+        report.note = Some(format!("No {} formatters to try", language));
+    }
+
+    report
+}
+
+/// Resolution for every supported language, as seen from `dir`
+pub fn doctor(dir: &Path, project_only: bool) -> Vec<Report> {
+    let mut versions = Versions::default();
+    DOCTOR_EXTENSIONS
+        .iter()
+        .map(|ext| {
+            let probe = dir.join(format!("ralph-hook-fmt-doctor.{}", ext));
+            explain(&probe, project_only, &mut versions)
+        })
+        .collect()
+}
+
+fn candidate(formatter: &Formatter, status: Status) -> Candidate {
+    Candidate {
+        name: formatter.name.clone(),
+        status,
+        programs: Vec::new(),
+        version: None,
+    }
+}
+
+/// Root the language's formatters are resolved from
+fn project_root(language: &str, file_path: &Path) -> Option<PathBuf> {
+    match language {
+        "JavaScript/TypeScript" => find_node_root(file_path).or_else(|| find_deno_root(file_path)),
+        "Rust" => find_cargo_root(file_path),
+        "Python" | "Jupyter Notebook" => find_python_root(file_path),
+        "Java" => find_java_root(file_path),
+        "Go" => find_go_root(file_path),
+        _ => find_project_root(file_path),
+    }
+}
+
+impl Status {
+    fn describe(&self) -> String {
+        match self {
+            Status::Selected => "selected".to_string(),
+            Status::Available => "available".to_string(),
+            Status::Missing(reason) => format!("skipped: {}", reason),
+            Status::ProjectOnly => "skipped: global tool with --project-only".to_string(),
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Status::Selected => "selected",
+            Status::Available => "available",
+            Status::Missing(_) => "missing",
+            Status::ProjectOnly => "project-only",
+        }
+    }
+}
+
+impl Candidate {
+    fn to_json(&self) -> Value {
+        let mut entries = vec![
+            ("name".to_string(), Value::String(self.name.clone())),
+            (
+                "status".to_string(),
+                Value::String(self.status.as_str().to_string()),
+            ),
+        ];
+        if let Status::Missing(reason) = &self.status {
+            entries.push(("reason".to_string(), Value::String(reason.clone())));
+        }
+        entries.push((
+            "programs".to_string(),
+            Value::Array(self.programs.iter().map(|p| path_value(p)).collect()),
+        ));
+        entries.push((
+            "version".to_string(),
+            self.version.clone().map_or(Value::Null, Value::String),
+        ));
+        Value::Object(entries)
+    }
+
+    fn describe(&self) -> String {
+        format!(
+            "{}: {}{}",
+            self.name,
+            self.status.describe(),
+            self.details()
+        )
+    }
+
+    /// Programs and version, if installed
+    fn details(&self) -> String {
+        let mut line = String::new();
+        if !self.programs.is_empty() {
+            let programs: Vec<String> = self
+                .programs
+                .iter()
+                .map(|p| p.display().to_string())
+                .collect();
+            line.push_str(&format!(" ({})", programs.join(", ")));
+        }
+        if let Some(version) = &self.version {
+            line.push_str(&format!(", {}", version));
+        }
+        line
+    }
+}
+
+impl Report {
+    pub fn to_json(&self) -> Value {
+        let optional = |value: Option<Value>| value.unwrap_or(Value::Null);
+        Value::Object(vec![
+            ("file".to_string(), path_value(&self.file)),
+            (
+                "language".to_string(),
+                optional(self.language.clone().map(Value::String)),
+            ),
+            (
+                "projectRoot".to_string(),
+                optional(self.project_root.as_deref().map(path_value)),
+            ),
+            (
+                "config".to_string(),
+                optional(self.config.as_deref().map(path_value)),
+            ),
+            (
+                "note".to_string(),
+                optional(self.note.clone().map(Value::String)),
+            ),
+            (
+                "candidates".to_string(),
+                Value::Array(self.candidates.iter().map(Candidate::to_json).collect()),
+            ),
+        ])
+    }
+
+    /// Full explanation, for `explain`
+    pub fn to_text(&self) -> String {
+        let mut lines = vec![format!("File:         {}", self.file.display())];
+        if let Some(language) = &self.language {
+            lines.push(format!("Language:     {}", language));
+            lines.push(format!(
+                "Project root: {}",
+                self.project_root
+                    .as_ref()
+                    .map_or("none".to_string(), |root| root.display().to_string())
+            ));
+        }
+        if let Some(config) = &self.config {
+            lines.push(format!("Config:       {}", config.display()));
+        }
+        if let Some(note) = &self.note {
+            lines.push(format!("Not formatted: {}", note));
+        }
+        if !self.candidates.is_empty() {
+            lines.push("Formatters, in priority order:".to_string());
+            for (i, candidate) in self.candidates.iter().enumerate() {
+                lines.push(format!("  {}. {}", i + 1, candidate.describe()));
+            }
+        }
+        lines.join("\n")
+    }
+
+    /// One language's summary, for `doctor`
+    pub fn to_summary(&self) -> String {
+        let language = self.language.as_deref().unwrap_or("unknown");
+        let root = self
+            .project_root
+            .as_ref()
+            .map_or("none".to_string(), |root| root.display().to_string());
+        let formatter = self
+            .candidates
+            .iter()
+            .find(|c| c.status == Status::Selected)
+            .map(|c| format!("{}{}", c.name, c.details()))
+            .or_else(|| self.note.clone())
+            .unwrap_or_else(|| "none installed".to_string());
+
+        format!(
+            "{}\n  project root: {}\n  formatter:    {}",
+            language, root, formatter
+        )
+    }
+}
+
+fn path_value(path: &Path) -> Value {
+    Value::String(path.to_string_lossy().into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_unsupported_file_has_note() {
+        let report = explain(Path::new("/p/a.xyz"), false, &mut Versions::default());
+        assert_eq!(
+            report.note.as_deref(),
+            Some("Unsupported file extension: xyz")
+        );
+        assert!(report.candidates.is_empty());
+    }
+
+    #[test]
+    fn test_missing_local_and_project_only() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("package.json"), "{}").unwrap();
+        let file = temp_dir.path().join("a.ts");
+
+        let report = explain(&file, true, &mut Versions::default());
+        assert_eq!(report.language.as_deref(), Some("JavaScript/TypeScript"));
+        assert_eq!(report.project_root.as_deref(), Some(temp_dir.path()));
+
+        let statuses: Vec<(&str, &Status)> = report
+            .candidates
+            .iter()
+            .map(|c| (c.name.as_str(), &c.status))
+            .collect();
+        let oxfmt = temp_dir.path().join("node_modules/.bin/oxfmt");
+        assert_eq!(
+            statuses[0],
+            (
+                "oxfmt",
+                &Status::Missing(format!("{} does not exist", oxfmt.display()))
+            )
+        );
+        assert_eq!(statuses[3], ("oxfmt", &Status::ProjectOnly));
+        assert_eq!(statuses[4], ("dprint", &Status::ProjectOnly));
+        assert_eq!(statuses.len(), 5);
+    }
+
+    #[test]
+    fn test_notebook_uses_python_chain() {
+        // An activated virtualenv would add candidates outside the project
+        let report = explain_with_env(
+            Path::new("/p/a.ipynb"),
+            true,
+            &mut Versions::default(),
+            &|_| None,
+        );
+        assert_eq!(report.language.as_deref(), Some("Jupyter Notebook"));
+        assert!(
+            report
+                .candidates
+                .iter()
+                .all(|c| c.status == Status::ProjectOnly)
+        );
+    }
+
+    #[test]
+    fn test_notebook_with_python_disabled() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join(".ralph-hook-fmt.toml"),
+            "[languages.python]\nenabled = false\n",
+        )
+        .unwrap();
+
+        let file = temp_dir.path().join("a.ipynb");
+        let report = explain_with_env(&file, false, &mut Versions::default(), &|_| None);
+        assert!(report.note.is_some());
+        assert!(report.candidates.is_empty());
+    }
+
+    #[test]
+    fn test_json_shape() {
+        let report = explain(Path::new("/p/a.xyz"), false, &mut Versions::default());
+        assert_eq!(
+            report.to_json().to_string(),
+            r#"{"file":"/p/a.xyz","language":null,"projectRoot":null,"config":null,"note":"Unsupported file extension: xyz","candidates":[]}"#
+        );
+    }
+}
@@ -1,10 +1,23 @@
-use std::path::Path;
-use std::process::Command;
+use std::fs;
+use std::path::{Path, PathBuf};
 
+use crate::config::{Choice, Config, Rules};
+use crate::diff::describe_changes;
+use crate::evidence::{declared_dependencies, javascript_evidence, python_evidence, ranked};
+use crate::ignore::{content_skip_reason, protected_reason, skip_reason};
+use crate::notebook::format_notebook;
+use crate::package_json::{SAFE_MODE, format_package_json, reindent};
 use crate::project::{
-    find_cargo_root, find_go_root, find_java_root, find_node_root, find_project_root,
+    PackageManager, env_var, find_cargo_root, find_deno_root, find_go_root, find_java_root,
+    find_node_package_dirs, find_node_root, find_pnp_root, find_project_root, find_python_envs,
     find_python_root,
 };
+use crate::tool::{Failure, Formatter, Tool};
+
+/// Extensions `deno fmt` formats in a Deno project
+pub const DENO_EXTENSIONS: [&str; 11] = [
+    "js", "jsx", "ts", "tsx", "mjs", "cjs", "json", "jsonc", "md", "markdown", "css",
+];
 
 /// Result of a formatting operation
 #[derive(Debug)]
@@ -13,6 +26,12 @@ pub struct FormatResult {
     pub formatted: bool,
     pub formatter: Option<String>,
     pub message: String,
+    /// Diff (or summary of changed lines) of what formatting rewrote
+    pub changes: Option<String>,
+    /// Formatted text, when formatting content rather than a file
+    pub output: Option<String>,
+    /// What the formatter printed when it failed
+    pub stderr: Option<String>,
 }
 
 impl FormatResult {
@@ -21,6 +40,9 @@ impl FormatResult {
             formatted: true,
             formatter: Some(formatter.to_string()),
             message: format!("Formatted with {}", formatter),
+            changes: None,
+            output: None,
+            stderr: None,
         }
     }
 
@@ -29,6 +51,9 @@ impl FormatResult {
             formatted: false,
             formatter: None,
             message: format!("No formatter found for {}", language),
+            changes: None,
+            output: None,
+            stderr: None,
         }
     }
 
@@ -37,6 +62,47 @@ impl FormatResult {
             formatted: false,
             formatter: None,
             message: format!("Unsupported file extension: {}", ext),
+            changes: None,
+            output: None,
+            stderr: None,
+        }
+    }
+
+    pub fn skipped(reason: &str) -> Self {
+        Self {
+            formatted: false,
+            formatter: None,
+            message: format!("Skipped: {}", reason),
+            changes: None,
+            output: None,
+            stderr: None,
+        }
+    }
+
+    pub fn protected(file_path: &Path, reason: &str) -> Self {
+        let name = file_path.file_name().unwrap_or_default().to_string_lossy();
+        Self {
+            formatted: false,
+            formatter: None,
+            message: format!("Skipped {}: {}", name, reason),
+            changes: None,
+            output: None,
+            stderr: None,
+        }
+    }
+
+    pub fn disabled(language: &str, config_path: &Path) -> Self {
+        Self {
+            formatted: false,
+            formatter: None,
+            message: format!(
+                "Formatting {} is disabled in {}",
+                language,
+                config_path.display()
+            ),
+            changes: None,
+            output: None,
+            stderr: None,
         }
     }
 
@@ -45,375 +111,820 @@ impl FormatResult {
             formatted: false,
             formatter: Some(formatter.to_string()),
             message: format!("{} error: {}", formatter, error),
+            changes: None,
+            output: None,
+            stderr: Some(error.to_string()),
         }
     }
 }
 
-/// Format a file based on its extension
+/// Format a file based on its extension, recording what the formatter changed
 pub fn format_file(file_path: &Path, project_only: bool) -> FormatResult {
-    // Skip package.json - formatting can reorder keys and break package managers
-    if let Some(name) = file_path.file_name().and_then(|n| n.to_str()) {
-        if name == "package.json" {
-            return FormatResult {
-                formatted: false,
-                formatter: None,
-                message: "Skipped package.json".to_string(),
-            };
-        }
-    }
+    let before = fs::read_to_string(file_path).ok();
 
-    let ext = file_path.extension().and_then(|e| e.to_str()).unwrap_or("");
+    let mut result = match plan(file_path, project_only) {
+        Plan::Chain(language, formatters) => run_chain(&language, &formatters, file_path),
+        Plan::Notebook => format_notebook(file_path, project_only),
+        Plan::PackageJson(note) => with_note(format_package_json(file_path), note),
+        Plan::Done(result) => result,
+    };
 
-    match ext {
-        "js" | "jsx" | "ts" | "tsx" | "mjs" | "cjs" => format_javascript(file_path, project_only),
-        "rs" => format_rust(file_path, project_only),
-        "py" | "pyi" => format_python(file_path, project_only),
-        "java" => format_java(file_path, project_only),
-        "go" => format_go(file_path, project_only),
-        // oxfmt-supported formats
-        "json" | "jsonc" | "json5" => format_with_oxfmt(file_path, "JSON", project_only),
-        "yaml" | "yml" => format_with_oxfmt(file_path, "YAML", project_only),
-        "toml" => format_with_oxfmt(file_path, "TOML", project_only),
-        "html" | "htm" => format_with_oxfmt(file_path, "HTML", project_only),
-        "vue" => format_with_oxfmt(file_path, "Vue", project_only),
-        "css" => format_with_oxfmt(file_path, "CSS", project_only),
-        "scss" => format_with_oxfmt(file_path, "SCSS", project_only),
-        "less" => format_with_oxfmt(file_path, "Less", project_only),
-        "md" | "markdown" => format_with_oxfmt(file_path, "Markdown", project_only),
-        "mdx" => format_with_oxfmt(file_path, "MDX", project_only),
-        "graphql" | "gql" => format_with_oxfmt(file_path, "GraphQL", project_only),
-        "hbs" | "handlebars" => format_with_oxfmt(file_path, "Handlebars", project_only),
-        _ => FormatResult::unsupported(ext),
+    if result.formatted {
+        if let (Some(before), Ok(after)) = (before, fs::read_to_string(file_path)) {
+            let label = file_path.display().to_string();
+            result.changes = describe_changes(&before, &after, &label);
+        }
     }
+
+    result
 }
 
-/// Format JavaScript/TypeScript files
-fn format_javascript(file_path: &Path, project_only: bool) -> FormatResult {
-    let project_root = find_node_root(file_path);
+/// Format a batch of files, running each formatter once over every file it
+/// applies to. Results are returned in the order the files were given.
+pub fn format_files(files: &[PathBuf], project_only: bool) -> Vec<FormatResult> {
+    let before: Vec<Option<String>> = files.iter().map(|f| fs::read_to_string(f).ok()).collect();
 
-    // Try local formatters first (in priority order)
-    if let Some(ref root) = project_root {
-        // Try oxfmt first (fastest)
-        let oxfmt_path = root.join("node_modules/.bin/oxfmt");
-        if oxfmt_path.exists() {
-            return run_formatter("oxfmt", &oxfmt_path, &["--write"], file_path, None);
-        }
+    let mut results: Vec<Option<FormatResult>> = files.iter().map(|_| None).collect();
+    // Files sharing a formatter chain, by index into `files`
+    let mut groups: Vec<(String, Vec<Formatter>, Vec<usize>)> = Vec::new();
 
-        // Try biome
-        let biome_path = root.join("node_modules/.bin/biome");
-        if biome_path.exists() {
-            return run_formatter(
-                "biome",
-                &biome_path,
-                &["format", "--write"],
-                file_path,
-                None,
-            );
+    for (index, file_path) in files.iter().enumerate() {
+        match plan(file_path, project_only) {
+            Plan::Chain(language, formatters) => {
+                match groups.iter_mut().find(|(_, chain, _)| *chain == formatters) {
+                    Some((_, _, members)) => members.push(index),
+                    None => groups.push((language, formatters, vec![index])),
+                }
+            }
+            Plan::Notebook => results[index] = Some(format_notebook(file_path, project_only)),
+            Plan::PackageJson(note) => {
+                results[index] = Some(with_note(format_package_json(file_path), note))
+            }
+            Plan::Done(result) => results[index] = Some(result),
         }
+    }
 
-        // Try prettier
-        let prettier_path = root.join("node_modules/.bin/prettier");
-        if prettier_path.exists() {
-            return run_formatter("prettier", &prettier_path, &["--write"], file_path, None);
+    for (language, formatters, members) in groups {
+        let group: Vec<&Path> = members.iter().map(|&i| files[i].as_path()).collect();
+        let group_results = run_chain_batch(&language, &formatters, &group);
+        for (index, result) in members.into_iter().zip(group_results) {
+            results[index] = Some(result);
         }
     }
 
-    if !project_only {
-        // Fall back to global formatters
-        if command_exists("oxfmt") {
-            return run_formatter_cmd("oxfmt", &["--write"], file_path, None);
+    results
+        .into_iter()
+        .zip(files.iter().zip(before))
+        .map(|(result, (file_path, before))| {
+            let mut result = result.expect("every file has a result");
+            if result.formatted {
+                if let (Some(before), Ok(after)) = (before, fs::read_to_string(file_path)) {
+                    let label = file_path.display().to_string();
+                    result.changes = describe_changes(&before, &after, &label);
+                }
+            }
+            result
+        })
+        .collect()
+}
+
+/// Check whether a file is formatted, without writing it. On success,
+/// `changes` describes what formatting would change, `None` if nothing.
+pub fn check_file(file_path: &Path, project_only: bool) -> FormatResult {
+    let content = match fs::read_to_string(file_path) {
+        Ok(content) => content,
+        Err(e) => return FormatResult::error("check", &e.to_string()),
+    };
+
+    match plan(file_path, project_only) {
+        Plan::Chain(language, formatters) => {
+            run_chain_check(&language, &formatters, file_path, &content)
         }
+        Plan::Notebook => check_notebook(file_path, &content, project_only),
+        Plan::PackageJson(note) => with_note(
+            match reindent(file_path, &content) {
+                Ok(output) => FormatResult {
+                    changes: describe_changes(&content, &output, &file_path.display().to_string()),
+                    ..FormatResult::success(SAFE_MODE)
+                },
+                Err(e) => FormatResult::error(SAFE_MODE, &e),
+            },
+            note,
+        ),
+        Plan::Done(result) => result,
+    }
+}
 
-        if command_exists("dprint") {
-            return run_formatter_cmd("dprint", &["fmt"], file_path, None);
+/// Format `content` as if it were the contents of `file_path`, without
+/// touching the file. The formatted text is returned in `output`.
+pub fn format_content(file_path: &Path, content: &str, project_only: bool) -> FormatResult {
+    let skip = match Config::find(file_path) {
+        Ok(config) => config.map(|c| c.skip).unwrap_or_default(),
+        Err(e) => return FormatResult::error("config", &e),
+    };
+    if let Some(reason) = content_skip_reason(content, &skip) {
+        return FormatResult::skipped(&reason);
+    }
+
+    match plan(file_path, project_only) {
+        Plan::Chain(language, formatters) => {
+            run_chain_piped(&language, &formatters, file_path, content)
         }
+        Plan::Notebook => FormatResult {
+            formatted: false,
+            formatter: None,
+            message: "Notebooks can only be formatted on disk".to_string(),
+            changes: None,
+            output: None,
+            stderr: None,
+        },
+        Plan::PackageJson(note) => with_note(
+            match reindent(file_path, content) {
+                Ok(output) => FormatResult {
+                    output: Some(output),
+                    ..FormatResult::success(SAFE_MODE)
+                },
+                Err(e) => FormatResult::error(SAFE_MODE, &e),
+            },
+            note,
+        ),
+        Plan::Done(result) => result,
     }
+}
 
-    FormatResult::no_formatter("JavaScript/TypeScript")
+/// Format Python files
+pub fn format_python(file_path: &Path, project_only: bool) -> FormatResult {
+    match plan(file_path, project_only) {
+        Plan::Chain(language, formatters) => run_chain(&language, &formatters, file_path),
+        Plan::Notebook | Plan::PackageJson(_) => unreachable!("called with Python files"),
+        Plan::Done(result) => result,
+    }
 }
 
-/// Format Rust files
-fn format_rust(file_path: &Path, project_only: bool) -> FormatResult {
-    let project_root = find_cargo_root(file_path);
+/// Whether the file has an extension this tool formats
+pub fn is_supported(file_path: &Path) -> bool {
+    !matches!(plan(file_path, true), Plan::Done(_))
+}
 
-    // Try cargo fmt if in a Cargo project
-    if let Some(ref root) = project_root {
-        let result = Command::new("cargo")
-            .args(["fmt", "--", file_path.to_str().unwrap_or("")])
-            .current_dir(root)
-            .output();
+/// What to do with a file
+pub enum Plan {
+    /// Try these formatters in priority order, for files of a language
+    Chain(String, Vec<Formatter>),
+    /// Jupyter notebook, formatted cell by cell
+    Notebook,
+    /// package.json, re-indented in process without reordering keys, with a
+    /// note on why the keys were not sorted as configured
+    PackageJson(Option<String>),
+    /// Nothing to run
+    Done(FormatResult),
+}
 
-        match result {
-            Ok(output) if output.status.success() => {
-                return FormatResult::success("cargo fmt");
-            }
-            Ok(output) => {
-                let stderr = String::from_utf8_lossy(&output.stderr);
-                return FormatResult::error("cargo fmt", &stderr);
+/// Pick the formatter chain for the file's extension, as adjusted by the
+/// nearest config file. Protected, ignored, vendored and generated files are
+/// skipped.
+/// Candidates that are not installed are included, see
+/// [`Formatter::is_available`].
+pub fn plan(file_path: &Path, project_only: bool) -> Plan {
+    plan_with_env(file_path, project_only, &env_var)
+}
+
+/// [`plan`] with environment variables read through `var`
+pub fn plan_with_env(
+    file_path: &Path,
+    project_only: bool,
+    var: &dyn Fn(&str) -> Option<PathBuf>,
+) -> Plan {
+    let config = match Config::find(file_path) {
+        Ok(config) => config,
+        Err(e) => return Plan::Done(FormatResult::error("config", &e)),
+    };
+
+    if let Some(reason) = protected_reason(file_path, config.as_ref()) {
+        return Plan::Done(FormatResult::protected(file_path, &reason));
+    }
+
+    let skip = config.as_ref().map(|c| c.skip.clone()).unwrap_or_default();
+    if let Some(reason) = skip_reason(file_path, &skip) {
+        return Plan::Done(FormatResult::skipped(&reason));
+    }
+
+    let ext = file_path.extension().and_then(|e| e.to_str()).unwrap_or("");
+    let language = language_of(ext);
+
+    let files = language.map_or_else(|| format!(".{} files", ext), str::to_string);
+    let rules = config
+        .as_ref()
+        .map_or_else(Rules::default, |config| config.rules(language, ext));
+    if let (Some(config), Some(false)) = (&config, rules.enabled) {
+        return Plan::Done(FormatResult::disabled(&files, &config.path));
+    }
+
+    if ext == "ipynb" {
+        return Plan::Notebook;
+    }
+
+    let project_only = rules.project_only.unwrap_or(project_only);
+    if file_path
+        .file_name()
+        .is_some_and(|name| name == "package.json")
+    {
+        if !config
+            .as_ref()
+            .is_some_and(|config| config.sort_package_json)
+        {
+            return Plan::PackageJson(None);
+        }
+        // Without sort-package-json, still re-indent rather than fail
+        let formatters = sort_package_json_formatters(file_path, project_only);
+        if !formatters.iter().any(Formatter::is_available) {
+            return Plan::PackageJson(Some(format!(
+                "{} not found, keys left unsorted",
+                Tool::SortPackageJson.name()
+            )));
+        }
+        return Plan::Chain("package.json".to_string(), formatters);
+    }
+    let mut formatters = Vec::new();
+
+    // Custom formatters matching the file go first, unless a list places them
+    if let Some(config) = &config {
+        for custom in config.matching(file_path) {
+            let listed = rules
+                .formatters
+                .as_ref()
+                .is_some_and(|choices| choices.contains(&Choice::Custom(custom.name.clone())));
+            if !listed {
+                formatters.push(custom.formatter(config.root(), file_path));
             }
-            Err(_) => {}
         }
     }
 
-    if !project_only {
-        // Fallback to rustfmt directly
-        if command_exists("rustfmt") {
-            return run_formatter_cmd("rustfmt", &[], file_path, None);
+    match (&rules.formatters, &config, language) {
+        (Some(choices), Some(config), language) => formatters.extend(configured_formatters(
+            choices,
+            config,
+            language,
+            file_path,
+            project_only,
+            var,
+        )),
+        (_, _, Some(language)) => {
+            formatters.extend(builtin_formatters(language, file_path, project_only, var))
         }
+        _ if formatters.is_empty() => return Plan::Done(FormatResult::unsupported(ext)),
+        _ => {}
     }
 
-    FormatResult::no_formatter("Rust")
+    Plan::Chain(files, formatters)
 }
 
-/// Format Python files
-fn format_python(file_path: &Path, project_only: bool) -> FormatResult {
-    let formatters = ["ruff", "black", "autopep8", "yapf"];
-    let formatter_args: &[&[&str]] = &[&["format"], &[], &["--in-place"], &["-i"]];
+/// Language of files with extension `ext`, `None` if unsupported
+fn language_of(ext: &str) -> Option<&'static str> {
+    let language = match ext {
+        "js" | "jsx" | "ts" | "tsx" | "mjs" | "cjs" => "JavaScript/TypeScript",
+        "rs" => "Rust",
+        "py" | "pyi" => "Python",
+        "java" => "Java",
+        "go" => "Go",
+        "json" | "jsonc" | "json5" => "JSON",
+        "yaml" | "yml" => "YAML",
+        "toml" => "TOML",
+        "html" | "htm" => "HTML",
+        "vue" => "Vue",
+        "css" => "CSS",
+        "scss" => "SCSS",
+        "less" => "Less",
+        "md" | "markdown" => "Markdown",
+        "mdx" => "MDX",
+        "graphql" | "gql" => "GraphQL",
+        "hbs" | "handlebars" => "Handlebars",
+        _ => return None,
+    };
+    Some(language)
+}
 
-    if project_only {
-        // In project-only mode, only check for formatters in local venv
-        if let Some(ref root) = find_python_root(file_path) {
-            let venv_dirs = [".venv", "venv"];
-            for (i, name) in formatters.iter().enumerate() {
-                for venv_dir in &venv_dirs {
-                    let formatter_path = root.join(venv_dir).join("bin").join(name);
-                    if formatter_path.exists() {
-                        return run_formatter(
-                            name,
-                            &formatter_path,
-                            formatter_args[i],
-                            file_path,
-                            None,
-                        );
-                    }
-                }
-            }
-        }
-    } else {
-        // Try ruff format
-        if command_exists("ruff") {
-            let result = run_formatter_cmd("ruff", &["format"], file_path, None);
-            if result.formatted {
-                return result;
+/// The built-in priority order for a language
+fn builtin_formatters(
+    language: &str,
+    file_path: &Path,
+    project_only: bool,
+    var: &dyn Fn(&str) -> Option<PathBuf>,
+) -> Vec<Formatter> {
+    match language {
+        "JavaScript/TypeScript" => javascript_formatters(file_path, project_only),
+        "Rust" => rust_formatters(file_path, project_only),
+        "Python" => python_formatters(file_path, project_only, var),
+        "Java" => java_formatters(file_path, project_only),
+        "Go" => go_formatters(file_path, project_only),
+        // oxfmt-supported formats
+        _ => oxfmt_formatters(file_path, project_only),
+    }
+}
+
+/// Formatters named in the config, in the order given. Built-in tools are
+/// resolved the way the built-in chains resolve them (local installs first,
+/// then PATH), so a tool can be picked for a language that does not list it
+/// by default. Custom formatters run even with `project_only`.
+fn configured_formatters(
+    choices: &[Choice],
+    config: &Config,
+    language: Option<&str>,
+    file_path: &Path,
+    project_only: bool,
+    var: &dyn Fn(&str) -> Option<PathBuf>,
+) -> Vec<Formatter> {
+    // One language per chain builder; the rest share oxfmt's
+    const BUILDERS: [&str; 6] = [
+        "JavaScript/TypeScript",
+        "Rust",
+        "Python",
+        "Java",
+        "Go",
+        "JSON",
+    ];
+    let own = language.map(|language| {
+        BUILDERS
+            .into_iter()
+            .find(|builder| *builder == language)
+            .unwrap_or("JSON")
+    });
+
+    // The file's own chain is enough unless a tool is not in it, so other
+    // languages' project lookups only run when needed
+    let known = own.map_or_else(Vec::new, |language| {
+        builtin_formatters(language, file_path, project_only, var)
+    });
+    let mut others: Option<Vec<Formatter>> = None;
+    let find = |known: &[Formatter], tools: &[Tool]| -> Vec<Formatter> {
+        known
+            .iter()
+            .filter(|f| {
+                f.steps
+                    .iter()
+                    .map(|step| step.tool)
+                    .eq(tools.iter().copied())
+            })
+            .cloned()
+            .collect()
+    };
+
+    let mut chain: Vec<Formatter> = Vec::new();
+    for choice in choices {
+        let tools = match choice {
+            Choice::Tools(tools) => tools,
+            Choice::Custom(name) => {
+                let custom = config.custom(name).expect("choices name known formatters");
+                chain.push(custom.formatter(config.root(), file_path));
+                continue;
             }
+        };
+
+        let mut matching = find(&known, tools);
+        if matching.is_empty() {
+            let others = others.get_or_insert_with(|| {
+                BUILDERS
+                    .into_iter()
+                    .filter(|builder| Some(*builder) != own)
+                    .flat_map(|builder| builtin_formatters(builder, file_path, project_only, var))
+                    .collect()
+            });
+            matching = find(others, tools);
         }
 
-        // Try black
-        if command_exists("black") {
-            let result = run_formatter_cmd("black", &[], file_path, None);
-            if result.formatted {
-                return result;
-            }
+        if matching.is_empty() && !project_only {
+            matching.extend(
+                tools
+                    .iter()
+                    .map(|tool| Formatter::global(*tool))
+                    .reduce(Formatter::then),
+            );
         }
 
-        // Try autopep8
-        if command_exists("autopep8") {
-            let result = run_formatter_cmd("autopep8", &["--in-place"], file_path, None);
-            if result.formatted {
-                return result;
+        for formatter in matching {
+            if !chain.contains(&formatter) {
+                chain.push(formatter);
             }
         }
+    }
 
-        // Try yapf
-        if command_exists("yapf") {
-            let result = run_formatter_cmd("yapf", &["-i"], file_path, None);
-            if result.formatted {
-                return result;
-            }
+    chain
+}
+
+/// JavaScript/TypeScript formatters. The formatters the project's config
+/// files and dependencies point to come first; otherwise whichever is
+/// installed wins.
+fn javascript_formatters(file_path: &Path, project_only: bool) -> Vec<Formatter> {
+    let mut formatters = Vec::new();
+    let evidence = javascript_evidence(file_path);
+
+    // Local formatters first (by default): oxfmt (fastest), biome, prettier
+    let tools = ranked(&evidence, &[Tool::Oxfmt, Tool::Biome, Tool::Prettier]);
+    formatters.extend(node_formatters(file_path, &tools, &evidence));
+    formatters.extend(deno_formatter(file_path));
+
+    if !project_only {
+        // Fall back to global formatters
+        for tool in ranked(&evidence, &[Tool::Oxfmt, Tool::Dprint]) {
+            formatters.push(Formatter::global(tool));
         }
     }
 
-    FormatResult::no_formatter("Python")
+    formatters
 }
 
-/// Format Java files
-fn format_java(file_path: &Path, project_only: bool) -> FormatResult {
-    let project_root = find_java_root(file_path);
+/// Local installs of `tools`, from the file's package up to the workspace
+/// root (see [`find_node_package_dirs`]). Each tool comes from the nearest
+/// `node_modules/.bin` that has it, and nearer installs go first unless the
+/// project's config points to the tool. Tools installed nowhere are listed
+/// at the nearest directory, keeping their place in the priority order.
+///
+/// Plug'n'Play projects have no `node_modules/.bin`, so the tools they
+/// depend on are run through the package manager instead.
+fn node_formatters(file_path: &Path, tools: &[Tool], evidence: &[Tool]) -> Vec<Formatter> {
+    let package_dirs = find_node_package_dirs(file_path);
 
-    if let Some(ref root) = project_root {
-        // Check for Maven with Spotless
+    if let Some(root) = find_pnp_root(file_path) {
+        let manager = PackageManager::detect(&root).unwrap_or(PackageManager::Yarn);
+        return declared_dependencies(&package_dirs, tools)
+            .into_iter()
+            .map(|tool| Formatter::exec(tool, manager.binary(), manager.exec(tool.binary()), &root))
+            .collect();
+    }
+
+    let bin_dirs: Vec<PathBuf> = package_dirs
+        .iter()
+        .map(|dir| dir.join("node_modules/.bin"))
+        .collect();
+    let Some(nearest) = bin_dirs.first() else {
+        return Vec::new();
+    };
+
+    let mut installs: Vec<(bool, usize, Formatter)> = tools
+        .iter()
+        .map(|tool| {
+            let (depth, path) = bin_dirs
+                .iter()
+                .map(|dir| dir.join(tool.binary()))
+                .enumerate()
+                .find(|(_, path)| path.is_file())
+                .unwrap_or_else(|| (0, nearest.join(tool.binary())));
+            (
+                !evidence.contains(tool),
+                depth,
+                Formatter::local(*tool, path),
+            )
+        })
+        .collect();
+    // Stable, so ties keep the priority order
+    installs.sort_by_key(|(unconfigured, depth, _)| (*unconfigured, *depth));
+
+    installs
+        .into_iter()
+        .map(|(_, _, formatter)| formatter)
+        .collect()
+}
+
+/// `deno fmt` from the Deno project root, when a deno.json is at least as
+/// near as any package.json and the file is one deno formats. Files in its
+/// `fmt.exclude` are skipped before getting here.
+fn deno_formatter(file_path: &Path) -> Option<Formatter> {
+    let root = find_deno_root(file_path)?;
+    if find_node_root(file_path).is_some_and(|node_root| !root.starts_with(node_root)) {
+        return None;
+    }
+    let ext = file_path.extension().and_then(|e| e.to_str())?;
+    DENO_EXTENSIONS
+        .contains(&ext)
+        .then(|| Formatter::global(Tool::DenoFmt).in_dir(Some(&root)))
+}
+
+/// sort-package-json, opted into with `[package_json] sort = true`
+fn sort_package_json_formatters(file_path: &Path, project_only: bool) -> Vec<Formatter> {
+    let mut formatters = node_formatters(file_path, &[Tool::SortPackageJson], &[]);
+    if !project_only {
+        formatters.push(Formatter::global(Tool::SortPackageJson));
+    }
+
+    formatters
+}
+
+/// Add the plan's note to the result message
+fn with_note(mut result: FormatResult, note: Option<String>) -> FormatResult {
+    if let Some(note) = note {
+        result.message = format!("{} ({})", result.message, note);
+    }
+    result
+}
+
+/// Rust formatters
+fn rust_formatters(file_path: &Path, project_only: bool) -> Vec<Formatter> {
+    let mut formatters = Vec::new();
+
+    // cargo fmt if in a Cargo project
+    if let Some(root) = find_cargo_root(file_path) {
+        formatters.push(Formatter::global(Tool::CargoFmt).in_dir(Some(&root)));
+    }
+
+    if !project_only {
+        // Fallback to rustfmt directly
+        formatters.push(Formatter::global(Tool::Rustfmt));
+    }
+
+    formatters
+}
+
+/// Python formatters: ruff > black > autopep8 > yapf
+fn python_formatters(
+    file_path: &Path,
+    project_only: bool,
+    var: &dyn Fn(&str) -> Option<PathBuf>,
+) -> Vec<Formatter> {
+    let tools = ranked(
+        &python_evidence(file_path),
+        &[Tool::Ruff, Tool::Black, Tool::Autopep8, Tool::Yapf],
+    );
+
+    if project_only {
+        // In project-only mode, only check for formatters in the project's
+        // Python environments, then the activated ones. A formatter in a
+        // nearer environment beats a preferred one further out.
+        let root = find_python_root(file_path);
+        return find_python_envs(root.as_deref(), var)
+            .iter()
+            .flat_map(|env| tools.iter().map(move |tool| (*tool, env)))
+            .map(|(tool, env)| Formatter::local(tool, env.join("bin").join(tool.binary())))
+            .collect();
+    }
+
+    // Each global formatter gets a go until one succeeds
+    tools
+        .iter()
+        .map(|tool| Formatter::global(*tool).or_next())
+        .collect()
+}
+
+/// Java formatters
+fn java_formatters(file_path: &Path, project_only: bool) -> Vec<Formatter> {
+    let mut formatters = Vec::new();
+
+    if let Some(ref root) = find_java_root(file_path) {
+        // Maven with Spotless
         if root.join("pom.xml").exists() {
-            let result = Command::new("mvn")
-                .args([
-                    "spotless:apply",
-                    &format!("-DspotlessFiles={}", file_path.display()),
-                ])
-                .current_dir(root)
-                .output();
-
-            if let Ok(output) = result {
-                if output.status.success() {
-                    return FormatResult::success("spotless (Maven)");
-                }
-            }
+            formatters.push(
+                Formatter::global(Tool::SpotlessMaven)
+                    .in_dir(Some(root))
+                    .or_next(),
+            );
         }
 
-        // Check for Gradle with Spotless
+        // Gradle with Spotless, preferring the wrapper
         if root.join("build.gradle").exists() || root.join("build.gradle.kts").exists() {
-            // Try gradlew first
             let gradlew = if cfg!(windows) {
                 root.join("gradlew.bat")
             } else {
                 root.join("gradlew")
             };
 
-            let gradle_cmd = if gradlew.exists() {
-                gradlew.to_str().unwrap_or("gradle").to_string()
+            let gradle = if gradlew.exists() {
+                Formatter::local(Tool::SpotlessGradle, gradlew)
             } else {
-                "gradle".to_string()
+                Formatter::global(Tool::SpotlessGradle)
             };
-
-            let result = Command::new(&gradle_cmd)
-                .args(["spotlessApply"])
-                .current_dir(root)
-                .output();
-
-            if let Ok(output) = result {
-                if output.status.success() {
-                    return FormatResult::success("spotless (Gradle)");
-                }
-            }
+            formatters.push(gradle.in_dir(Some(root)).or_next());
         }
     }
 
     if !project_only {
-        // Try google-java-format
-        if command_exists("google-java-format") {
-            return run_formatter_cmd("google-java-format", &["--replace"], file_path, None);
-        }
-
-        // Try palantir-java-format
-        if command_exists("palantir-java-format") {
-            return run_formatter_cmd("palantir-java-format", &["--replace"], file_path, None);
-        }
+        formatters.push(Formatter::global(Tool::GoogleJavaFormat));
+        formatters.push(Formatter::global(Tool::PalantirJavaFormat));
     }
 
-    FormatResult::no_formatter("Java")
+    formatters
 }
 
-/// Format Go files
-fn format_go(file_path: &Path, project_only: bool) -> FormatResult {
+/// Go formatters
+fn go_formatters(file_path: &Path, project_only: bool) -> Vec<Formatter> {
     let project_root = find_go_root(file_path);
 
     if project_only && project_root.is_none() {
-        return FormatResult::no_formatter("Go");
+        return Vec::new();
     }
 
     let cwd = project_root.as_deref();
+    let go = |tool| Formatter::global(tool).in_dir(cwd);
 
-    // Best: goimports (imports) + gofumpt (strict formatting)
-    if command_exists("goimports") && command_exists("gofumpt") {
-        let result = run_formatter_cmd("goimports", &["-w"], file_path, cwd);
-        if result.formatted {
-            let result2 = run_formatter_cmd("gofumpt", &["-w"], file_path, cwd);
-            if result2.formatted {
-                return FormatResult::success("goimports + gofumpt");
-            }
-        }
-    }
+    vec![
+        // Best: goimports (imports) + gofumpt (strict formatting)
+        go(Tool::Goimports).then(go(Tool::Gofumpt)).or_next(),
+        // gofumpt alone (strict formatting, no import management)
+        go(Tool::Gofumpt).or_next(),
+        // goimports alone (imports + basic formatting)
+        go(Tool::Goimports).or_next(),
+        // gofmt (always available with Go installation)
+        go(Tool::Gofmt),
+    ]
+}
 
-    // Try gofumpt alone (strict formatting, no import management)
-    if command_exists("gofumpt") {
-        let result = run_formatter_cmd("gofumpt", &["-w"], file_path, cwd);
-        if result.formatted {
-            return result;
-        }
-    }
+/// Formatters for files handled by oxfmt (JSON, YAML, TOML, HTML, Vue, CSS, SCSS, Less, Markdown, MDX, GraphQL, Handlebars)
+fn oxfmt_formatters(file_path: &Path, project_only: bool) -> Vec<Formatter> {
+    let mut formatters = Vec::new();
 
-    // Try goimports alone (imports + basic formatting)
-    if command_exists("goimports") {
-        let result = run_formatter_cmd("goimports", &["-w"], file_path, cwd);
-        if result.formatted {
-            return result;
-        }
+    // Project-local oxfmt first (node_modules/.bin/oxfmt)
+    if find_node_root(file_path).is_some() {
+        formatters.extend(node_formatters(file_path, &[Tool::Oxfmt], &[]));
+    } else if let Some(root) = find_project_root(file_path) {
+        let oxfmt_path = root.join("node_modules/.bin/oxfmt");
+        formatters.push(Formatter::local(Tool::Oxfmt, oxfmt_path));
     }
+    formatters.extend(deno_formatter(file_path));
 
-    // Fallback to gofmt (always available with Go installation)
-    if command_exists("gofmt") {
-        return run_formatter_cmd("gofmt", &["-w"], file_path, cwd);
+    if !project_only {
+        // Fallback to global oxfmt
+        formatters.push(Formatter::global(Tool::Oxfmt));
     }
 
-    FormatResult::no_formatter("Go")
+    formatters
 }
 
-/// Format files using oxfmt (JSON, YAML, TOML, HTML, Vue, CSS, SCSS, Less, Markdown, MDX, GraphQL, Handlebars)
-fn format_with_oxfmt(file_path: &Path, language: &str, project_only: bool) -> FormatResult {
-    let project_root = find_project_root(file_path);
+/// Run the first available formatter in the chain on the file
+fn run_chain(language: &str, formatters: &[Formatter], file_path: &Path) -> FormatResult {
+    let mut first_error = None;
 
-    // Try project-local oxfmt first (node_modules/.bin/oxfmt)
-    if let Some(ref root) = project_root {
-        let oxfmt_path = root.join("node_modules/.bin/oxfmt");
-        if oxfmt_path.exists() {
-            return run_formatter("oxfmt", &oxfmt_path, &["--write"], file_path, None);
-        }
-    }
+    for formatter in formatters.iter().filter(|f| f.is_available()) {
+        let error = match formatter.write(file_path) {
+            Ok(()) => return FormatResult::success(&formatter.name),
+            Err(Failure::NotFound) => continue,
+            Err(Failure::NoStdin) => unreachable!("writing never reads stdin"),
+            Err(Failure::Error(e)) => FormatResult::error(&formatter.name, &e),
+        };
 
-    if !project_only {
-        // Fallback to global oxfmt
-        if command_exists("oxfmt") {
-            return run_formatter_cmd("oxfmt", &["--write"], file_path, None);
+        if !formatter.fallthrough {
+            return error;
         }
+        first_error.get_or_insert(error);
     }
 
-    FormatResult::no_formatter(language)
+    first_error.unwrap_or_else(|| FormatResult::no_formatter(language))
 }
 
-/// Check if a command exists in PATH
-fn command_exists(cmd: &str) -> bool {
-    Command::new("which")
-        .arg(cmd)
-        .output()
-        .map(|o| o.status.success())
-        .unwrap_or(false)
+/// Run the first available formatter in the chain on every file at once.
+/// If the formatter fails, each file is retried on its own so one file with a
+/// syntax error does not hold back the rest.
+fn run_chain_batch(language: &str, formatters: &[Formatter], files: &[&Path]) -> Vec<FormatResult> {
+    if let [file_path] = files {
+        return vec![run_chain(language, formatters, file_path)];
+    }
+
+    for formatter in formatters.iter().filter(|f| f.is_available()) {
+        match formatter.write_all(files) {
+            Ok(()) => {
+                return files
+                    .iter()
+                    .map(|_| FormatResult::success(&formatter.name))
+                    .collect();
+            }
+            Err(Failure::NotFound) => continue,
+            Err(_) => break,
+        }
+    }
+
+    files
+        .iter()
+        .map(|file_path| run_chain(language, formatters, file_path))
+        .collect()
 }
 
-/// Run a formatter command
-fn run_formatter_cmd(
-    name: &str,
-    args: &[&str],
+/// Check `content` with the first available formatter in the chain, by
+/// comparing its stdin output, or with the tool's own check mode if it
+/// cannot read stdin
+fn run_chain_check(
+    language: &str,
+    formatters: &[Formatter],
     file_path: &Path,
-    cwd: Option<&Path>,
+    content: &str,
 ) -> FormatResult {
-    let mut cmd = Command::new(name);
-    cmd.args(args).arg(file_path);
+    let label = file_path.display().to_string();
+    let mut first_error = None;
+
+    for formatter in formatters.iter().filter(|f| f.is_available()) {
+        let checked = match formatter.pipe(file_path, content) {
+            Ok(output) => Ok(describe_changes(content, &output, &label)),
+            Err(Failure::NoStdin) => match formatter.check(file_path) {
+                // Neither stdin nor a check mode: format a copy instead
+                Err(Failure::NoStdin) => formatter
+                    .pipe_via_file(file_path, content)
+                    .map(|output| describe_changes(content, &output, &label)),
+                checked => checked.map(|report| {
+                    report.map(|_| {
+                        format!("{}: not formatted according to {}", label, formatter.name)
+                    })
+                }),
+            },
+            Err(failure) => Err(failure),
+        };
 
-    if let Some(dir) = cwd {
-        cmd.current_dir(dir);
+        let error = match checked {
+            Ok(changes) => {
+                return FormatResult {
+                    changes,
+                    ..FormatResult::success(&formatter.name)
+                };
+            }
+            Err(Failure::NotFound) => continue,
+            // Spotless for Gradle only formats or checks the whole project
+            Err(Failure::NoStdin) => {
+                return FormatResult {
+                    formatted: false,
+                    formatter: Some(formatter.name.clone()),
+                    message: format!("{} cannot check a single file", formatter.name),
+                    changes: None,
+                    output: None,
+                    stderr: None,
+                };
+            }
+            Err(Failure::Error(e)) => FormatResult::error(&formatter.name, &e),
+        };
+
+        if !formatter.fallthrough {
+            return error;
+        }
+        first_error.get_or_insert(error);
     }
 
-    match cmd.output() {
-        Ok(output) if output.status.success() => FormatResult::success(name),
-        Ok(output) => {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            FormatResult::error(name, &stderr)
+    first_error.unwrap_or_else(|| FormatResult::no_formatter(language))
+}
+
+/// Check a notebook by formatting a scratch copy of it
+fn check_notebook(file_path: &Path, content: &str, project_only: bool) -> FormatResult {
+    let name = file_path
+        .file_name()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let scratch = file_path.with_file_name(format!(
+        ".{}.ralph-hook-fmt-check-{}.ipynb",
+        name,
+        std::process::id()
+    ));
+
+    if let Err(e) = fs::write(&scratch, content) {
+        return FormatResult::error("notebook", &e.to_string());
+    }
+    let mut result = format_notebook(&scratch, project_only);
+    if result.formatted {
+        if let Ok(after) = fs::read_to_string(&scratch) {
+            result.changes = describe_changes(content, &after, &file_path.display().to_string());
         }
-        Err(e) => FormatResult::error(name, &e.to_string()),
     }
+    let _ = fs::remove_file(&scratch);
+
+    result
 }
 
-/// Run a formatter with a specific path
-fn run_formatter(
-    name: &str,
-    formatter_path: &Path,
-    args: &[&str],
+/// Run the first available formatter in the chain on `content`, through a
+/// temporary file if it cannot read stdin
+fn run_chain_piped(
+    language: &str,
+    formatters: &[Formatter],
     file_path: &Path,
-    cwd: Option<&Path>,
+    content: &str,
 ) -> FormatResult {
-    let mut cmd = Command::new(formatter_path);
-    cmd.args(args).arg(file_path);
+    let mut first_error = None;
 
-    if let Some(dir) = cwd {
-        cmd.current_dir(dir);
-    }
+    for formatter in formatters.iter().filter(|f| f.is_available()) {
+        let piped = match formatter.pipe(file_path, content) {
+            Err(Failure::NoStdin) => formatter.pipe_via_file(file_path, content),
+            piped => piped,
+        };
 
-    match cmd.output() {
-        Ok(output) if output.status.success() => FormatResult::success(name),
-        Ok(output) => {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            FormatResult::error(name, &stderr)
+        let error = match piped {
+            Ok(output) => {
+                return FormatResult {
+                    output: Some(output),
+                    ..FormatResult::success(&formatter.name)
+                };
+            }
+            Err(Failure::NotFound) => continue,
+            // Falling through would format differently than on disk
+            Err(Failure::NoStdin) => {
+                return FormatResult {
+                    formatted: false,
+                    formatter: Some(formatter.name.clone()),
+                    message: format!("{} cannot format from stdin", formatter.name),
+                    changes: None,
+                    output: None,
+                    stderr: None,
+                };
+            }
+            Err(Failure::Error(e)) => FormatResult::error(&formatter.name, &e),
+        };
+
+        if !formatter.fallthrough {
+            return error;
         }
-        Err(e) => FormatResult::error(name, &e.to_string()),
+        first_error.get_or_insert(error);
     }
+
+    first_error.unwrap_or_else(|| FormatResult::no_formatter(language))
 }
 
 #[cfg(test)]
@@ -448,10 +959,155 @@ mod tests {
         assert!(result.message.contains("Unsupported"));
     }
 
+    #[cfg(unix)]
     #[test]
-    fn test_skip_package_json() {
-        let result = format_file(Path::new("/path/to/package.json"), false);
+    fn test_non_utf8_path_is_passed_through() {
+        use std::ffi::OsStr;
+        use std::fs;
+        use std::os::unix::ffi::OsStrExt;
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        let log = dir.join("log");
+        let mock = |name: &str, script: &str| {
+            let program = dir.join(name);
+            fs::write(&program, format!("#!/bin/sh\n{}\n", script)).unwrap();
+            fs::set_permissions(&program, fs::Permissions::from_mode(0o755)).unwrap();
+            program
+        };
+
+        // gofmt gets the path itself, byte for byte
+        let file_path = dir.join(OsStr::from_bytes(b"caf\xe9.go"));
+        fs::write(&file_path, "package main").unwrap();
+        let gofmt = mock(
+            "gofmt",
+            &format!(r#"printf '%s' "$2" > "{}""#, log.display()),
+        );
+        Formatter::local(Tool::Gofmt, gofmt)
+            .write(&file_path)
+            .unwrap();
+        assert_eq!(fs::read(&log).unwrap(), file_path.as_os_str().as_bytes());
+
+        // rustfmt would panic on it, so the content is piped instead
+        let file_path = dir.join(OsStr::from_bytes(b"caf\xe9.rs"));
+        fs::write(&file_path, "fn main(){let x=1;}").unwrap();
+        let rustfmt = mock(
+            "rustfmt",
+            &format!(
+                r#"printf '%s\n' "$@" > "{}.args"; cat > "{}"; printf 'fn main() {{}}\n'"#,
+                log.display(),
+                log.display()
+            ),
+        );
+        Formatter::local(Tool::Rustfmt, rustfmt)
+            .write(&file_path)
+            .unwrap();
+        assert_eq!(fs::read(&log).unwrap(), b"fn main(){let x=1;}");
+        let args = fs::read(dir.join("log.args")).unwrap();
+        assert!(!args.windows(4).any(|w| w == b"caf\xe9"));
+        assert_eq!(fs::read_to_string(&file_path).unwrap(), "fn main() {}\n");
+    }
+
+    #[test]
+    fn test_package_json_is_reindented() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("package.json");
+        fs::write(
+            &file_path,
+            "{\"name\":\"a\",\"dependencies\":{\"z\":\"1\",\"a\":\"2\"}}\n",
+        )
+        .unwrap();
+
+        let result = format_file(&file_path, false);
+        assert!(result.formatted, "{}", result.message);
+        assert_eq!(result.formatter.as_deref(), Some(SAFE_MODE));
+        assert_eq!(
+            fs::read_to_string(&file_path).unwrap(),
+            "{\n  \"name\": \"a\",\n  \"dependencies\": {\n    \"z\": \"1\",\n    \"a\": \"2\"\n  }\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_config_overrides_chain() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("package.json"), "{}").unwrap();
+        fs::write(
+            temp_dir.path().join(".ralph-hook-fmt.toml"),
+            "[languages.markdown]\n\
+             formatters = [\"prettier\", \"oxfmt\"]\n\
+             project_only = true\n\
+             \n\
+             [languages.go]\n\
+             enabled = false\n",
+        )
+        .unwrap();
+
+        let Plan::Chain(language, chain) =
+            plan_with_env(&temp_dir.path().join("README.md"), false, &|_| None)
+        else {
+            panic!("expected a chain");
+        };
+        assert_eq!(language, "Markdown");
+        let bin = temp_dir.path().join("node_modules/.bin");
+        assert_eq!(
+            chain,
+            vec![
+                Formatter::local(Tool::Prettier, bin.join("prettier")),
+                Formatter::local(Tool::Oxfmt, bin.join("oxfmt")),
+            ]
+        );
+
+        let result = format_file(&temp_dir.path().join("main.go"), false);
+        assert!(!result.formatted);
+        assert!(
+            result.message.starts_with("Formatting Go is disabled in "),
+            "{}",
+            result.message
+        );
+    }
+
+    #[test]
+    fn test_configured_tools_from_own_and_other_languages() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::write(root.join("package.json"), "{}").unwrap();
+        fs::write(root.join("pyproject.toml"), "").unwrap();
+        fs::write(
+            root.join(".ralph-hook-fmt.toml"),
+            "[languages.python]\n\
+             formatters = [\"ruff\", \"prettier\"]\n",
+        )
+        .unwrap();
+
+        let Plan::Chain(_, chain) = plan_with_env(&root.join("a.py"), true, &|_| None) else {
+            panic!("expected a chain");
+        };
+        assert_eq!(
+            chain,
+            vec![
+                Formatter::local(Tool::Ruff, root.join(".venv/bin/ruff")),
+                Formatter::local(Tool::Ruff, root.join("venv/bin/ruff")),
+                Formatter::local(Tool::Prettier, root.join("node_modules/.bin/prettier")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_invalid_config_is_reported() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let config = temp_dir.path().join(".ralph-hook-fmt.toml");
+        fs::write(&config, "[languages.rust]\nformatter = \"rustfmtt\"\n").unwrap();
+
+        let result = format_file(&temp_dir.path().join("main.rs"), false);
         assert!(!result.formatted);
-        assert!(result.message.contains("Skipped package.json"));
+        assert!(
+            result.message.starts_with(&format!(
+                "config error: {}: unknown formatter 'rustfmtt'",
+                config.display()
+            )),
+            "{}",
+            result.message
+        );
     }
 }
@@ -0,0 +1,227 @@
+use std::path::Path;
+
+/// A gitignore-style glob, matched against paths relative to a base
+/// directory.
+///
+/// `*` and `?` stay within one path segment, `**` spans any number of
+/// segments, `[a-z]`/`[!a-z]` match a character class and `{a,b}` either
+/// alternative. A pattern without a `/` matches at any depth; a leading `/`
+/// anchors it to the base. A trailing `/` only matches directories.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Glob {
+    /// The pattern as written
+    pub pattern: String,
+    /// Brace alternatives expanded, each anchored to the base
+    alternatives: Vec<Vec<char>>,
+    dir_only: bool,
+}
+
+impl Glob {
+    pub fn new(pattern: &str) -> Result<Glob, String> {
+        let mut body = pattern;
+        let dir_only = body.len() > 1 && body.ends_with('/');
+        if dir_only {
+            body = &body[..body.len() - 1];
+        }
+        if body.is_empty() {
+            return Err("empty pattern".to_string());
+        }
+
+        let anchored = body.contains('/');
+        let body = body.strip_prefix('/').unwrap_or(body);
+        let alternatives = expand_braces(body)
+            .ok_or_else(|| format!("unbalanced braces in '{}'", pattern))?
+            .into_iter()
+            .map(|alternative| {
+                let alternative = if anchored {
+                    alternative
+                } else {
+                    format!("**/{}", alternative)
+                };
+                alternative.chars().collect()
+            })
+            .collect();
+
+        Ok(Glob {
+            pattern: pattern.to_string(),
+            alternatives,
+            dir_only,
+        })
+    }
+
+    /// Whether `path`, relative to the base, matches. `is_dir` tells whether
+    /// it names a directory.
+    pub fn matches(&self, path: &Path, is_dir: bool) -> bool {
+        if self.dir_only && !is_dir {
+            return false;
+        }
+        let path: Vec<char> = path
+            .to_string_lossy()
+            .chars()
+            .map(|c| if c == '\\' { '/' } else { c })
+            .collect();
+        self.alternatives
+            .iter()
+            .any(|pattern| matches_from(pattern, &path))
+    }
+}
+
+/// Expand `{a,b}` alternatives, nested ones included. `None` if unbalanced.
+fn expand_braces(pattern: &str) -> Option<Vec<String>> {
+    let Some(open) = pattern.find('{') else {
+        return (!pattern.contains('}')).then(|| vec![pattern.to_string()]);
+    };
+
+    // The matching close brace, and the top-level commas in between
+    let mut depth = 0;
+    let mut commas = Vec::new();
+    let mut close = None;
+    for (i, c) in pattern[open..].char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    close = Some(open + i);
+                    break;
+                }
+            }
+            ',' if depth == 1 => commas.push(open + i),
+            _ => {}
+        }
+    }
+    let close = close?;
+
+    let mut bounds = vec![open];
+    bounds.extend(commas);
+    bounds.push(close);
+
+    let prefix = &pattern[..open];
+    let mut expanded = Vec::new();
+    for suffix in expand_braces(&pattern[close + 1..])? {
+        for window in bounds.windows(2) {
+            for middle in expand_braces(&pattern[window[0] + 1..window[1]])? {
+                expanded.push(format!("{}{}{}", prefix, middle, suffix));
+            }
+        }
+    }
+    Some(expanded)
+}
+
+fn matches_from(pattern: &[char], path: &[char]) -> bool {
+    match pattern {
+        [] => path.is_empty(),
+        // `**/` matches zero or more whole segments
+        ['*', '*', '/', rest @ ..] => {
+            matches_from(rest, path)
+                || path
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, c)| **c == '/')
+                    .any(|(i, _)| matches_from(rest, &path[i + 1..]))
+        }
+        // A trailing `**` matches everything below
+        ['*', '*'] => true,
+        ['*', rest @ ..] => {
+            let segment = path.iter().position(|c| *c == '/').unwrap_or(path.len());
+            (0..=segment).any(|i| matches_from(rest, &path[i..]))
+        }
+        ['?', rest @ ..] => matches!(path, [c, tail @ ..] if *c != '/' && matches_from(rest, tail)),
+        ['[', class @ ..] => match (path, class_end(class)) {
+            ([c, tail @ ..], Some(end)) => {
+                *c != '/' && in_class(&class[..end], *c) && matches_from(&class[end + 1..], tail)
+            }
+            // No closing bracket: a literal `[`
+            ([c, tail @ ..], None) => *c == '[' && matches_from(class, tail),
+            ([], _) => false,
+        },
+        ['\\', escaped, rest @ ..] => {
+            matches!(path, [c, tail @ ..] if c == escaped && matches_from(rest, tail))
+        }
+        [literal, rest @ ..] => {
+            matches!(path, [c, tail @ ..] if c == literal && matches_from(rest, tail))
+        }
+    }
+}
+
+/// Index of the `]` closing a class that starts just after `[`
+fn class_end(class: &[char]) -> Option<usize> {
+    let start = match class {
+        ['!' | '^', ']', ..] => 2,
+        ['!' | '^', ..] | [']', ..] => 1,
+        _ => 0,
+    };
+    class[start..]
+        .iter()
+        .position(|c| *c == ']')
+        .map(|i| start + i)
+}
+
+fn in_class(class: &[char], c: char) -> bool {
+    let (negated, class) = match class {
+        ['!' | '^', rest @ ..] => (true, rest),
+        _ => (false, class),
+    };
+
+    let mut found = false;
+    let mut i = 0;
+    while i < class.len() {
+        if i + 2 < class.len() && class[i + 1] == '-' {
+            found |= class[i] <= c && c <= class[i + 2];
+            i += 3;
+        } else {
+            found |= class[i] == c;
+            i += 1;
+        }
+    }
+    found != negated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(pattern: &str, path: &str) -> bool {
+        Glob::new(pattern).unwrap().matches(Path::new(path), false)
+    }
+
+    #[test]
+    fn test_unanchored_matches_any_depth() {
+        assert!(matches("*.sql", "a.sql"));
+        assert!(matches("*.sql", "db/migrations/a.sql"));
+        assert!(!matches("*.sql", "a.sql.bak"));
+        assert!(matches("Makefile", "sub/Makefile"));
+    }
+
+    #[test]
+    fn test_anchored_and_double_star() {
+        assert!(matches("db/*.sql", "db/a.sql"));
+        assert!(!matches("db/*.sql", "db/x/a.sql"));
+        assert!(!matches("db/*.sql", "src/db/a.sql"));
+        assert!(matches("/db/**/*.sql", "db/a.sql"));
+        assert!(matches("db/**/*.sql", "db/x/y/a.sql"));
+        assert!(matches("vendor/**", "vendor/a/b.go"));
+        assert!(!matches("vendor/**", "src/vendor/a.go"));
+    }
+
+    #[test]
+    fn test_classes_braces_and_escapes() {
+        assert!(matches("file?.[ch]", "file1.c"));
+        assert!(!matches("file?.[!ch]", "file1.c"));
+        assert!(matches("[a-c]x", "bx"));
+        assert!(matches("*.{yml,yaml}", "ci/a.yaml"));
+        assert!(matches("{src,lib/{a,b}}/*.rs", "lib/b/x.rs"));
+        assert!(!matches("{src,lib/{a,b}}/*.rs", "lib/c/x.rs"));
+        assert!(matches("\\*.md", "*.md"));
+        assert!(!matches("\\*.md", "a.md"));
+        assert!(Glob::new("{a,b").is_err());
+    }
+
+    #[test]
+    fn test_dir_only() {
+        let glob = Glob::new("build/").unwrap();
+        assert!(glob.matches(Path::new("build"), true));
+        assert!(glob.matches(Path::new("pkg/build"), true));
+        assert!(!glob.matches(Path::new("build"), false));
+    }
+}
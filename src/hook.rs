@@ -0,0 +1,255 @@
+use std::fs;
+use std::io::{self, Read};
+use std::path::Path;
+
+use crate::batch::{self, Ledger};
+use crate::cli::HookOptions;
+use crate::diagnostics;
+use crate::format::{FormatResult, format_content, format_file, format_files};
+use crate::input::HookInput;
+use crate::json::Value;
+use crate::output::{HookOutput, HookSpecificOutput};
+
+/// Handle one hook invocation. The hook never fails the tool call: every
+/// outcome is reported in the JSON response.
+pub fn run(options: &HookOptions) {
+    let HookOptions {
+        debug,
+        project_only,
+        pre_tool_use,
+        batch,
+        on_stop,
+        block_on_error,
+        ..
+    } = *options;
+
+    // Read JSON input from stdin, or the payload being replayed
+    let input = match &options.input {
+        Some(file) => fs::read_to_string(file),
+        None => {
+            let mut input = String::new();
+            io::stdin().read_to_string(&mut input).map(|_| input)
+        }
+    };
+    let Ok(input) = input else {
+        print_response(debug, true, "Failed to read input");
+        return;
+    };
+
+    // Parse the hook payload
+    let hook = match HookInput::parse(&input) {
+        Some(hook) => hook,
+        None => {
+            print_response(debug, true, "Could not parse hook input");
+            return;
+        }
+    };
+
+    if on_stop {
+        format_session(&hook, debug, project_only, block_on_error);
+        return;
+    }
+
+    if !hook.is_supported_tool() {
+        let tool = hook.tool_name.as_deref().unwrap_or_default();
+        print_response(debug, true, &format!("Ignoring {} tool", tool));
+        return;
+    }
+
+    if pre_tool_use {
+        format_before_write(&hook, debug, project_only);
+        return;
+    }
+
+    // Nothing was written if the tool itself failed
+    if hook.tool_failed() {
+        print_response(debug, true, "Tool reported failure, skipping");
+        return;
+    }
+
+    // Extract file path from input, relative to the session's cwd
+    let file_path = match hook.resolved_file_path() {
+        Some(path) => path,
+        None => {
+            print_response(debug, true, "Could not extract file path from input");
+            return;
+        }
+    };
+
+    // Check if file exists
+    if !file_path.exists() {
+        print_response(
+            debug,
+            true,
+            &format!("File does not exist: {}", file_path.display()),
+        );
+        return;
+    }
+
+    // In batch mode, only note the file; it is formatted when the session stops
+    if batch {
+        if let Some(ledger) = hook.session_id.as_deref().and_then(Ledger::for_session) {
+            let message = match ledger.record(&file_path) {
+                Ok(()) => format!("Recorded {} for formatting on stop", file_path.display()),
+                Err(e) => format!("Could not record {}: {}", file_path.display(), e),
+            };
+            print_response(debug, true, &message);
+            return;
+        }
+    }
+
+    // Format the file
+    let result = format_file(&file_path, project_only);
+
+    // Build the response message
+    let message = format!("[ralph-hook-fmt] {}", result.message);
+
+    // Hand syntax errors back to Claude so it fixes them right away
+    if block_on_error {
+        if let Some(reason) = syntax_errors(&file_path, &result) {
+            let mut response = HookOutput::block(&reason);
+            if debug {
+                response.system_message = Some(message);
+            }
+            println!("{}", response);
+            return;
+        }
+    }
+
+    let mut response = HookOutput::proceed();
+    if debug {
+        response.system_message = Some(message);
+    }
+
+    // Tell Claude what changed so its view of the file stays accurate
+    if let Some(changes) = result.changes {
+        let formatter = result.formatter.as_deref().unwrap_or("the formatter");
+        response.hook_specific_output = Some(HookSpecificOutput {
+            hook_event_name: hook
+                .hook_event_name
+                .unwrap_or_else(|| "PostToolUse".to_string()),
+            additional_context: Some(format!(
+                "[ralph-hook-fmt] {} was reformatted with {}. \
+                 Use the current file contents for further edits:\n{}",
+                file_path.display(),
+                formatter,
+                changes
+            )),
+            ..HookSpecificOutput::default()
+        });
+    }
+
+    println!("{}", response);
+}
+
+/// PreToolUse: format the content of a Write before it lands on disk, so the
+/// agent's record of the write matches the file
+fn format_before_write(hook: &HookInput, debug: bool, project_only: bool) {
+    if hook.tool_name.as_deref() != Some("Write") {
+        print_response(
+            debug,
+            true,
+            "Only Write content is formatted before writing",
+        );
+        return;
+    }
+
+    let Some(content) = hook.tool_input.get("content").and_then(Value::as_str) else {
+        print_response(debug, true, "Could not extract content from input");
+        return;
+    };
+
+    let Some(file_path) = hook.resolved_file_path() else {
+        print_response(debug, true, "Could not extract file path from input");
+        return;
+    };
+
+    let result = format_content(&file_path, content, project_only);
+
+    let mut response = HookOutput::proceed();
+    if debug {
+        response.system_message = Some(format!("[ralph-hook-fmt] {}", result.message));
+    }
+
+    if let Some(formatted) = result.output.filter(|formatted| formatted != content) {
+        let mut updated_input = hook.tool_input.clone();
+        if let Some(content) = updated_input.get_mut("content") {
+            *content = Value::String(formatted);
+        }
+
+        response.hook_specific_output = Some(HookSpecificOutput {
+            hook_event_name: "PreToolUse".to_string(),
+            updated_input: Some(updated_input),
+            ..HookSpecificOutput::default()
+        });
+    }
+
+    println!("{}", response);
+}
+
+/// Stop: format every file recorded in batch mode during the session and
+/// report the results in one message
+fn format_session(hook: &HookInput, debug: bool, project_only: bool, block_on_error: bool) {
+    let Some(ledger) = hook.session_id.as_deref().and_then(Ledger::for_session) else {
+        print_response(debug, true, "Could not extract session_id from input");
+        return;
+    };
+
+    let files: Vec<_> = ledger.take().into_iter().filter(|f| f.exists()).collect();
+    if files.is_empty() {
+        println!("{}", HookOutput::proceed());
+        return;
+    }
+
+    let results = format_files(&files, project_only);
+    let results: Vec<_> = files.into_iter().zip(results).collect();
+
+    let mut response = HookOutput::proceed();
+
+    // Keep Claude going until the syntax errors are fixed, but only once so a
+    // persistent error can't stop the session from ending
+    if block_on_error && !hook.stop_hook_active {
+        let errors: Vec<String> = results
+            .iter()
+            .filter_map(|(file_path, result)| syntax_errors(file_path, result))
+            .collect();
+        if !errors.is_empty() {
+            response = HookOutput::block(&errors.join("\n\n"));
+        }
+    }
+
+    response.system_message = Some(batch::summarize(&results, hook.cwd.as_deref()));
+    println!("{}", response);
+}
+
+/// Reason to block with when the formatter failed on a syntax error, `None`
+/// for any other outcome
+fn syntax_errors(file_path: &Path, result: &FormatResult) -> Option<String> {
+    if result.formatted {
+        return None;
+    }
+    let formatter = result.formatter.as_deref()?;
+    let report = diagnostics::report(formatter, result.stderr.as_deref()?)?;
+
+    Some(format!(
+        "[ralph-hook-fmt] {} could not format {} because of syntax errors. \
+         Fix them before continuing:\n{}",
+        formatter,
+        file_path.display(),
+        report
+    ))
+}
+
+fn print_response(debug: bool, continue_execution: bool, message: &str) {
+    let mut response = if continue_execution {
+        HookOutput::proceed()
+    } else {
+        HookOutput::block(message)
+    };
+
+    if debug {
+        response.system_message = Some(message.to_string());
+    }
+
+    println!("{}", response);
+}
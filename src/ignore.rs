@@ -0,0 +1,599 @@
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use crate::config::{Config, Skip};
+use crate::format::DENO_EXTENSIONS;
+use crate::glob::Glob;
+use crate::json::{self, Value};
+use crate::project::{find_deno_root, find_project_root};
+use crate::toml;
+
+/// gitignore-syntax file listing paths this hook never formats
+pub const IGNORE_FILE: &str = ".ralph-hook-fmt-ignore";
+
+/// Comment that turns formatting off for a file
+pub const PRAGMA: &str = "ralph-hook-fmt: off";
+
+/// How far into a file the pragma and generated-code headers are looked for
+const HEADER_LINES: usize = 5;
+const HEADER_BYTES: u64 = 64 * 1024;
+
+/// Directories holding vendored dependencies or build output
+pub const VENDORED_DIRS: [&str; 4] = ["vendor", "node_modules", "target", "dist"];
+
+/// Files written by package managers and other tools, matched by file
+/// name, with why they are never formatted
+const PROTECTED_FILES: [(&str, &str); 16] = [
+    ("package-lock.json", "lockfile written by npm"),
+    ("npm-shrinkwrap.json", "lockfile written by npm"),
+    ("pnpm-lock.yaml", "lockfile written by pnpm"),
+    ("yarn.lock", "lockfile written by Yarn"),
+    ("bun.lock", "lockfile written by Bun"),
+    ("bun.lockb", "lockfile written by Bun"),
+    ("deno.lock", "lockfile written by Deno"),
+    ("composer.lock", "lockfile written by Composer"),
+    ("Cargo.lock", "lockfile written by Cargo"),
+    ("Gemfile.lock", "lockfile written by Bundler"),
+    ("poetry.lock", "lockfile written by Poetry"),
+    ("uv.lock", "lockfile written by uv"),
+    ("Pipfile.lock", "lockfile written by Pipenv"),
+    ("flake.lock", "lockfile written by Nix"),
+    (".terraform.lock.hcl", "lockfile written by Terraform"),
+    ("*.tsbuildinfo", "build cache written by tsc"),
+];
+
+/// gitignore-syntax files read in every directory from the root down
+const NESTED_IGNORE_FILES: [&str; 2] = [".gitignore", IGNORE_FILE];
+
+/// Extensions prettier formats, the files `.prettierignore` applies to
+const PRETTIER_EXTENSIONS: [&str; 24] = [
+    "js",
+    "jsx",
+    "ts",
+    "tsx",
+    "mjs",
+    "cjs",
+    "json",
+    "jsonc",
+    "json5",
+    "yaml",
+    "yml",
+    "html",
+    "htm",
+    "vue",
+    "css",
+    "scss",
+    "less",
+    "md",
+    "markdown",
+    "mdx",
+    "graphql",
+    "gql",
+    "hbs",
+    "handlebars",
+];
+
+/// Extensions biome formats, the files its ignore settings apply to
+const BIOME_EXTENSIONS: [&str; 11] = [
+    "js", "jsx", "ts", "tsx", "mjs", "cjs", "json", "jsonc", "css", "graphql", "gql",
+];
+
+/// One pattern from an ignore file
+#[derive(Debug)]
+struct Rule {
+    glob: Glob,
+    /// `!pattern`: re-include what an earlier rule ignored
+    negated: bool,
+    /// Directory the pattern is relative to
+    base: PathBuf,
+    /// File the pattern came from
+    source: PathBuf,
+}
+
+/// Why `file_path` must never be formatted, if it is a lockfile or other
+/// tool-managed file: the built-in table, then the config's `[protected]`
+/// table
+pub fn protected_reason(file_path: &Path, config: Option<&Config>) -> Option<String> {
+    let name = file_path.file_name()?;
+    PROTECTED_FILES
+        .iter()
+        .find(|(pattern, _)| {
+            Glob::new(pattern).is_ok_and(|glob| glob.matches(Path::new(name), false))
+        })
+        .map(|(_, reason)| reason.to_string())
+        .or_else(|| config?.protected_reason(file_path).map(str::to_string))
+}
+
+/// Why `file_path` should not be formatted: it is ignored by an ignore file
+/// or formatter setting, vendored, generated, or turned off with the pragma
+pub fn skip_reason(file_path: &Path, skip: &Skip) -> Option<String> {
+    ignored_by(file_path)
+        .or_else(|| vendored(file_path, skip))
+        .or_else(|| {
+            let mut head = Vec::new();
+            fs::File::open(file_path)
+                .ok()?
+                .take(HEADER_BYTES)
+                .read_to_end(&mut head)
+                .ok()?;
+            content_skip_reason(&String::from_utf8_lossy(&head), skip)
+        })
+}
+
+/// Why `content` should not be formatted, if it is generated or turns
+/// formatting off
+pub fn content_skip_reason(content: &str, skip: &Skip) -> Option<String> {
+    if has_pragma(content) {
+        return Some(format!("the file contains '{}'", PRAGMA));
+    }
+    if skip.generated.unwrap_or(true) {
+        return generated_marker(content)
+            .map(|marker| format!("generated file (its header says '{}')", marker));
+    }
+    None
+}
+
+/// Whether the pragma appears near the top of `content`
+pub fn has_pragma(content: &str) -> bool {
+    content
+        .lines()
+        .take(HEADER_LINES)
+        .any(|line| line.contains(PRAGMA))
+}
+
+/// The generated-code marker near the top of `content`: `@generated`, or
+/// Go's `Code generated ... DO NOT EDIT.` convention
+fn generated_marker(content: &str) -> Option<&'static str> {
+    content.lines().take(HEADER_LINES).find_map(|line| {
+        if line.contains("@generated") {
+            Some("@generated")
+        } else if line.contains("Code generated ") && line.trim_end().ends_with("DO NOT EDIT.") {
+            Some("Code generated ... DO NOT EDIT.")
+        } else {
+            None
+        }
+    })
+}
+
+/// The vendored or build directory `file_path` is in, relative to the
+/// project. Directories above the project don't count.
+fn vendored(file_path: &Path, skip: &Skip) -> Option<String> {
+    let default = VENDORED_DIRS.map(str::to_string);
+    let directories = skip.directories.as_deref().unwrap_or(&default);
+
+    let root = ignore_root(file_path)?;
+    let relative = file_path.parent()?.strip_prefix(&root).ok()?;
+    relative.components().find_map(|component| {
+        let name = component.as_os_str().to_str()?;
+        directories
+            .iter()
+            .any(|dir| dir == name)
+            .then(|| format!("vendored or build output ({}/)", name))
+    })
+}
+
+/// The ignore file and pattern that exclude `file_path`
+fn ignored_by(file_path: &Path) -> Option<String> {
+    let dir = file_path.parent()?;
+    let root = ignore_root(file_path)?;
+    let ext = file_path.extension().and_then(|e| e.to_str()).unwrap_or("");
+
+    // Each source is evaluated on its own, so a negation in one can't
+    // re-include what another ignores
+    let mut sources: Vec<Vec<Rule>> = Vec::new();
+
+    for name in NESTED_IGNORE_FILES {
+        let rules = dir
+            .ancestors()
+            .take_while(|d| d.starts_with(&root))
+            .collect::<Vec<_>>()
+            .into_iter()
+            .rev()
+            .flat_map(|d| read_rules(&d.join(name)))
+            .collect();
+        sources.push(rules);
+    }
+
+    // A formatter's settings only speak for the files it formats
+    if PRETTIER_EXTENSIONS.contains(&ext) {
+        if let Some(prettierignore) = nearest(dir, &root, &[".prettierignore"]) {
+            sources.push(read_rules(&prettierignore));
+        }
+    }
+    if BIOME_EXTENSIONS.contains(&ext) {
+        if let Some(biome) = nearest(dir, &root, &["biome.json", "biome.jsonc"]) {
+            sources.push(biome_rules(&biome));
+        }
+    }
+    if DENO_EXTENSIONS.contains(&ext) {
+        if let Some(deno) = nearest(dir, &root, &["deno.json", "deno.jsonc"]) {
+            sources.push(deno_rules(&deno));
+        }
+    }
+    if ext == "rs" {
+        if let Some(rustfmt) = nearest(dir, &root, &["rustfmt.toml", ".rustfmt.toml"]) {
+            sources.push(rustfmt_rules(&rustfmt));
+        }
+    }
+
+    sources.iter().find_map(|rules| {
+        let rule = ignoring_rule(rules, file_path)?;
+        Some(format!(
+            "matches '{}' in {}",
+            rule.glob.pattern,
+            rule.source.display()
+        ))
+    })
+}
+
+/// Where nested ignore files start: the repository containing the project
+/// root, or the project root itself outside a repository. A Deno project
+/// may have no other marker than its deno.json.
+fn ignore_root(file_path: &Path) -> Option<PathBuf> {
+    let root = find_project_root(file_path).or_else(|| find_deno_root(file_path))?;
+    let repository = root.ancestors().find(|dir| dir.join(".git").exists());
+    Some(repository.map_or(root.clone(), Path::to_path_buf))
+}
+
+/// The nearest of `names` in `dir` or its ancestors, up to `root`
+fn nearest(dir: &Path, root: &Path, names: &[&str]) -> Option<PathBuf> {
+    dir.ancestors()
+        .take_while(|d| d.starts_with(root))
+        .flat_map(|d| names.iter().map(move |name| d.join(name)))
+        .find(|path| path.is_file())
+}
+
+/// The rule that ignores `file_path` or one of its directories. As in git, a
+/// file in an ignored directory stays ignored whatever later rules say.
+fn ignoring_rule<'a>(rules: &'a [Rule], file_path: &Path) -> Option<&'a Rule> {
+    let mut paths: Vec<(&Path, bool)> = file_path
+        .ancestors()
+        .skip(1)
+        .map(|dir| (dir, true))
+        .collect();
+    paths.reverse();
+    paths.push((file_path, false));
+
+    paths.into_iter().find_map(|(path, is_dir)| {
+        // The last matching rule wins
+        let rule = rules.iter().rev().find(|rule| {
+            path.strip_prefix(&rule.base).is_ok_and(|relative| {
+                !relative.as_os_str().is_empty() && rule.glob.matches(relative, is_dir)
+            })
+        })?;
+        (!rule.negated).then_some(rule)
+    })
+}
+
+/// Rules from a gitignore-syntax file, empty if it does not exist
+fn read_rules(ignore_file: &Path) -> Vec<Rule> {
+    let Ok(content) = fs::read_to_string(ignore_file) else {
+        return Vec::new();
+    };
+    let base = ignore_file.parent().unwrap_or(Path::new("."));
+
+    content
+        .lines()
+        .filter_map(|line| {
+            let line = line.trim_end();
+            if line.is_empty() || line.starts_with('#') {
+                return None;
+            }
+            let (negated, pattern) = match line.strip_prefix('!') {
+                Some(pattern) => (true, pattern),
+                None => (false, line),
+            };
+            // `\#` and `\!` start patterns with a literal `#` or `!`
+            let pattern = pattern
+                .strip_prefix('\\')
+                .filter(|p| p.starts_with(['#', '!']))
+                .unwrap_or(pattern);
+            rule(pattern, negated, base, ignore_file)
+        })
+        .collect()
+}
+
+fn rule(pattern: &str, negated: bool, base: &Path, source: &Path) -> Option<Rule> {
+    Some(Rule {
+        // Patterns this matcher can't read are left out rather than guessed
+        glob: Glob::new(pattern).ok()?,
+        negated,
+        base: base.to_path_buf(),
+        source: source.to_path_buf(),
+    })
+}
+
+/// Paths biome leaves alone: `files.ignore` and `formatter.ignore` (biome 1),
+/// or `!` entries in `files.includes` and `formatter.includes` (biome 2)
+fn biome_rules(config_path: &Path) -> Vec<Rule> {
+    let Some(config) = fs::read_to_string(config_path)
+        .ok()
+        .and_then(|content| json::parse_jsonc(&content))
+    else {
+        return Vec::new();
+    };
+    let base = config_path.parent().unwrap_or(Path::new("."));
+
+    let mut rules = Vec::new();
+    for section in ["files", "formatter"] {
+        let patterns = |key: &str| -> Vec<String> {
+            match config.get(section).and_then(|s| s.get(key)) {
+                Some(Value::Array(items)) => items
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .map(str::to_string)
+                    .collect(),
+                _ => Vec::new(),
+            }
+        };
+
+        for pattern in patterns("ignore") {
+            rules.extend(rule(&pattern, false, base, config_path));
+        }
+        for pattern in patterns("includes") {
+            if let Some(excluded) = pattern.strip_prefix('!') {
+                let excluded = excluded.trim_start_matches('!');
+                rules.extend(rule(excluded, false, base, config_path));
+            }
+        }
+    }
+    rules
+}
+
+/// Paths `deno fmt` leaves alone: `exclude` and `fmt.exclude`, where a `!`
+/// entry re-includes a path
+fn deno_rules(config_path: &Path) -> Vec<Rule> {
+    let Some(config) = fs::read_to_string(config_path)
+        .ok()
+        .and_then(|content| json::parse_jsonc(&content))
+    else {
+        return Vec::new();
+    };
+    let base = config_path.parent().unwrap_or(Path::new("."));
+
+    [
+        config.get("exclude"),
+        config.get("fmt").and_then(|fmt| fmt.get("exclude")),
+    ]
+    .into_iter()
+    .flat_map(|exclude| match exclude {
+        Some(Value::Array(items)) => items.iter().filter_map(Value::as_str).collect(),
+        _ => Vec::new(),
+    })
+    // Paths and globs relative to the config file
+    .filter_map(|entry| {
+        let (negated, path) = match entry.strip_prefix('!') {
+            Some(path) => (true, path),
+            None => (false, entry),
+        };
+        let anchored = format!("/{}", path.trim_start_matches("./"));
+        let mut rule = rule(&anchored, negated, base, config_path)?;
+        rule.glob.pattern = entry.to_string();
+        Some(rule)
+    })
+    .collect()
+}
+
+/// Files and directories listed in rustfmt's `ignore` option
+fn rustfmt_rules(config_path: &Path) -> Vec<Rule> {
+    let Some(config) = fs::read_to_string(config_path)
+        .ok()
+        .and_then(|content| toml::parse(&content).ok())
+    else {
+        return Vec::new();
+    };
+    let base = config_path.parent().unwrap_or(Path::new("."));
+
+    let Some(Value::Array(paths)) = config.get("ignore") else {
+        return Vec::new();
+    };
+    paths
+        .iter()
+        .filter_map(Value::as_str)
+        // Paths relative to the config file, not patterns matched at any depth
+        .filter_map(|path| {
+            let anchored = format!("/{}", path.trim_start_matches("./"));
+            let mut rule = rule(&anchored, false, base, config_path)?;
+            rule.glob.pattern = path.to_string();
+            Some(rule)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn setup(files: &[(&str, &str)]) -> TempDir {
+        let temp_dir = TempDir::new().unwrap();
+        for (path, content) in files {
+            let path = temp_dir.path().join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+        temp_dir
+    }
+
+    #[test]
+    fn test_gitignore_nested_and_negated() {
+        let temp_dir = setup(&[
+            (".git/HEAD", ""),
+            (
+                ".gitignore",
+                "# build output\ndist/\n*.gen.ts\n!keep.gen.ts\n",
+            ),
+            ("packages/a/package.json", "{}"),
+            ("packages/a/.gitignore", "/local.js\n"),
+        ]);
+        let root = temp_dir.path();
+        let reason = |path: &str| skip_reason(&root.join(path), &Skip::default());
+
+        assert_eq!(
+            reason("packages/a/dist/index.js"),
+            Some(format!(
+                "matches 'dist/' in {}",
+                root.join(".gitignore").display()
+            ))
+        );
+        assert!(reason("src/a.gen.ts").is_some());
+        assert!(reason("src/keep.gen.ts").is_none());
+        assert!(reason("packages/a/local.js").is_some());
+        assert!(reason("packages/a/src/local.js").is_none());
+        assert!(reason("src/index.ts").is_none());
+    }
+
+    #[test]
+    fn test_ignored_directory_cannot_be_reincluded() {
+        let temp_dir = setup(&[
+            (".git/HEAD", ""),
+            (IGNORE_FILE, "legacy\n!legacy/keep.py\n"),
+        ]);
+        assert!(skip_reason(&temp_dir.path().join("legacy/keep.py"), &Skip::default()).is_some());
+    }
+
+    #[test]
+    fn test_formatter_ignore_settings() {
+        let temp_dir = setup(&[
+            ("package.json", "{}"),
+            (".prettierignore", "*.min.js\n"),
+            (
+                "biome.jsonc",
+                "{\n  // biome 2\n  \"files\": { \"includes\": [\"**\", \"!generated/**\"] },\n  \"formatter\": { \"ignore\": [\"fixtures\"] },\n}\n",
+            ),
+            (
+                "rustfmt.toml",
+                "ignore = [\"src/bindings.rs\", \"examples\"]\n",
+            ),
+        ]);
+        let root = temp_dir.path();
+        let reason = |path: &str| skip_reason(&root.join(path), &Skip::default());
+
+        assert!(reason("lib/a.min.js").is_some());
+        assert!(reason("generated/types.ts").is_some());
+        assert!(reason("test/fixtures/a.ts").is_some());
+        assert_eq!(
+            reason("src/bindings.rs"),
+            Some(format!(
+                "matches 'src/bindings.rs' in {}",
+                root.join("rustfmt.toml").display()
+            ))
+        );
+        assert!(reason("examples/demo.rs").is_some());
+        assert!(reason("lib/examples/demo.rs").is_none());
+        assert!(reason("lib/a.js").is_none());
+    }
+
+    #[test]
+    fn test_pragma() {
+        let temp_dir = setup(&[("a.py", "# ralph-hook-fmt: off\nx=1\n")]);
+        assert_eq!(
+            skip_reason(&temp_dir.path().join("a.py"), &Skip::default()),
+            Some("the file contains 'ralph-hook-fmt: off'".to_string())
+        );
+        assert!(content_skip_reason("// ralph-hook-fmt: off\n", &Skip::default()).is_some());
+        assert!(
+            content_skip_reason(
+                &format!("{}// ralph-hook-fmt: off\n", "\n".repeat(5)),
+                &Skip::default()
+            )
+            .is_none()
+        );
+    }
+
+    #[test]
+    fn test_vendored_directories() {
+        let temp_dir = setup(&[("go.mod", "module m\n")]);
+        let root = temp_dir.path();
+        let skip = Skip::default();
+
+        assert_eq!(
+            skip_reason(&root.join("vendor/github.com/x/a.go"), &skip),
+            Some("vendored or build output (vendor/)".to_string())
+        );
+        assert!(skip_reason(&root.join("pkg/target/a.go"), &skip).is_some());
+        assert!(skip_reason(&root.join("pkg/vendors/a.go"), &skip).is_none());
+
+        let custom = Skip {
+            generated: None,
+            directories: Some(vec!["third_party".to_string()]),
+        };
+        assert!(skip_reason(&root.join("vendor/a.go"), &custom).is_none());
+        assert!(skip_reason(&root.join("third_party/a.go"), &custom).is_some());
+    }
+
+    #[test]
+    fn test_generated_headers() {
+        let skip = Skip::default();
+        assert_eq!(
+            content_skip_reason(
+                "// Code generated by protoc-gen-go. DO NOT EDIT.\npackage pb\n",
+                &skip
+            ),
+            Some("generated file (its header says 'Code generated ... DO NOT EDIT.')".to_string())
+        );
+        assert!(content_skip_reason("# @generated by tool\n", &skip).is_some());
+        assert!(content_skip_reason("// Code generated here, edit freely\n", &skip).is_none());
+
+        let off = Skip {
+            generated: Some(false),
+            directories: None,
+        };
+        assert!(content_skip_reason("# @generated by tool\n", &off).is_none());
+    }
+
+    #[test]
+    fn test_protected_files() {
+        let reason = |path: &str| protected_reason(Path::new(path), None);
+        assert_eq!(
+            reason("/repo/pnpm-lock.yaml"),
+            Some("lockfile written by pnpm".to_string())
+        );
+        assert!(reason("/repo/packages/a/package-lock.json").is_some());
+        assert!(reason("/repo/infra/.terraform.lock.hcl").is_some());
+        assert!(reason("/repo/tsconfig.app.tsbuildinfo").is_some());
+        assert!(reason("/repo/deno.json").is_none());
+        assert!(reason("/repo/locks.yaml").is_none());
+    }
+
+    #[test]
+    fn test_deno_fmt_exclude() {
+        let temp_dir = setup(&[(
+            "deno.jsonc",
+            "{\n  // generated code\n  \"exclude\": [\"./vendor_js/\", \"scripts\"],\n  \"fmt\": {\"exclude\": [\"testdata\", \"**/*.gen.ts\", \"!src/keep.gen.ts\"]}\n}\n",
+        )]);
+        let root = temp_dir.path();
+        let reason = |path: &str| skip_reason(&root.join(path), &Skip::default());
+
+        assert_eq!(
+            reason("testdata/a.ts"),
+            Some(format!(
+                "matches 'testdata' in {}",
+                root.join("deno.jsonc").display()
+            ))
+        );
+        assert!(reason("vendor_js/a.ts").is_some());
+        assert!(reason("src/api.gen.ts").is_some());
+        assert!(reason("src/keep.gen.ts").is_none());
+        assert!(reason("src/testdata/a.ts").is_none());
+
+        // deno fmt never formats Python or Go, so its excludes don't apply
+        assert!(reason("scripts/a.ts").is_some());
+        assert!(reason("scripts/foo.py").is_none());
+        assert!(reason("scripts/main.go").is_none());
+    }
+
+    #[test]
+    fn test_formatter_settings_above_the_repository_are_ignored() {
+        let temp_dir = setup(&[
+            (".prettierignore", "*.ts\n"),
+            ("biome.json", "{\"files\": {\"ignore\": [\"src\"]}}"),
+            ("repo/.git/HEAD", ""),
+        ]);
+        let root = temp_dir.path();
+
+        assert!(skip_reason(&root.join("repo/src/a.ts"), &Skip::default()).is_none());
+        assert!(skip_reason(&root.join("outside/src/a.ts"), &Skip::default()).is_none());
+    }
+}
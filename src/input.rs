@@ -0,0 +1,280 @@
+use std::fs;
+use std::path::PathBuf;
+
+use crate::json::{self, Value};
+
+/// Tools whose output files are formatted
+const SUPPORTED_TOOLS: [&str; 4] = ["Write", "Edit", "MultiEdit", "NotebookEdit"];
+
+/// Typed view of the JSON payload Claude Code sends to hooks on stdin
+#[derive(Debug)]
+#[allow(dead_code)]
+pub struct HookInput {
+    pub session_id: Option<String>,
+    pub transcript_path: Option<PathBuf>,
+    pub cwd: Option<PathBuf>,
+    pub hook_event_name: Option<String>,
+    pub tool_name: Option<String>,
+    pub tool_input: Value,
+    pub tool_response: Value,
+    /// Stop only: Claude is already continuing because of a Stop hook
+    pub stop_hook_active: bool,
+}
+
+impl HookInput {
+    /// Parse hook input JSON; the payload must be a JSON object
+    pub fn parse(input: &str) -> Option<Self> {
+        let value = json::parse(input)?;
+        let Value::Object(_) = value else {
+            return None;
+        };
+
+        let string = |key: &str| value.get(key).and_then(Value::as_str).map(str::to_string);
+        let object = |key: &str| value.get(key).cloned().unwrap_or(Value::Null);
+
+        Some(Self {
+            session_id: string("session_id"),
+            transcript_path: string("transcript_path").map(PathBuf::from),
+            cwd: string("cwd").map(PathBuf::from),
+            hook_event_name: string("hook_event_name"),
+            tool_name: string("tool_name"),
+            tool_input: object("tool_input"),
+            tool_response: object("tool_response"),
+            stop_hook_active: value
+                .get("stop_hook_active")
+                .and_then(Value::as_bool)
+                .unwrap_or(false),
+        })
+    }
+
+    /// Whether the payload comes from a tool this hook formats after
+    pub fn is_supported_tool(&self) -> bool {
+        match self.tool_name.as_deref() {
+            Some(name) => SUPPORTED_TOOLS.contains(&name),
+            None => true,
+        }
+    }
+
+    /// Path of the file the tool wrote
+    /// Looks for: {"tool_input": {"file_path": "..."}} (Write, Edit, MultiEdit) or
+    /// {"tool_input": {"notebook_path": "..."}} (NotebookEdit), falling back to
+    /// {"tool_response": {"filePath": "..."}}
+    pub fn file_path(&self) -> Option<PathBuf> {
+        let from_input = self
+            .tool_input
+            .get("file_path")
+            .or_else(|| self.tool_input.get("notebook_path"))
+            .and_then(Value::as_str);
+        let from_response = self.tool_response.get("filePath").and_then(Value::as_str);
+
+        from_input
+            .or(from_response)
+            .filter(|path| !path.is_empty())
+            .map(PathBuf::from)
+    }
+
+    /// Absolute, canonical form of [`Self::file_path`].
+    /// Relative paths are anchored on the payload's `cwd`, not the hook
+    /// process's working directory.
+    pub fn resolved_file_path(&self) -> Option<PathBuf> {
+        let path = self.file_path()?;
+
+        let absolute = match &self.cwd {
+            Some(cwd) if path.is_relative() => cwd.join(path),
+            _ => path,
+        };
+
+        // Canonicalization fails for missing files; keep the joined path so
+        // the caller can report it
+        Some(fs::canonicalize(&absolute).unwrap_or(absolute))
+    }
+
+    /// Whether the tool reported a failure, in which case nothing was written
+    pub fn tool_failed(&self) -> bool {
+        self.tool_response.get("success").and_then(Value::as_bool) == Some(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_full_payload() {
+        let input = r#"{
+            "session_id": "abc123",
+            "transcript_path": "/tmp/transcript.jsonl",
+            "cwd": "/work",
+            "hook_event_name": "PostToolUse",
+            "tool_name": "Write",
+            "tool_input": {"file_path": "/work/a.rs", "content": "fn main() {}"},
+            "tool_response": {"filePath": "/work/a.rs", "success": true}
+        }"#;
+        let hook = HookInput::parse(input).unwrap();
+        assert_eq!(hook.session_id.as_deref(), Some("abc123"));
+        assert_eq!(
+            hook.transcript_path,
+            Some(PathBuf::from("/tmp/transcript.jsonl"))
+        );
+        assert_eq!(hook.cwd, Some(PathBuf::from("/work")));
+        assert_eq!(hook.hook_event_name.as_deref(), Some("PostToolUse"));
+        assert_eq!(hook.tool_name.as_deref(), Some("Write"));
+        assert_eq!(hook.file_path(), Some(PathBuf::from("/work/a.rs")));
+        assert!(!hook.tool_failed());
+    }
+
+    #[test]
+    fn test_file_path_write() {
+        let input = r#"{
+            "tool_name": "Write",
+            "tool_input": {
+                "file_path": "/path/to/file.rs",
+                "content": "fn main() {}"
+            }
+        }"#;
+        let path = HookInput::parse(input).unwrap().file_path().unwrap();
+        assert_eq!(path, PathBuf::from("/path/to/file.rs"));
+    }
+
+    #[test]
+    fn test_file_path_edit() {
+        let input = r#"{
+            "tool_name": "Edit",
+            "tool_input": {
+                "file_path": "/path/to/file.py",
+                "old_string": "foo",
+                "new_string": "bar"
+            }
+        }"#;
+        let path = HookInput::parse(input).unwrap().file_path().unwrap();
+        assert_eq!(path, PathBuf::from("/path/to/file.py"));
+    }
+
+    #[test]
+    fn test_file_path_ignores_key_inside_content() {
+        let input = r#"{
+            "tool_name": "Write",
+            "tool_input": {
+                "content": "{\"file_path\": \"/wrong.rs\"}",
+                "file_path": "/right.rs"
+            }
+        }"#;
+        let path = HookInput::parse(input).unwrap().file_path().unwrap();
+        assert_eq!(path, PathBuf::from("/right.rs"));
+    }
+
+    #[test]
+    fn test_file_path_multi_edit() {
+        let input = r#"{
+            "tool_name": "MultiEdit",
+            "tool_input": {
+                "file_path": "/path/to/file.ts",
+                "edits": [{"old_string": "a", "new_string": "b"}]
+            }
+        }"#;
+        let hook = HookInput::parse(input).unwrap();
+        assert!(hook.is_supported_tool());
+        assert_eq!(hook.file_path(), Some(PathBuf::from("/path/to/file.ts")));
+    }
+
+    #[test]
+    fn test_file_path_notebook_edit() {
+        let input = r#"{
+            "tool_name": "NotebookEdit",
+            "tool_input": {
+                "notebook_path": "/path/to/analysis.ipynb",
+                "cell_id": "abc",
+                "new_source": "x=1"
+            }
+        }"#;
+        let hook = HookInput::parse(input).unwrap();
+        assert!(hook.is_supported_tool());
+        assert_eq!(
+            hook.file_path(),
+            Some(PathBuf::from("/path/to/analysis.ipynb"))
+        );
+    }
+
+    #[test]
+    fn test_unsupported_tool() {
+        let input = r#"{"tool_name": "Bash", "tool_input": {"command": "ls"}}"#;
+        assert!(!HookInput::parse(input).unwrap().is_supported_tool());
+    }
+
+    #[test]
+    fn test_file_path_missing() {
+        let input = r#"{
+            "tool_name": "Write",
+            "tool_input": {}
+        }"#;
+        assert!(HookInput::parse(input).unwrap().file_path().is_none());
+    }
+
+    #[test]
+    fn test_file_path_empty() {
+        let input = r#"{"tool_input": {"file_path": ""}}"#;
+        assert!(HookInput::parse(input).unwrap().file_path().is_none());
+    }
+
+    #[test]
+    fn test_parse_invalid_json() {
+        assert!(HookInput::parse("not valid json").is_none());
+        assert!(HookInput::parse("[1, 2]").is_none());
+    }
+
+    #[test]
+    fn test_file_path_with_spaces() {
+        let input = r#"{"tool_input": {"file_path": "/path/with spaces/file.rs"}}"#;
+        let path = HookInput::parse(input).unwrap().file_path().unwrap();
+        assert_eq!(path, PathBuf::from("/path/with spaces/file.rs"));
+    }
+
+    #[test]
+    fn test_file_path_json_escapes() {
+        let input = r#"{"tool_input": {"file_path": "\/tmp\/caf\u00e9 \ud83d\ude00\\x\"y\nz.rs"}}"#;
+        let path = HookInput::parse(input).unwrap().file_path().unwrap();
+        assert_eq!(path, PathBuf::from("/tmp/café 😀\\x\"y\nz.rs"));
+    }
+
+    #[test]
+    fn test_file_path_compact_json() {
+        let input = r#"{"tool_input":{"file_path":"/path/to/file.rs"}}"#;
+        let path = HookInput::parse(input).unwrap().file_path().unwrap();
+        assert_eq!(path, PathBuf::from("/path/to/file.rs"));
+    }
+
+    #[test]
+    fn test_resolved_file_path_uses_cwd() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let src_dir = temp_dir.path().join("src");
+        fs::create_dir_all(&src_dir).unwrap();
+        fs::write(src_dir.join("main.rs"), "fn main() {}").unwrap();
+
+        let input = format!(
+            r#"{{"cwd": "{}", "tool_input": {{"file_path": "src/../src/main.rs"}}}}"#,
+            temp_dir.path().display()
+        );
+        let path = HookInput::parse(&input)
+            .unwrap()
+            .resolved_file_path()
+            .unwrap();
+        assert_eq!(path, fs::canonicalize(src_dir.join("main.rs")).unwrap());
+    }
+
+    #[test]
+    fn test_resolved_file_path_keeps_missing_path() {
+        let input = r#"{"cwd": "/work", "tool_input": {"file_path": "missing.rs"}}"#;
+        let path = HookInput::parse(input)
+            .unwrap()
+            .resolved_file_path()
+            .unwrap();
+        assert_eq!(path, PathBuf::from("/work/missing.rs"));
+    }
+
+    #[test]
+    fn test_tool_failed() {
+        let input =
+            r#"{"tool_input": {"file_path": "/a.rs"}, "tool_response": {"success": false}}"#;
+        assert!(HookInput::parse(input).unwrap().tool_failed());
+    }
+}
@@ -0,0 +1,658 @@
+use std::fmt;
+use std::ops::Range;
+
+/// Minimal JSON value, parsed without external dependencies.
+///
+/// Objects keep their keys in document order and numbers keep their original
+/// lexeme, so a parsed document can be written back without surprises.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Number(String),
+    String(String),
+    Array(Vec<Value>),
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    /// Look up a key in an object (the first occurrence wins)
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Object(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    /// Mutable lookup of a key in an object
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut Value> {
+        match self {
+            Value::Object(entries) => entries.iter_mut().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    /// Serialize with one entry per line, indenting each level by `indent`.
+    /// Empty arrays and objects stay on one line, matching Python's `json.dumps`.
+    pub fn to_pretty_string(&self, indent: &str) -> String {
+        let mut out = String::new();
+        write_value(&mut out, self, Some(indent), 0);
+        out
+    }
+}
+
+/// Compact serialization with no whitespace between tokens
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut out = String::new();
+        write_value(&mut out, self, None, 0);
+        f.write_str(&out)
+    }
+}
+
+fn write_value(out: &mut String, value: &Value, indent: Option<&str>, level: usize) {
+    let newline = |out: &mut String, level: usize| {
+        if let Some(indent) = indent {
+            out.push('\n');
+            for _ in 0..level {
+                out.push_str(indent);
+            }
+        }
+    };
+
+    match value {
+        Value::Array(items) if !items.is_empty() => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                newline(out, level + 1);
+                write_value(out, item, indent, level + 1);
+            }
+            newline(out, level);
+            out.push(']');
+        }
+        Value::Object(entries) if !entries.is_empty() => {
+            out.push('{');
+            for (i, (key, item)) in entries.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                newline(out, level + 1);
+                write_string(out, key);
+                out.push_str(if indent.is_some() { ": " } else { ":" });
+                write_value(out, item, indent, level + 1);
+            }
+            newline(out, level);
+            out.push('}');
+        }
+        Value::Array(_) => out.push_str("[]"),
+        Value::Object(_) => out.push_str("{}"),
+        Value::Null => out.push_str("null"),
+        Value::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+        Value::Number(n) => out.push_str(n),
+        Value::String(s) => write_string(out, s),
+    }
+}
+
+/// Write a quoted JSON string, escaping quotes, backslashes and every
+/// control character below U+0020
+fn write_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '\u{08}' => out.push_str("\\b"),
+            '\u{0C}' => out.push_str("\\f"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// Maximum nesting depth accepted by the parser
+const MAX_DEPTH: usize = 256;
+
+/// Parse a complete JSON document
+pub fn parse(input: &str) -> Option<Value> {
+    let mut parser = Parser {
+        bytes: input.as_bytes(),
+        pos: 0,
+        depth: 0,
+    };
+    parser.skip_whitespace();
+    let value = parser.parse_value()?;
+    parser.skip_whitespace();
+
+    if parser.pos != parser.bytes.len() {
+        return None;
+    }
+
+    Some(value)
+}
+
+/// A step into a JSON document: an object key or an array index
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Key<'a> {
+    Name(&'a str),
+    Index(usize),
+}
+
+/// Byte range of the value at `path` in a JSON document, so it can be
+/// replaced without re-serializing the rest of the text. The first
+/// occurrence of a repeated key wins, as with [`Value::get`].
+pub fn span(input: &str, path: &[Key]) -> Option<Range<usize>> {
+    let mut parser = Parser {
+        bytes: input.as_bytes(),
+        pos: 0,
+        depth: 0,
+    };
+    parser.find(path)
+}
+
+/// Parse a JSON document that may contain comments and trailing commas,
+/// as used by tsconfig, biome and deno config files
+pub fn parse_jsonc(input: &str) -> Option<Value> {
+    parse(&strip_trailing_commas(&strip_comments(input)))
+}
+
+/// Replace `//` and `/* */` comments outside strings with spaces
+fn strip_comments(input: &str) -> String {
+    let mut out = String::with_capacity(input.len());
+    let mut chars = input.chars().peekable();
+    let mut in_string = false;
+
+    while let Some(c) = chars.next() {
+        if in_string {
+            out.push(c);
+            match c {
+                '\\' => out.extend(chars.next()),
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        match (c, chars.peek()) {
+            ('"', _) => {
+                in_string = true;
+                out.push(c);
+            }
+            ('/', Some('/')) => while chars.next_if(|c| *c != '\n').is_some() {},
+            ('/', Some('*')) => {
+                chars.next();
+                let mut previous = ' ';
+                for c in chars.by_ref() {
+                    if previous == '*' && c == '/' {
+                        break;
+                    }
+                    // Keep line breaks so positions stay on the same line
+                    if c == '\n' {
+                        out.push('\n');
+                    }
+                    previous = c;
+                }
+                out.push(' ');
+            }
+            _ => out.push(c),
+        }
+    }
+
+    out
+}
+
+/// Drop commas directly before a closing bracket or brace
+fn strip_trailing_commas(input: &str) -> String {
+    let mut out = String::with_capacity(input.len());
+    let mut in_string = false;
+    let mut escaped = false;
+    // Position in `out` of a comma that may turn out to be trailing
+    let mut pending: Option<usize> = None;
+
+    for c in input.chars() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            out.push(c);
+            continue;
+        }
+
+        match c {
+            '}' | ']' => {
+                if let Some(comma) = pending.take() {
+                    out.remove(comma);
+                }
+            }
+            c if c.is_whitespace() => {}
+            _ => pending = None,
+        }
+        match c {
+            ',' => pending = Some(out.len()),
+            '"' => in_string = true,
+            _ => {}
+        }
+        out.push(c);
+    }
+
+    out
+}
+
+struct Parser<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.peek() {
+            self.pos += 1;
+        }
+    }
+
+    fn expect_literal(&mut self, literal: &str) -> Option<()> {
+        let end = self.pos + literal.len();
+        if self.bytes.get(self.pos..end)? == literal.as_bytes() {
+            self.pos = end;
+            Some(())
+        } else {
+            None
+        }
+    }
+
+    fn parse_value(&mut self) -> Option<Value> {
+        match self.peek()? {
+            b'{' => self.parse_object(),
+            b'[' => self.parse_array(),
+            b'"' => self.parse_string().map(Value::String),
+            b't' => self.expect_literal("true").map(|_| Value::Bool(true)),
+            b'f' => self.expect_literal("false").map(|_| Value::Bool(false)),
+            b'n' => self.expect_literal("null").map(|_| Value::Null),
+            b'-' | b'0'..=b'9' => self.parse_number(),
+            _ => None,
+        }
+    }
+
+    fn enter(&mut self) -> Option<()> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            None
+        } else {
+            Some(())
+        }
+    }
+
+    fn parse_object(&mut self) -> Option<Value> {
+        self.enter()?;
+        self.pos += 1;
+        let mut entries = Vec::new();
+
+        self.skip_whitespace();
+        if self.peek()? == b'}' {
+            self.pos += 1;
+            self.depth -= 1;
+            return Some(Value::Object(entries));
+        }
+
+        loop {
+            self.skip_whitespace();
+            if self.peek()? != b'"' {
+                return None;
+            }
+            let key = self.parse_string()?;
+
+            self.skip_whitespace();
+            if self.peek()? != b':' {
+                return None;
+            }
+            self.pos += 1;
+
+            self.skip_whitespace();
+            let value = self.parse_value()?;
+            entries.push((key, value));
+
+            self.skip_whitespace();
+            match self.peek()? {
+                b',' => self.pos += 1,
+                b'}' => {
+                    self.pos += 1;
+                    self.depth -= 1;
+                    return Some(Value::Object(entries));
+                }
+                _ => return None,
+            }
+        }
+    }
+
+    fn parse_array(&mut self) -> Option<Value> {
+        self.enter()?;
+        self.pos += 1;
+        let mut items = Vec::new();
+
+        self.skip_whitespace();
+        if self.peek()? == b']' {
+            self.pos += 1;
+            self.depth -= 1;
+            return Some(Value::Array(items));
+        }
+
+        loop {
+            self.skip_whitespace();
+            items.push(self.parse_value()?);
+
+            self.skip_whitespace();
+            match self.peek()? {
+                b',' => self.pos += 1,
+                b']' => {
+                    self.pos += 1;
+                    self.depth -= 1;
+                    return Some(Value::Array(items));
+                }
+                _ => return None,
+            }
+        }
+    }
+
+    fn parse_number(&mut self) -> Option<Value> {
+        let start = self.pos;
+
+        if self.peek() == Some(b'-') {
+            self.pos += 1;
+        }
+
+        match self.peek()? {
+            b'0' => self.pos += 1,
+            b'1'..=b'9' => self.skip_digits(),
+            _ => return None,
+        }
+
+        if self.peek() == Some(b'.') {
+            self.pos += 1;
+            if !self.peek()?.is_ascii_digit() {
+                return None;
+            }
+            self.skip_digits();
+        }
+
+        if let Some(b'e' | b'E') = self.peek() {
+            self.pos += 1;
+            if let Some(b'+' | b'-') = self.peek() {
+                self.pos += 1;
+            }
+            if !self.peek()?.is_ascii_digit() {
+                return None;
+            }
+            self.skip_digits();
+        }
+
+        let lexeme = std::str::from_utf8(&self.bytes[start..self.pos]).ok()?;
+        Some(Value::Number(lexeme.to_string()))
+    }
+
+    /// Span of the value at `path` below the current position
+    fn find(&mut self, path: &[Key]) -> Option<Range<usize>> {
+        self.skip_whitespace();
+        let Some((first, rest)) = path.split_first() else {
+            let start = self.pos;
+            self.parse_value()?;
+            return Some(start..self.pos);
+        };
+
+        let close = match (first, self.peek()?) {
+            (Key::Name(_), b'{') => b'}',
+            (Key::Index(_), b'[') => b']',
+            _ => return None,
+        };
+        self.pos += 1;
+
+        for index in 0.. {
+            self.skip_whitespace();
+            if self.peek()? == close {
+                return None;
+            }
+            let found = match first {
+                Key::Name(name) => {
+                    if self.peek()? != b'"' {
+                        return None;
+                    }
+                    let key = self.parse_string()?;
+                    self.skip_whitespace();
+                    if self.peek()? != b':' {
+                        return None;
+                    }
+                    self.pos += 1;
+                    key == *name
+                }
+                Key::Index(i) => index == *i,
+            };
+            if found {
+                return self.find(rest);
+            }
+
+            self.skip_whitespace();
+            self.parse_value()?;
+            self.skip_whitespace();
+            if self.peek()? != b',' {
+                return None;
+            }
+            self.pos += 1;
+        }
+        None
+    }
+
+    fn skip_digits(&mut self) {
+        while self.peek().is_some_and(|b| b.is_ascii_digit()) {
+            self.pos += 1;
+        }
+    }
+
+    fn parse_string(&mut self) -> Option<String> {
+        // Skip the opening quote
+        self.pos += 1;
+        let mut out = String::new();
+
+        loop {
+            // Copy the run of plain characters up to the next quote or escape
+            let start = self.pos;
+            while let Some(b) = self.peek() {
+                if b == b'"' || b == b'\\' || b < 0x20 {
+                    break;
+                }
+                self.pos += 1;
+            }
+            out.push_str(std::str::from_utf8(&self.bytes[start..self.pos]).ok()?);
+
+            match self.peek()? {
+                b'"' => {
+                    self.pos += 1;
+                    return Some(out);
+                }
+                b'\\' => {
+                    self.pos += 1;
+                    let escape = self.peek()?;
+                    self.pos += 1;
+                    match escape {
+                        b'"' => out.push('"'),
+                        b'\\' => out.push('\\'),
+                        b'/' => out.push('/'),
+                        b'b' => out.push('\u{08}'),
+                        b'f' => out.push('\u{0C}'),
+                        b'n' => out.push('\n'),
+                        b'r' => out.push('\r'),
+                        b't' => out.push('\t'),
+                        b'u' => out.push(self.parse_unicode_escape()?),
+                        _ => return None,
+                    }
+                }
+                // Raw control characters are not allowed inside strings
+                _ => return None,
+            }
+        }
+    }
+
+    /// Decode the code point after `\u`, combining UTF-16 surrogate pairs.
+    /// Unpaired surrogates cannot be represented in a Rust string and are
+    /// replaced with U+FFFD.
+    fn parse_unicode_escape(&mut self) -> Option<char> {
+        let high = self.parse_hex4()?;
+
+        if !(0xD800..0xDC00).contains(&high) {
+            return Some(char::from_u32(high).unwrap_or(char::REPLACEMENT_CHARACTER));
+        }
+
+        // A high surrogate must be followed by `\u` and a low surrogate
+        if self.bytes.get(self.pos..self.pos + 2) == Some(b"\\u") {
+            let saved = self.pos;
+            self.pos += 2;
+            let low = self.parse_hex4()?;
+            if (0xDC00..0xE000).contains(&low) {
+                let code = 0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00);
+                return char::from_u32(code);
+            }
+            // Not a pair: leave the second escape to be decoded on its own
+            self.pos = saved;
+        }
+
+        Some(char::REPLACEMENT_CHARACTER)
+    }
+
+    fn parse_hex4(&mut self) -> Option<u32> {
+        let digits = self.bytes.get(self.pos..self.pos + 4)?;
+        let digits = std::str::from_utf8(digits).ok()?;
+        if !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return None;
+        }
+        self.pos += 4;
+        u32::from_str_radix(digits, 16).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_nested_object() {
+        let value = parse(r#"{"a": {"b": [1, -2.5e3, true, null]}, "c": "d"}"#).unwrap();
+        let b = value.get("a").and_then(|a| a.get("b")).unwrap();
+        assert_eq!(
+            b,
+            &Value::Array(vec![
+                Value::Number("1".to_string()),
+                Value::Number("-2.5e3".to_string()),
+                Value::Bool(true),
+                Value::Null,
+            ])
+        );
+        assert_eq!(value.get("c").and_then(Value::as_str), Some("d"));
+    }
+
+    #[test]
+    fn test_parse_keeps_key_order() {
+        let value = parse(r#"{"z": 1, "a": 2, "m": 3}"#).unwrap();
+        let Value::Object(entries) = value else {
+            panic!("expected object");
+        };
+        let keys: Vec<&str> = entries.iter().map(|(k, _)| k.as_str()).collect();
+        assert_eq!(keys, ["z", "a", "m"]);
+    }
+
+    #[test]
+    fn test_parse_string_escapes() {
+        let value = parse(r#""a\"b\\c\/d\n\té""#).unwrap();
+        assert_eq!(value.as_str(), Some("a\"b\\c/d\n\té"));
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid() {
+        assert!(parse("not valid json").is_none());
+        assert!(parse(r#"{"a": 1,}"#).is_none());
+        assert!(parse(r#"{"a": 1} trailing"#).is_none());
+        assert!(parse("\"raw\nnewline\"").is_none());
+        assert!(parse("01").is_none());
+        assert!(parse("").is_none());
+    }
+
+    #[test]
+    fn test_pretty_round_trip() {
+        let input = "{\n \"cells\": [],\n \"metadata\": {\n  \"n\": 1.50,\n  \"s\": \"a\\\"b\\u001b\\n\"\n }\n}";
+        let value = parse(input).unwrap();
+        assert_eq!(value.to_pretty_string(" "), input);
+    }
+
+    #[test]
+    fn test_compact_round_trip() {
+        let input = r#"{"a":[1,{"b":null}],"c":"\u0000\u001f\t","d":{},"e":[]}"#;
+        let value = parse(input).unwrap();
+        assert_eq!(value.to_string(), input);
+    }
+
+    #[test]
+    fn test_span() {
+        let input = r#"{"a": 1, "cells": [{"source": "x"}, {"n": 2, "source": ["y\u00e9"]}]}"#;
+        let at = |path: &[Key]| span(input, path).map(|range| &input[range]);
+        assert_eq!(
+            at(&[Key::Name("cells"), Key::Index(1), Key::Name("source")]),
+            Some(r#"["y\u00e9"]"#)
+        );
+        assert_eq!(at(&[Key::Name("a")]), Some("1"));
+        assert_eq!(at(&[Key::Name("cells"), Key::Index(2)]), None);
+        assert_eq!(at(&[Key::Index(0)]), None);
+    }
+
+    #[test]
+    fn test_parse_depth_limit() {
+        let deep = "[".repeat(MAX_DEPTH + 1) + &"]".repeat(MAX_DEPTH + 1);
+        assert!(parse(&deep).is_none());
+    }
+
+    #[test]
+    fn test_parse_jsonc() {
+        let value = parse_jsonc(
+            "{\n  // line comment\n  \"a\": \"//not a comment\", /* block\n */\n  \"b\": [1, 2,],\n  \"c\": \"x\\\",\",\n}\n",
+        )
+        .unwrap();
+        assert_eq!(
+            value.get("a").and_then(Value::as_str),
+            Some("//not a comment")
+        );
+        assert_eq!(
+            value.get("b"),
+            Some(&Value::Array(vec![
+                Value::Number("1".to_string()),
+                Value::Number("2".to_string()),
+            ]))
+        );
+        assert_eq!(value.get("c").and_then(Value::as_str), Some("x\","));
+        assert!(parse_jsonc("{\"a\": 1 /* unterminated }").is_none());
+        assert!(parse_jsonc("[1,,]").is_none());
+    }
+}
@@ -1,88 +1,53 @@
-mod extract;
+mod batch;
+mod cli;
+mod commands;
+mod config;
+mod diagnostics;
+mod diff;
+mod evidence;
+mod explain;
 mod format;
+mod glob;
+mod hook;
+mod ignore;
+mod input;
+mod json;
+mod notebook;
+mod output;
+mod package_json;
 mod project;
+mod toml;
+mod tool;
 
 use std::env;
-use std::io::{self, Read};
-
-use extract::extract_file_path;
-use format::format_file;
-
-fn main() {
-    let args: Vec<String> = env::args().collect();
-
-    // Handle --version flag
-    if args.iter().any(|a| a == "--version" || a == "-V") {
-        println!("{}", env!("CARGO_PKG_VERSION"));
-        return;
-    }
-
-    let debug = args.iter().any(|a| a == "--debug");
-    let project_only = args.iter().any(|a| a == "--project-only");
-
-    // Read JSON input from stdin
-    let mut input = String::new();
-    if io::stdin().read_to_string(&mut input).is_err() {
-        print_response(debug, true, "Failed to read input");
-        return;
-    }
-
-    // Extract file path from input
-    let file_path = match extract_file_path(&input) {
-        Some(path) => path,
-        None => {
-            print_response(debug, true, "Could not extract file path from input");
-            return;
+use std::process::ExitCode;
+
+use cli::Command;
+
+fn main() -> ExitCode {
+    match cli::parse(env::args_os().skip(1)) {
+        Ok(Command::Hook(options)) => {
+            for warning in &options.warnings {
+                eprintln!("warning: {}", warning);
+            }
+            hook::run(&options);
+            ExitCode::SUCCESS
+        }
+        Ok(Command::Fmt(options)) => commands::fmt(&options),
+        Ok(Command::Stdin(options)) => commands::stdin(&options),
+        Ok(Command::Explain(options)) => commands::explain(&options),
+        Ok(Command::Doctor(options)) => commands::doctor(&options),
+        Ok(Command::Help(usage)) => {
+            print!("{}", usage);
+            ExitCode::SUCCESS
+        }
+        Ok(Command::Version) => {
+            println!("{}", env!("CARGO_PKG_VERSION"));
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("error: {}\n\nRun 'ralph-hook-fmt --help' for usage.", e);
+            ExitCode::from(2)
         }
-    };
-
-    // Check if file exists
-    if !file_path.exists() {
-        print_response(
-            debug,
-            true,
-            &format!("File does not exist: {}", file_path.display()),
-        );
-        return;
-    }
-
-    // Format the file
-    let result = format_file(&file_path, project_only);
-
-    // Build the response message
-    let message = format!("[ralph-hook-fmt] {}", result.message);
-
-    print_response(debug, true, &message);
-}
-
-fn escape_json(message: &str) -> String {
-    message
-        .replace('\\', "\\\\")
-        .replace('"', "\\\"")
-        .replace('\n', "\\n")
-        .replace('\r', "\\r")
-        .replace('\t', "\\t")
-}
-
-fn print_response(debug: bool, continue_execution: bool, message: &str) {
-    if continue_execution && !debug {
-        println!("{{\"continue\":true}}");
-        return;
-    }
-
-    let escaped_message = escape_json(message);
-
-    if continue_execution {
-        println!(
-            r#"{{"continue":true,"systemMessage":"{}"}}"#,
-            escaped_message
-        );
-    } else if debug {
-        println!(
-            r#"{{"decision":"block","reason":"{}","systemMessage":"{}"}}"#,
-            escaped_message, escaped_message
-        );
-    } else {
-        println!(r#"{{"decision":"block","reason":"{}"}}"#, escaped_message);
     }
 }
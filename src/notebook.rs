@@ -0,0 +1,284 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::format::{FormatResult, format_python};
+use crate::ignore::has_pragma;
+use crate::json::{self, Key, Value};
+
+/// Format the Python code cells of a Jupyter notebook.
+///
+/// Each code cell is written to a scratch `.py` file next to the notebook (so
+/// project configuration still applies), run through the Python formatter and
+/// written back. Only the text of changed `source` values is replaced, so
+/// markdown cells, outputs and metadata stay byte for byte as they were.
+pub fn format_notebook(file_path: &Path, project_only: bool) -> FormatResult {
+    let content = match fs::read_to_string(file_path) {
+        Ok(content) => content,
+        Err(e) => return FormatResult::error("notebook", &e.to_string()),
+    };
+
+    let mut notebook = match json::parse(&content) {
+        Some(notebook) => notebook,
+        None => return FormatResult::error("notebook", "invalid notebook JSON"),
+    };
+
+    if let Some(language) = notebook_language(&notebook) {
+        if language != "python" {
+            return FormatResult::unsupported(&format!("ipynb ({})", language));
+        }
+    }
+
+    let scratch = scratch_path(file_path);
+    let result = format_cells(&mut notebook, &scratch, project_only);
+    let _ = fs::remove_file(&scratch);
+
+    let (formatter, changed) = match result {
+        Ok(Some(outcome)) => outcome,
+        Ok(None) => return FormatResult::no_formatter("Python notebook"),
+        Err(result) => return *result,
+    };
+
+    if !changed.is_empty() {
+        let Some(output) = splice_sources(&content, &notebook, &changed) else {
+            return FormatResult::error("notebook", "could not locate a cell source");
+        };
+        if let Err(e) = fs::write(file_path, output) {
+            return FormatResult::error("notebook", &e.to_string());
+        }
+    }
+
+    FormatResult::success(&formatter)
+}
+
+/// `content` with the `source` of each cell in `changed` replaced by its
+/// value in `notebook`, laid out like the rest of the file
+fn splice_sources(content: &str, notebook: &Value, changed: &[usize]) -> Option<String> {
+    let Some(Value::Array(cells)) = notebook.get("cells") else {
+        return None;
+    };
+    let indent = detect_indent(content);
+    let newline = if content.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+
+    let mut output = content.to_string();
+    // Back to front, so earlier spans stay valid
+    for &index in changed.iter().rev() {
+        let path = [Key::Name("cells"), Key::Index(index), Key::Name("source")];
+        let range = json::span(content, &path)?;
+        let source = cells.get(index)?.get("source")?;
+
+        // Nested lines are indented relative to the line the value starts on
+        let line_start = content[..range.start].rfind('\n').map_or(0, |i| i + 1);
+        let line_indent: String = content[line_start..]
+            .chars()
+            .take_while(|c| *c == ' ' || *c == '\t')
+            .collect();
+        let text = source
+            .to_pretty_string(&indent)
+            .replace('\n', &format!("{}{}", newline, line_indent));
+        output.replace_range(range, &text);
+    }
+    Some(output)
+}
+
+/// Format every code cell in place.
+/// Returns the formatter used and the indices of the cells that changed,
+/// `None` if the notebook had no code to format or no formatter was found.
+fn format_cells(
+    notebook: &mut Value,
+    scratch: &Path,
+    project_only: bool,
+) -> Result<Option<(String, Vec<usize>)>, Box<FormatResult>> {
+    let Some(Value::Array(cells)) = notebook.get_mut("cells") else {
+        return Err(Box::new(FormatResult::error(
+            "notebook",
+            "missing cells array",
+        )));
+    };
+
+    let mut formatter = None;
+    let mut changed = Vec::new();
+
+    for (index, cell) in cells.iter_mut().enumerate() {
+        if cell.get("cell_type").and_then(Value::as_str) != Some("code") {
+            continue;
+        }
+
+        let Some(source) = cell.get_mut("source") else {
+            continue;
+        };
+        let Some(code) = source_text(source) else {
+            continue;
+        };
+        // The pragma turns formatting off for a single cell
+        if code.trim().is_empty() || has_ipython_syntax(&code) || has_pragma(&code) {
+            continue;
+        }
+
+        fs::write(scratch, &code)
+            .map_err(|e| Box::new(FormatResult::error("notebook", &e.to_string())))?;
+
+        let result = format_python(scratch, project_only);
+        if !result.formatted {
+            return match result.formatter {
+                Some(_) => Err(Box::new(result)),
+                None => Ok(None),
+            };
+        }
+        formatter = result.formatter;
+
+        let formatted = fs::read_to_string(scratch)
+            .map_err(|e| Box::new(FormatResult::error("notebook", &e.to_string())))?;
+        // Cells conventionally have no trailing newline
+        let formatted = if code.ends_with('\n') {
+            formatted
+        } else {
+            formatted.trim_end_matches('\n').to_string()
+        };
+
+        if formatted != code {
+            set_source_text(source, &formatted);
+            changed.push(index);
+        }
+    }
+
+    Ok(formatter.map(|formatter| (formatter, changed)))
+}
+
+/// Kernel language declared in the notebook metadata
+fn notebook_language(notebook: &Value) -> Option<String> {
+    let metadata = notebook.get("metadata")?;
+    metadata
+        .get("kernelspec")
+        .and_then(|k| k.get("language"))
+        .or_else(|| metadata.get("language_info").and_then(|l| l.get("name")))
+        .and_then(Value::as_str)
+        .map(str::to_lowercase)
+}
+
+/// Cell source is either a single string or a list of lines
+fn source_text(source: &Value) -> Option<String> {
+    match source {
+        Value::String(s) => Some(s.clone()),
+        Value::Array(lines) => lines.iter().map(Value::as_str).collect(),
+        _ => None,
+    }
+}
+
+/// Write source back in the same shape it was read in
+fn set_source_text(source: &mut Value, text: &str) {
+    *source = match source {
+        Value::Array(_) => Value::Array(
+            text.split_inclusive('\n')
+                .map(|line| Value::String(line.to_string()))
+                .collect(),
+        ),
+        _ => Value::String(text.to_string()),
+    };
+}
+
+/// Magics, shell escapes and help queries are not valid Python
+fn has_ipython_syntax(code: &str) -> bool {
+    code.lines().map(str::trim_start).any(|line| {
+        line.starts_with('%')
+            || line.starts_with('!')
+            || (line.ends_with('?') && !line.starts_with('#'))
+    })
+}
+
+/// Indentation unit used by the notebook file, Jupyter writes one space
+fn detect_indent(content: &str) -> String {
+    content
+        .lines()
+        .nth(1)
+        .map(|line| {
+            line.chars()
+                .take_while(|c| *c == ' ' || *c == '\t')
+                .collect::<String>()
+        })
+        .filter(|indent| !indent.is_empty())
+        .unwrap_or_else(|| " ".to_string())
+}
+
+fn scratch_path(file_path: &Path) -> PathBuf {
+    let stem = file_path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    file_path.with_file_name(format!(
+        ".{}.ralph-hook-fmt-{}.py",
+        stem,
+        std::process::id()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_source_round_trip_keeps_shape() {
+        let mut lines = Value::Array(vec![
+            Value::String("x = 1\n".to_string()),
+            Value::String("y = 2".to_string()),
+        ]);
+        assert_eq!(source_text(&lines).unwrap(), "x = 1\ny = 2");
+
+        set_source_text(&mut lines, "x = 1\ny = 2\nz = 3");
+        assert_eq!(
+            lines,
+            Value::Array(vec![
+                Value::String("x = 1\n".to_string()),
+                Value::String("y = 2\n".to_string()),
+                Value::String("z = 3".to_string()),
+            ])
+        );
+
+        let mut single = Value::String("x=1".to_string());
+        set_source_text(&mut single, "x = 1");
+        assert_eq!(single, Value::String("x = 1".to_string()));
+    }
+
+    #[test]
+    fn test_splice_keeps_untouched_text() {
+        let content = "{\n \"cells\": [\n  {\n   \"cell_type\": \"markdown\",\n   \"source\": [\"caf\\u00e9 \\/ x=1\"]\n  },\n  {\n   \"cell_type\": \"code\",\n   \"execution_count\": 1.0e1,\n   \"source\": [\n    \"x=1\\n\",\n    \"y=2\"\n   ]\n  }\n ]\n}\n";
+        let mut notebook = json::parse(content).unwrap();
+        let Some(Value::Array(cells)) = notebook.get_mut("cells") else {
+            panic!("expected cells");
+        };
+        set_source_text(cells[1].get_mut("source").unwrap(), "x = 1\ny = 2\nz = 3");
+
+        assert_eq!(
+            splice_sources(content, &notebook, &[1]).unwrap(),
+            "{\n \"cells\": [\n  {\n   \"cell_type\": \"markdown\",\n   \"source\": [\"caf\\u00e9 \\/ x=1\"]\n  },\n  {\n   \"cell_type\": \"code\",\n   \"execution_count\": 1.0e1,\n   \"source\": [\n    \"x = 1\\n\",\n    \"y = 2\\n\",\n    \"z = 3\"\n   ]\n  }\n ]\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_has_ipython_syntax() {
+        assert!(has_ipython_syntax("%matplotlib inline\nx = 1"));
+        assert!(has_ipython_syntax("!pip install foo"));
+        assert!(has_ipython_syntax("len?"));
+        assert!(!has_ipython_syntax("x = 1\n# what?"));
+    }
+
+    #[test]
+    fn test_notebook_language() {
+        let notebook = json::parse(r#"{"metadata": {"kernelspec": {"language": "R"}}}"#).unwrap();
+        assert_eq!(notebook_language(&notebook).as_deref(), Some("r"));
+
+        let notebook =
+            json::parse(r#"{"metadata": {"language_info": {"name": "python"}}}"#).unwrap();
+        assert_eq!(notebook_language(&notebook).as_deref(), Some("python"));
+    }
+
+    #[test]
+    fn test_detect_indent() {
+        assert_eq!(detect_indent("{\n \"cells\": []\n}"), " ");
+        assert_eq!(detect_indent("{\n  \"cells\": []\n}"), "  ");
+        assert_eq!(detect_indent("{}"), " ");
+    }
+}
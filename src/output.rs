@@ -0,0 +1,174 @@
+use std::fmt;
+
+use crate::json::Value;
+
+/// Decision a hook can return to Claude Code
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Decision {
+    /// Feed `reason` back to Claude as feedback it has to address
+    Block,
+}
+
+impl Decision {
+    fn as_str(self) -> &'static str {
+        match self {
+            Decision::Block => "block",
+        }
+    }
+}
+
+/// Event-specific fields, serialized under `hookSpecificOutput`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HookSpecificOutput {
+    pub hook_event_name: String,
+    pub additional_context: Option<String>,
+    /// PreToolUse only: replacement for the tool's input
+    pub updated_input: Option<Value>,
+}
+
+/// Response printed to stdout for Claude Code, see
+/// https://docs.claude.com/en/docs/claude-code/hooks#advanced%3A-json-output
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HookOutput {
+    pub continue_execution: Option<bool>,
+    pub suppress_output: Option<bool>,
+    pub decision: Option<Decision>,
+    pub reason: Option<String>,
+    pub system_message: Option<String>,
+    pub hook_specific_output: Option<HookSpecificOutput>,
+}
+
+impl HookOutput {
+    /// Let Claude continue
+    pub fn proceed() -> Self {
+        Self {
+            continue_execution: Some(true),
+            ..Self::default()
+        }
+    }
+
+    /// Block and feed `reason` back to Claude
+    pub fn block(reason: &str) -> Self {
+        Self {
+            decision: Some(Decision::Block),
+            reason: Some(reason.to_string()),
+            ..Self::default()
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        let mut entries = Vec::new();
+        let string = |s: &str| Value::String(s.to_string());
+
+        if let Some(continue_execution) = self.continue_execution {
+            entries.push(("continue".to_string(), Value::Bool(continue_execution)));
+        }
+        if let Some(suppress_output) = self.suppress_output {
+            entries.push(("suppressOutput".to_string(), Value::Bool(suppress_output)));
+        }
+        if let Some(decision) = self.decision {
+            entries.push(("decision".to_string(), string(decision.as_str())));
+        }
+        if let Some(ref reason) = self.reason {
+            entries.push(("reason".to_string(), string(reason)));
+        }
+        if let Some(ref system_message) = self.system_message {
+            entries.push(("systemMessage".to_string(), string(system_message)));
+        }
+        if let Some(ref specific) = self.hook_specific_output {
+            let mut fields = vec![(
+                "hookEventName".to_string(),
+                string(&specific.hook_event_name),
+            )];
+            if let Some(ref context) = specific.additional_context {
+                fields.push(("additionalContext".to_string(), string(context)));
+            }
+            if let Some(ref updated_input) = specific.updated_input {
+                fields.push(("updatedInput".to_string(), updated_input.clone()));
+            }
+            entries.push(("hookSpecificOutput".to_string(), Value::Object(fields)));
+        }
+
+        Value::Object(entries)
+    }
+}
+
+impl fmt::Display for HookOutput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_json())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json;
+
+    #[test]
+    fn test_proceed_is_compact() {
+        assert_eq!(HookOutput::proceed().to_string(), r#"{"continue":true}"#);
+    }
+
+    #[test]
+    fn test_block_with_system_message() {
+        let output = HookOutput {
+            system_message: Some("bad".to_string()),
+            ..HookOutput::block("bad")
+        };
+        assert_eq!(
+            output.to_string(),
+            r#"{"decision":"block","reason":"bad","systemMessage":"bad"}"#
+        );
+    }
+
+    #[test]
+    fn test_all_fields() {
+        let output = HookOutput {
+            suppress_output: Some(true),
+            system_message: Some("msg".to_string()),
+            hook_specific_output: Some(HookSpecificOutput {
+                hook_event_name: "PostToolUse".to_string(),
+                additional_context: Some("ctx".to_string()),
+                ..HookSpecificOutput::default()
+            }),
+            ..HookOutput::proceed()
+        };
+        assert_eq!(
+            output.to_string(),
+            r#"{"continue":true,"suppressOutput":true,"systemMessage":"msg","hookSpecificOutput":{"hookEventName":"PostToolUse","additionalContext":"ctx"}}"#
+        );
+    }
+
+    #[test]
+    fn test_updated_input() {
+        let output = HookOutput {
+            hook_specific_output: Some(HookSpecificOutput {
+                hook_event_name: "PreToolUse".to_string(),
+                updated_input: json::parse(r#"{"file_path":"a.rs","content":"x"}"#),
+                ..HookSpecificOutput::default()
+            }),
+            ..HookOutput::proceed()
+        };
+        assert_eq!(
+            output.to_string(),
+            r#"{"continue":true,"hookSpecificOutput":{"hookEventName":"PreToolUse","updatedInput":{"file_path":"a.rs","content":"x"}}}"#
+        );
+    }
+
+    #[test]
+    fn test_control_characters_are_escaped() {
+        let message = "\u{1b}[31merror\u{1b}[0m\0\u{7}\r\n\t\"\\";
+        let output = HookOutput {
+            system_message: Some(message.to_string()),
+            ..HookOutput::proceed()
+        };
+        let serialized = output.to_string();
+        assert!(!serialized.chars().any(|c| (c as u32) < 0x20));
+
+        let parsed = json::parse(&serialized).unwrap();
+        assert_eq!(
+            parsed.get("systemMessage").and_then(json::Value::as_str),
+            Some(message)
+        );
+    }
+}
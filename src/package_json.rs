@@ -0,0 +1,262 @@
+use std::fs;
+use std::path::Path;
+
+use crate::format::FormatResult;
+use crate::glob::Glob;
+use crate::json;
+
+/// Name reported for the built-in package.json formatter
+pub const SAFE_MODE: &str = "package.json safe mode";
+
+/// Indentation npm writes when nothing else says otherwise
+const DEFAULT_INDENT: &str = "  ";
+
+/// Re-indent a package.json in place, see [`reindent`]
+pub fn format_package_json(file_path: &Path) -> FormatResult {
+    let content = match fs::read_to_string(file_path) {
+        Ok(content) => content,
+        Err(e) => return FormatResult::error(SAFE_MODE, &e.to_string()),
+    };
+
+    let output = match reindent(file_path, &content) {
+        Ok(output) => output,
+        Err(e) => return FormatResult::error(SAFE_MODE, &e),
+    };
+    if output != content {
+        if let Err(e) = fs::write(file_path, output) {
+            return FormatResult::error(SAFE_MODE, &e.to_string());
+        }
+    }
+
+    FormatResult::success(SAFE_MODE)
+}
+
+/// Normalize the whitespace of a package.json, one entry per line.
+///
+/// Only whitespace between tokens changes: keys keep their order and strings
+/// and numbers are copied as written. The indentation comes from
+/// `.editorconfig`, then the file itself, then npm's two spaces. CRLF line
+/// endings and the final newline are kept.
+pub fn reindent(file_path: &Path, content: &str) -> Result<String, String> {
+    if json::parse(content).is_none() {
+        return Err("invalid JSON".to_string());
+    }
+
+    let indent = editorconfig_indent(file_path)
+        .or_else(|| detect_indent(content))
+        .unwrap_or_else(|| DEFAULT_INDENT.to_string());
+    let newline = if content.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+
+    let mut output = reindent_tokens(content, &indent, newline);
+    if content.trim_end_matches([' ', '\t']).ends_with('\n') {
+        output.push_str(newline);
+    }
+    Ok(output)
+}
+
+/// Rewrite valid JSON with `indent` per level, copying tokens verbatim
+fn reindent_tokens(content: &str, indent: &str, newline: &str) -> String {
+    let mut out = String::with_capacity(content.len());
+    let mut level = 0;
+    // Just after `{` or `[`: the next token starts a new line unless it
+    // closes the container, so empty ones stay `{}` and `[]`
+    let mut opened = false;
+    let break_line = |out: &mut String, level: usize| {
+        out.push_str(newline);
+        for _ in 0..level {
+            out.push_str(indent);
+        }
+    };
+
+    let mut chars = content.chars();
+    while let Some(c) = chars.next() {
+        if c.is_ascii_whitespace() {
+            continue;
+        }
+        if opened && c != '}' && c != ']' {
+            break_line(&mut out, level);
+        }
+        opened = false;
+
+        match c {
+            '{' | '[' => {
+                out.push(c);
+                level += 1;
+                opened = true;
+            }
+            '}' | ']' => {
+                level -= 1;
+                if !out.ends_with(['{', '[']) {
+                    break_line(&mut out, level);
+                }
+                out.push(c);
+            }
+            ',' => {
+                out.push(',');
+                break_line(&mut out, level);
+            }
+            ':' => out.push_str(": "),
+            '"' => {
+                out.push('"');
+                let mut escaped = false;
+                for c in chars.by_ref() {
+                    out.push(c);
+                    match c {
+                        '"' if !escaped => break,
+                        '\\' => escaped = !escaped,
+                        _ => escaped = false,
+                    }
+                }
+            }
+            c => out.push(c),
+        }
+    }
+
+    out
+}
+
+/// Indentation of the first indented line
+fn detect_indent(content: &str) -> Option<String> {
+    content
+        .lines()
+        .map(|line| {
+            line.chars()
+                .take_while(|c| *c == ' ' || *c == '\t')
+                .collect::<String>()
+        })
+        .find(|indent| !indent.is_empty())
+}
+
+/// Indentation `.editorconfig` files set for `file_path`. Nearer files win,
+/// and the search stops at one with `root = true`.
+fn editorconfig_indent(file_path: &Path) -> Option<String> {
+    let mut style = None;
+    let mut size = None;
+
+    for dir in file_path.ancestors().skip(1) {
+        let Ok(content) = fs::read_to_string(dir.join(".editorconfig")) else {
+            continue;
+        };
+        let Ok(relative) = file_path.strip_prefix(dir) else {
+            continue;
+        };
+        let section = EditorConfig::parse(&content, relative);
+        style = style.or(section.indent_style);
+        size = size.or(section.indent_size);
+        if section.root {
+            break;
+        }
+    }
+
+    match (style.as_deref(), size) {
+        (Some("tab"), _) => Some("\t".to_string()),
+        (Some("space") | None, Some(size)) => Some(" ".repeat(size)),
+        _ => None,
+    }
+}
+
+/// The settings one `.editorconfig` gives a file
+#[derive(Debug, Default, PartialEq)]
+struct EditorConfig {
+    root: bool,
+    indent_style: Option<String>,
+    indent_size: Option<usize>,
+}
+
+impl EditorConfig {
+    /// Settings from the sections matching `relative`, later sections
+    /// overriding earlier ones
+    fn parse(content: &str, relative: &Path) -> EditorConfig {
+        let mut config = EditorConfig::default();
+        // `None` before the first section, where only `root` is read
+        let mut matching = None;
+
+        for line in content.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with(['#', ';']) {
+                continue;
+            }
+            if let Some(pattern) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                let matches = Glob::new(pattern).is_ok_and(|glob| glob.matches(relative, false));
+                matching = Some(matches);
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let key = key.trim().to_ascii_lowercase();
+            let value = value.trim().to_ascii_lowercase();
+
+            match (matching, key.as_str()) {
+                (None, "root") => config.root = value == "true",
+                (Some(true), "indent_style") => config.indent_style = Some(value),
+                // `tab` means the tab width, which only matters for tabs
+                (Some(true), "indent_size") => config.indent_size = value.parse().ok(),
+                _ => {}
+            }
+        }
+
+        config
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_reindent_keeps_order_and_tokens() {
+        let content = "{\"name\":\"a\",\n      \"version\": \"1.0.0\",\"z\":{},\"scripts\":{\"b\":\"x \\\"y\\\" \\u00e9\",\"a\":\"[1, {2}]\"},\"n\":[1.50,  2e3 , []]}\n";
+        assert_eq!(
+            reindent(Path::new("/nowhere/package.json"), content).unwrap(),
+            "{\n      \"name\": \"a\",\n      \"version\": \"1.0.0\",\n      \"z\": {},\n      \"scripts\": {\n            \"b\": \"x \\\"y\\\" \\u00e9\",\n            \"a\": \"[1, {2}]\"\n      },\n      \"n\": [\n            1.50,\n            2e3,\n            []\n      ]\n}\n"
+        );
+        assert_eq!(
+            reindent(Path::new("/nowhere/package.json"), "{\"a\":1}").unwrap(),
+            "{\n  \"a\": 1\n}"
+        );
+        assert_eq!(
+            reindent(
+                Path::new("/nowhere/package.json"),
+                "{\r\n\t\"a\": [1,2]\r\n}\r\n"
+            )
+            .unwrap(),
+            "{\r\n\t\"a\": [\r\n\t\t1,\r\n\t\t2\r\n\t]\r\n}\r\n"
+        );
+        assert!(reindent(Path::new("/nowhere/package.json"), "{\"a\":}").is_err());
+    }
+
+    #[test]
+    fn test_editorconfig_indent() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("packages/a")).unwrap();
+        fs::write(
+            root.join(".editorconfig"),
+            "root = true\n\n[*]\nindent_style = space\nindent_size = 4\n\n[{package.json,*.yml}]\nindent_size = 2\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("packages/.editorconfig"),
+            "[*.json]\nindent_style = tab\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            editorconfig_indent(&root.join("package.json")).as_deref(),
+            Some("  ")
+        );
+        assert_eq!(
+            editorconfig_indent(&root.join("tsconfig.json")).as_deref(),
+            Some("    ")
+        );
+        assert_eq!(
+            editorconfig_indent(&root.join("packages/a/package.json")).as_deref(),
+            Some("\t")
+        );
+    }
+}
@@ -1,4 +1,77 @@
+use std::env;
+use std::fs;
 use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::evidence::normalize_package;
+use crate::json::{self, Value};
+use crate::toml;
+use crate::tool::Exec;
+
+/// Package manager of a JavaScript project
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PackageManager {
+    Yarn,
+    Pnpm,
+    Bun,
+}
+
+impl PackageManager {
+    /// The package manager named by the `packageManager` field of the
+    /// package.json in `root`, or else the one whose lockfile is there
+    pub fn detect(root: &Path) -> Option<PackageManager> {
+        let declared = fs::read_to_string(root.join("package.json"))
+            .ok()
+            .and_then(|content| json::parse(&content))
+            .and_then(|package| Some(package.get("packageManager")?.as_str()?.to_string()));
+        let by_name = match declared.as_deref().and_then(|d| d.split('@').next()) {
+            Some("yarn") => Some(PackageManager::Yarn),
+            Some("pnpm") => Some(PackageManager::Pnpm),
+            Some("bun") => Some(PackageManager::Bun),
+            _ => None,
+        };
+
+        let lockfiles = [
+            ("yarn.lock", PackageManager::Yarn),
+            ("pnpm-lock.yaml", PackageManager::Pnpm),
+            ("bun.lock", PackageManager::Bun),
+            ("bun.lockb", PackageManager::Bun),
+        ];
+        by_name.or_else(|| {
+            lockfiles
+                .iter()
+                .find(|(lockfile, _)| root.join(lockfile).exists())
+                .map(|(_, manager)| *manager)
+        })
+    }
+
+    pub fn binary(self) -> &'static str {
+        match self {
+            PackageManager::Yarn => "yarn",
+            PackageManager::Pnpm => "pnpm",
+            PackageManager::Bun => "bun",
+        }
+    }
+
+    /// How to run the installed package binary `binary` without installing
+    /// or downloading anything
+    pub fn exec(self, binary: &str) -> Exec {
+        let (args, env): (&[&str], _) = match self {
+            PackageManager::Yarn => (&["exec"], vec![("YARN_ENABLE_OFFLINE_MODE", "1")]),
+            PackageManager::Pnpm => (&["exec"], vec![("npm_config_offline", "true")]),
+            PackageManager::Bun => (&["x", "--no-install"], Vec::new()),
+        };
+        Exec {
+            args: args
+                .iter()
+                .copied()
+                .chain([binary])
+                .map(str::to_string)
+                .collect(),
+            env,
+        }
+    }
+}
 
 /// Find the project root by looking for common project markers
 pub fn find_project_root(file_path: &Path) -> Option<PathBuf> {
@@ -66,6 +139,74 @@ pub fn find_node_root(file_path: &Path) -> Option<PathBuf> {
     }
 }
 
+/// Find the root of the npm/Yarn/pnpm workspace or Lerna monorepo holding
+/// the file: the nearest directory with `pnpm-workspace.yaml`, `lerna.json`
+/// or a package.json with a `workspaces` field
+pub fn find_workspace_root(file_path: &Path) -> Option<PathBuf> {
+    let mut current = file_path.parent()?;
+
+    loop {
+        if current.join("pnpm-workspace.yaml").exists() || current.join("lerna.json").exists() {
+            return Some(current.to_path_buf());
+        }
+        let workspaces = fs::read_to_string(current.join("package.json"))
+            .ok()
+            .and_then(|content| json::parse(&content))
+            .is_some_and(|package| package.get("workspaces").is_some());
+        if workspaces {
+            return Some(current.to_path_buf());
+        }
+
+        match current.parent() {
+            Some(parent) => current = parent,
+            None => return None,
+        }
+    }
+}
+
+/// Package directories whose `node_modules` a JavaScript tool may be
+/// installed in, nearest first: the nearest package.json directory and,
+/// inside a workspace, each package.json directory above it up to the
+/// workspace root
+pub fn find_node_package_dirs(file_path: &Path) -> Vec<PathBuf> {
+    let Some(node_root) = find_node_root(file_path) else {
+        return Vec::new();
+    };
+    let workspace_root = find_workspace_root(file_path).unwrap_or_else(|| node_root.clone());
+
+    node_root
+        .ancestors()
+        .take_while(|dir| dir.starts_with(&workspace_root))
+        .filter(|dir| *dir == node_root || dir.join("package.json").exists())
+        .map(Path::to_path_buf)
+        .collect()
+}
+
+/// Find the Plug'n'Play root (the directory with `.pnp.cjs`) of the
+/// JavaScript project holding the file. Such projects have no
+/// `node_modules/.bin`.
+pub fn find_pnp_root(file_path: &Path) -> Option<PathBuf> {
+    find_node_package_dirs(file_path)
+        .into_iter()
+        .find(|dir| dir.join(".pnp.cjs").exists() || dir.join(".pnp.js").exists())
+}
+
+/// Find the nearest Deno project root (deno.json or deno.jsonc)
+pub fn find_deno_root(file_path: &Path) -> Option<PathBuf> {
+    let mut current = file_path.parent()?;
+
+    loop {
+        if current.join("deno.json").exists() || current.join("deno.jsonc").exists() {
+            return Some(current.to_path_buf());
+        }
+
+        match current.parent() {
+            Some(parent) => current = parent,
+            None => return None,
+        }
+    }
+}
+
 /// Find the nearest Python project root (pyproject.toml or setup.py)
 pub fn find_python_root(file_path: &Path) -> Option<PathBuf> {
     let mut current = file_path.parent()?;
@@ -82,6 +223,236 @@ pub fn find_python_root(file_path: &Path) -> Option<PathBuf> {
     }
 }
 
+/// Python environments a project's formatters may be installed in, in the
+/// order they are tried. The project at `root`, if any, comes first: its
+/// `.venv` and `venv`, pdm's `__pypackages__`, the `.venv` of a uv
+/// workspace above it, and poetry's and hatch's managed environments. The
+/// activated virtualenv and conda environment come last, and are tried
+/// even outside a project. Environment variables are read through `var`,
+/// usually [`env_var`].
+pub fn find_python_envs(
+    root: Option<&Path>,
+    var: impl Fn(&str) -> Option<PathBuf>,
+) -> Vec<PathBuf> {
+    let mut envs = root
+        .map(|root| project_envs(root, &var))
+        .unwrap_or_default();
+    envs.extend(var("VIRTUAL_ENV"));
+    envs.extend(var("CONDA_PREFIX"));
+
+    let mut unique: Vec<PathBuf> = Vec::new();
+    for env in envs {
+        if !unique.contains(&env) {
+            unique.push(env);
+        }
+    }
+    unique
+}
+
+/// A path from the process environment, `None` if unset or empty
+pub fn env_var(name: &str) -> Option<PathBuf> {
+    env::var_os(name)
+        .filter(|value| !value.is_empty())
+        .map(PathBuf::from)
+}
+
+/// The environments that belong to the Python project at `root`
+fn project_envs(root: &Path, var: &impl Fn(&str) -> Option<PathBuf>) -> Vec<PathBuf> {
+    let pyproject = fs::read_to_string(root.join("pyproject.toml"))
+        .ok()
+        .and_then(|content| toml::parse(&content).ok());
+    let pyproject = pyproject.as_ref();
+    let name = pyproject.and_then(|p| {
+        let name = p
+            .get("project")
+            .and_then(|project| project.get("name"))
+            .or_else(|| p.get("tool")?.get("poetry")?.get("name"))?;
+        Some(normalize_package(name.as_str()?))
+    });
+
+    let mut envs = vec![root.join(".venv"), root.join("venv")];
+    envs.extend(pdm_packages(root));
+    if let Some(workspace) = find_uv_workspace_root(root) {
+        let env = var("UV_PROJECT_ENVIRONMENT").unwrap_or_else(|| PathBuf::from(".venv"));
+        envs.push(workspace.join(env));
+    }
+    if let Some(name) = &name {
+        let uses_poetry = root.join("poetry.lock").exists()
+            || pyproject
+                .and_then(|p| p.get("tool")?.get("poetry"))
+                .is_some();
+        if uses_poetry {
+            envs.extend(poetry_envs(var, name));
+        }
+        envs.extend(hatch_envs(root, pyproject, var, name));
+    }
+    envs
+}
+
+/// pdm's PEP 582 package directories, `__pypackages__/<X.Y>`, newest
+/// Python first
+fn pdm_packages(root: &Path) -> Vec<PathBuf> {
+    let mut versions: Vec<(Vec<u32>, PathBuf)> = fs::read_dir(root.join("__pypackages__"))
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter_map(|entry| {
+                    let name = entry.file_name().to_string_lossy().into_owned();
+                    let version = name
+                        .split('.')
+                        .map(|part| part.parse().ok())
+                        .collect::<Option<_>>()?;
+                    Some((version, entry.path()))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    versions.sort_by(|a, b| b.0.cmp(&a.0));
+    versions.into_iter().map(|(_, path)| path).collect()
+}
+
+/// Find the root of the uv workspace the Python project at `root` is a
+/// member of: a directory above it whose pyproject.toml has a
+/// `[tool.uv.workspace]` table
+fn find_uv_workspace_root(root: &Path) -> Option<PathBuf> {
+    for dir in root.ancestors().skip(1) {
+        let is_workspace = fs::read_to_string(dir.join("pyproject.toml"))
+            .ok()
+            .and_then(|content| toml::parse(&content).ok())
+            .is_some_and(|p| {
+                p.get("tool")
+                    .and_then(|t| t.get("uv"))
+                    .and_then(|uv| uv.get("workspace"))
+                    .is_some()
+            });
+        if is_workspace {
+            return Some(dir.to_path_buf());
+        }
+        if dir.join(".git").exists() {
+            return None;
+        }
+    }
+    None
+}
+
+/// The environments poetry created for the project `name`, most recently
+/// used first. Poetry names them `<name>-<hash of the path>-py<X.Y>`; the
+/// hash is not recomputed, so clones sharing a name are told apart by age.
+fn poetry_envs(var: &impl Fn(&str) -> Option<PathBuf>, name: &str) -> Vec<PathBuf> {
+    let Some(dir) = var("POETRY_VIRTUALENVS_PATH")
+        .or_else(|| Some(var("POETRY_CACHE_DIR")?.join("virtualenvs")))
+        .or_else(|| Some(poetry_cache_dir(var)?.join("virtualenvs")))
+    else {
+        return Vec::new();
+    };
+
+    let prefix: String = name
+        .chars()
+        .map(|c| if " $`!*@\"\\".contains(c) { '_' } else { c })
+        .take(42)
+        .collect();
+    newest_first(
+        fs::read_dir(dir)
+            .map(|entries| {
+                entries
+                    .flatten()
+                    .filter(|entry| {
+                        let file_name = entry.file_name().to_string_lossy().into_owned();
+                        file_name
+                            .strip_prefix(&prefix)
+                            .and_then(|rest| rest.strip_prefix('-'))
+                            .is_some_and(|rest| {
+                                rest.get(8..).is_some_and(|py| py.starts_with("-py"))
+                            })
+                    })
+                    .map(|entry| entry.path())
+                    .collect()
+            })
+            .unwrap_or_default(),
+    )
+}
+
+/// The default environment hatch manages for the project `name`: a `path`
+/// set for it in `[tool.hatch.envs.default]` or hatch.toml, or else
+/// `<data dir>/env/virtual/<name>/<hash>/<name>`, most recently used first
+fn hatch_envs(
+    root: &Path,
+    pyproject: Option<&Value>,
+    var: &impl Fn(&str) -> Option<PathBuf>,
+    name: &str,
+) -> Vec<PathBuf> {
+    let hatch_toml = fs::read_to_string(root.join("hatch.toml"))
+        .ok()
+        .and_then(|content| toml::parse(&content).ok());
+    let hatch = pyproject
+        .and_then(|p| p.get("tool")?.get("hatch"))
+        .or(hatch_toml.as_ref());
+    let Some(hatch) = hatch else {
+        return Vec::new();
+    };
+
+    if let Some(path) = hatch
+        .get("envs")
+        .and_then(|envs| envs.get("default"))
+        .and_then(|env| env.get("path"))
+        .and_then(Value::as_str)
+    {
+        return vec![root.join(path)];
+    }
+
+    let Some(data) = var("HATCH_DATA_DIR").or_else(|| hatch_data_dir(var)) else {
+        return Vec::new();
+    };
+    newest_first(
+        fs::read_dir(data.join("env").join("virtual").join(name))
+            .map(|entries| {
+                entries
+                    .flatten()
+                    .map(|entry| entry.path().join(name))
+                    .filter(|env| env.is_dir())
+                    .collect()
+            })
+            .unwrap_or_default(),
+    )
+}
+
+/// Poetry's default cache directory on this platform
+fn poetry_cache_dir(var: &impl Fn(&str) -> Option<PathBuf>) -> Option<PathBuf> {
+    if cfg!(windows) {
+        return Some(var("LOCALAPPDATA")?.join("pypoetry").join("Cache"));
+    }
+    let home = var("HOME");
+    if cfg!(target_os = "macos") {
+        return Some(home?.join("Library/Caches/pypoetry"));
+    }
+    let cache = var("XDG_CACHE_HOME").or_else(|| Some(home?.join(".cache")))?;
+    Some(cache.join("pypoetry"))
+}
+
+/// Hatch's default data directory on this platform
+fn hatch_data_dir(var: &impl Fn(&str) -> Option<PathBuf>) -> Option<PathBuf> {
+    if cfg!(windows) {
+        return Some(var("LOCALAPPDATA")?.join("hatch"));
+    }
+    let home = var("HOME");
+    if cfg!(target_os = "macos") {
+        return Some(home?.join("Library/Application Support/hatch"));
+    }
+    let data = var("XDG_DATA_HOME").or_else(|| Some(home?.join(".local/share")))?;
+    Some(data.join("hatch"))
+}
+
+/// `paths` with the most recently modified first
+fn newest_first(mut paths: Vec<PathBuf>) -> Vec<PathBuf> {
+    let modified = |path: &PathBuf| {
+        fs::metadata(path)
+            .and_then(|m| m.modified())
+            .unwrap_or(SystemTime::UNIX_EPOCH)
+    };
+    paths.sort_by_key(|path| std::cmp::Reverse(modified(path)));
+    paths
+}
+
 /// Find the nearest Maven/Gradle project root
 pub fn find_java_root(file_path: &Path) -> Option<PathBuf> {
     let mut current = file_path.parent()?;
@@ -149,4 +520,134 @@ mod tests {
         let root = find_node_root(&file_path).unwrap();
         assert_eq!(root, project_dir);
     }
+
+    #[test]
+    fn test_find_node_bin_dirs_in_workspace() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let app = root.join("apps/web");
+        fs::create_dir_all(app.join("src")).unwrap();
+        fs::write(root.join("package.json"), r#"{"workspaces": ["apps/*"]}"#).unwrap();
+        fs::write(app.join("package.json"), "{}").unwrap();
+
+        let file_path = app.join("src/index.ts");
+        assert_eq!(find_workspace_root(&file_path), Some(root.to_path_buf()));
+        assert_eq!(
+            find_node_package_dirs(&file_path),
+            vec![app.clone(), root.to_path_buf()]
+        );
+
+        // Without a workspace only the nearest package counts
+        fs::write(root.join("package.json"), "{}").unwrap();
+        assert_eq!(find_node_package_dirs(&file_path), vec![app.clone()]);
+
+        fs::write(root.join("pnpm-workspace.yaml"), "packages: ['apps/*']\n").unwrap();
+        assert_eq!(find_node_package_dirs(&file_path).len(), 2);
+    }
+
+    #[test]
+    fn test_pnp_and_package_manager() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::write(root.join("package.json"), "{}").unwrap();
+        let file_path = root.join("src/index.ts");
+        assert_eq!(find_pnp_root(&file_path), None);
+        assert_eq!(PackageManager::detect(root), None);
+
+        fs::write(root.join(".pnp.cjs"), "").unwrap();
+        fs::write(root.join("yarn.lock"), "").unwrap();
+        assert_eq!(find_pnp_root(&file_path), Some(root.to_path_buf()));
+        assert_eq!(PackageManager::detect(root), Some(PackageManager::Yarn));
+
+        fs::write(
+            root.join("package.json"),
+            r#"{"packageManager": "pnpm@9.1.0"}"#,
+        )
+        .unwrap();
+        assert_eq!(PackageManager::detect(root), Some(PackageManager::Pnpm));
+        assert_eq!(
+            PackageManager::Bun.exec("biome").args,
+            ["x", "--no-install", "biome"]
+        );
+    }
+
+    #[test]
+    fn test_find_python_envs() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().join("repo");
+        let app = root.join("packages/app");
+        let cache = temp_dir.path().join("cache");
+        fs::create_dir_all(root.join(".git")).unwrap();
+        fs::create_dir_all(app.join("__pypackages__/3.9/bin")).unwrap();
+        fs::create_dir_all(app.join("__pypackages__/3.12/bin")).unwrap();
+        fs::create_dir_all(cache.join("pypoetry/virtualenvs/my-app-AbCd1234-py3.12")).unwrap();
+        fs::create_dir_all(cache.join("pypoetry/virtualenvs/my-app-extra-AbCd1234-py3.12"))
+            .unwrap();
+        fs::write(
+            root.join("pyproject.toml"),
+            "[tool.uv.workspace]\nmembers = [\"packages/*\"]\n",
+        )
+        .unwrap();
+        fs::write(
+            app.join("pyproject.toml"),
+            "[tool.poetry]\nname = \"My_App\"\n",
+        )
+        .unwrap();
+
+        let vars = |name: &str| match name {
+            "XDG_CACHE_HOME" if cfg!(target_os = "linux") => Some(cache.clone()),
+            "POETRY_CACHE_DIR" if !cfg!(target_os = "linux") => Some(cache.join("pypoetry")),
+            "VIRTUAL_ENV" => Some(PathBuf::from("/envs/active")),
+            "CONDA_PREFIX" => Some(PathBuf::from("/opt/conda")),
+            _ => None,
+        };
+        assert_eq!(
+            find_python_envs(Some(&app), vars),
+            vec![
+                app.join(".venv"),
+                app.join("venv"),
+                app.join("__pypackages__/3.12"),
+                app.join("__pypackages__/3.9"),
+                root.join(".venv"),
+                cache.join("pypoetry/virtualenvs/my-app-AbCd1234-py3.12"),
+                PathBuf::from("/envs/active"),
+                PathBuf::from("/opt/conda"),
+            ]
+        );
+        // Activated environments apply outside a project too
+        assert_eq!(
+            find_python_envs(None, vars),
+            vec![PathBuf::from("/envs/active"), PathBuf::from("/opt/conda")]
+        );
+    }
+
+    #[test]
+    fn test_hatch_envs() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().join("proj");
+        let data = temp_dir.path().join("hatch");
+        fs::create_dir_all(data.join("env/virtual/proj/x1Y2z3W4/proj")).unwrap();
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("pyproject.toml"), "[project]\nname = \"proj\"\n").unwrap();
+        let vars = |name: &str| (name == "HATCH_DATA_DIR").then(|| data.clone());
+
+        // Only projects that use hatch get its environments
+        assert_eq!(find_python_envs(Some(&root), vars).len(), 2);
+
+        fs::write(root.join("hatch.toml"), "[envs.test]\n").unwrap();
+        assert_eq!(
+            find_python_envs(Some(&root), vars).last(),
+            Some(&data.join("env/virtual/proj/x1Y2z3W4/proj"))
+        );
+
+        fs::write(
+            root.join("hatch.toml"),
+            "[envs.default]\npath = \".hatch\"\n",
+        )
+        .unwrap();
+        assert_eq!(
+            find_python_envs(Some(&root), vars).last(),
+            Some(&root.join(".hatch"))
+        );
+    }
 }
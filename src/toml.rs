@@ -0,0 +1,562 @@
+use crate::json::Value;
+
+/// Parse the subset of TOML used by config files: tables, arrays of tables,
+/// dotted keys, basic, literal and multi-line strings, numbers, booleans,
+/// arrays and inline tables.
+///
+/// Tables become [`Value::Object`]s in document order. Numbers (and dates)
+/// keep their original lexeme.
+pub fn parse(input: &str) -> Result<Value, String> {
+    let mut parser = Parser {
+        bytes: input.as_bytes(),
+        pos: 0,
+    };
+    parser.parse_document().map_err(|e| {
+        let line = parser.bytes[..parser.pos.min(parser.bytes.len())]
+            .iter()
+            .filter(|b| **b == b'\n')
+            .count()
+            + 1;
+        format!("line {}: {}", line, e)
+    })
+}
+
+struct Parser<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+
+    fn starts_with(&self, literal: &str) -> bool {
+        self.bytes[self.pos..].starts_with(literal.as_bytes())
+    }
+
+    fn skip_spaces(&mut self) {
+        while let Some(b' ' | b'\t') = self.peek() {
+            self.pos += 1;
+        }
+    }
+
+    fn skip_comment(&mut self) {
+        if self.peek() == Some(b'#') {
+            while !matches!(self.peek(), None | Some(b'\n')) {
+                self.pos += 1;
+            }
+        }
+    }
+
+    /// Skip spaces, comments and newlines, as allowed between array items
+    fn skip_blank(&mut self) {
+        loop {
+            self.skip_spaces();
+            self.skip_comment();
+            match self.peek() {
+                Some(b'\n') => self.pos += 1,
+                Some(b'\r') if self.bytes.get(self.pos + 1) == Some(&b'\n') => self.pos += 2,
+                _ => return,
+            }
+        }
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), String> {
+        if self.peek() == Some(byte) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(format!("expected '{}'", byte as char))
+        }
+    }
+
+    /// Nothing but a comment may follow a key/value pair or table header
+    fn expect_line_end(&mut self) -> Result<(), String> {
+        self.skip_spaces();
+        self.skip_comment();
+        match self.peek() {
+            None | Some(b'\n') => Ok(()),
+            Some(b'\r') if self.bytes.get(self.pos + 1) == Some(&b'\n') => Ok(()),
+            Some(_) => Err("expected the end of the line".to_string()),
+        }
+    }
+
+    fn parse_document(&mut self) -> Result<Value, String> {
+        let mut root = Value::Object(Vec::new());
+        let mut table: Vec<String> = Vec::new();
+
+        loop {
+            self.skip_blank();
+            match self.peek() {
+                None => return Ok(root),
+                Some(b'[') if self.starts_with("[[") => {
+                    self.pos += 2;
+                    self.skip_spaces();
+                    table = self.parse_key()?;
+                    self.skip_spaces();
+                    self.expect(b']')?;
+                    self.expect(b']')?;
+                    push_table(&mut root, &table)?;
+                }
+                Some(b'[') => {
+                    self.pos += 1;
+                    self.skip_spaces();
+                    table = self.parse_key()?;
+                    self.skip_spaces();
+                    self.expect(b']')?;
+                    table_mut(&mut root, &table)?;
+                }
+                Some(_) => {
+                    let key = self.parse_key()?;
+                    self.skip_spaces();
+                    self.expect(b'=')?;
+                    self.skip_spaces();
+                    let value = self.parse_value()?;
+                    insert(&mut root, &table, &key, value)?;
+                }
+            }
+            self.expect_line_end()?;
+        }
+    }
+
+    /// A bare, quoted or dotted key
+    fn parse_key(&mut self) -> Result<Vec<String>, String> {
+        let mut parts = Vec::new();
+        loop {
+            let part = match self.peek() {
+                Some(b'"') => self.parse_basic_string()?,
+                Some(b'\'') => self.parse_literal_string()?,
+                _ => {
+                    let start = self.pos;
+                    while let Some(b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'_' | b'-') =
+                        self.peek()
+                    {
+                        self.pos += 1;
+                    }
+                    if self.pos == start {
+                        return Err("expected a key".to_string());
+                    }
+                    String::from_utf8_lossy(&self.bytes[start..self.pos]).into_owned()
+                }
+            };
+            parts.push(part);
+
+            self.skip_spaces();
+            if self.peek() != Some(b'.') {
+                return Ok(parts);
+            }
+            self.pos += 1;
+            self.skip_spaces();
+        }
+    }
+
+    fn parse_value(&mut self) -> Result<Value, String> {
+        match self.peek() {
+            Some(b'"') if self.starts_with("\"\"\"") => {
+                self.parse_multiline_string("\"\"\"").map(Value::String)
+            }
+            Some(b'\'') if self.starts_with("'''") => {
+                self.parse_multiline_string("'''").map(Value::String)
+            }
+            Some(b'"') => self.parse_basic_string().map(Value::String),
+            Some(b'\'') => self.parse_literal_string().map(Value::String),
+            Some(b'[') => self.parse_array(),
+            Some(b'{') => self.parse_inline_table(),
+            _ if self.starts_with("true") => {
+                self.pos += 4;
+                Ok(Value::Bool(true))
+            }
+            _ if self.starts_with("false") => {
+                self.pos += 5;
+                Ok(Value::Bool(false))
+            }
+            Some(b'+' | b'-' | b'0'..=b'9') => self.parse_number(),
+            _ => Err("expected a value".to_string()),
+        }
+    }
+
+    /// Numbers and dates, kept as written (without digit separators)
+    fn parse_number(&mut self) -> Result<Value, String> {
+        let start = self.pos;
+        while let Some(b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'_' | b'+' | b'-' | b'.' | b':') =
+            self.peek()
+        {
+            self.pos += 1;
+        }
+        let lexeme = String::from_utf8_lossy(&self.bytes[start..self.pos]).replace('_', "");
+        Ok(Value::Number(lexeme))
+    }
+
+    fn parse_array(&mut self) -> Result<Value, String> {
+        self.pos += 1;
+        let mut items = Vec::new();
+
+        loop {
+            self.skip_blank();
+            if self.peek() == Some(b']') {
+                self.pos += 1;
+                return Ok(Value::Array(items));
+            }
+            items.push(self.parse_value()?);
+
+            self.skip_blank();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b']') => {}
+                _ => return Err("expected ',' or ']'".to_string()),
+            }
+        }
+    }
+
+    fn parse_inline_table(&mut self) -> Result<Value, String> {
+        self.pos += 1;
+        let mut table = Value::Object(Vec::new());
+
+        self.skip_spaces();
+        if self.peek() == Some(b'}') {
+            self.pos += 1;
+            return Ok(table);
+        }
+
+        loop {
+            self.skip_spaces();
+            let key = self.parse_key()?;
+            self.skip_spaces();
+            self.expect(b'=')?;
+            self.skip_spaces();
+            let value = self.parse_value()?;
+            insert(&mut table, &[], &key, value)?;
+
+            self.skip_spaces();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b'}') => {
+                    self.pos += 1;
+                    return Ok(table);
+                }
+                _ => return Err("expected ',' or '}'".to_string()),
+            }
+        }
+    }
+
+    fn parse_basic_string(&mut self) -> Result<String, String> {
+        self.pos += 1;
+        let mut bytes = Vec::new();
+
+        loop {
+            match self.peek() {
+                None | Some(b'\n') => return Err("unterminated string".to_string()),
+                Some(b'"') => {
+                    self.pos += 1;
+                    return String::from_utf8(bytes).map_err(|e| e.to_string());
+                }
+                Some(b'\\') => {
+                    self.pos += 1;
+                    let escaped = self.parse_escape()?;
+                    self.pos += 1;
+                    let mut buf = [0; 4];
+                    bytes.extend_from_slice(escaped.encode_utf8(&mut buf).as_bytes());
+                }
+                Some(b) => {
+                    bytes.push(b);
+                    self.pos += 1;
+                }
+            }
+        }
+    }
+
+    /// A `"""` or `'''` string. A newline right after the opening
+    /// delimiter is dropped; in basic strings a backslash at the end of a
+    /// line also drops the whitespace that follows.
+    fn parse_multiline_string(&mut self, delimiter: &str) -> Result<String, String> {
+        let basic = delimiter == "\"\"\"";
+        self.pos += 3;
+        if self.starts_with("\r\n") {
+            self.pos += 2;
+        } else if self.peek() == Some(b'\n') {
+            self.pos += 1;
+        }
+
+        let mut bytes = Vec::new();
+        loop {
+            // Up to two quotes may end the content right before the delimiter
+            if self.starts_with(delimiter)
+                && !self.bytes[self.pos + 3..].starts_with(&delimiter.as_bytes()[..1])
+            {
+                self.pos += 3;
+                return String::from_utf8(bytes).map_err(|e| e.to_string());
+            }
+            match self.peek() {
+                None => return Err("unterminated string".to_string()),
+                Some(b'\\') if basic => {
+                    self.pos += 1;
+                    let rest = &self.bytes[self.pos..];
+                    let line_end = rest
+                        .iter()
+                        .position(|b| !matches!(b, b' ' | b'\t' | b'\r'))
+                        .is_some_and(|i| rest[i] == b'\n');
+                    if line_end {
+                        while let Some(b' ' | b'\t' | b'\r' | b'\n') = self.peek() {
+                            self.pos += 1;
+                        }
+                        continue;
+                    }
+                    let escaped = self.parse_escape()?;
+                    self.pos += 1;
+                    let mut buf = [0; 4];
+                    bytes.extend_from_slice(escaped.encode_utf8(&mut buf).as_bytes());
+                }
+                Some(b) => {
+                    bytes.push(b);
+                    self.pos += 1;
+                }
+            }
+        }
+    }
+
+    /// The character escaped by the backslash before the position, leaving
+    /// the position on the escape's last byte
+    fn parse_escape(&mut self) -> Result<char, String> {
+        Ok(match self.peek() {
+            Some(b'"') => '"',
+            Some(b'\\') => '\\',
+            Some(b'n') => '\n',
+            Some(b't') => '\t',
+            Some(b'r') => '\r',
+            Some(b'b') => '\u{8}',
+            Some(b'f') => '\u{c}',
+            Some(b'u') => self.parse_unicode_escape(4)?,
+            Some(b'U') => self.parse_unicode_escape(8)?,
+            _ => return Err("invalid escape in string".to_string()),
+        })
+    }
+
+    /// `\uXXXX` or `\UXXXXXXXX`, leaving the position on the last digit
+    fn parse_unicode_escape(&mut self, digits: usize) -> Result<char, String> {
+        let hex = self
+            .bytes
+            .get(self.pos + 1..self.pos + 1 + digits)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .ok_or("invalid unicode escape")?;
+        let c = u32::from_str_radix(hex, 16)
+            .ok()
+            .and_then(char::from_u32)
+            .ok_or("invalid unicode escape")?;
+        self.pos += digits;
+        Ok(c)
+    }
+
+    fn parse_literal_string(&mut self) -> Result<String, String> {
+        self.pos += 1;
+        let start = self.pos;
+        loop {
+            match self.peek() {
+                None | Some(b'\n') => return Err("unterminated string".to_string()),
+                Some(b'\'') => break,
+                Some(_) => self.pos += 1,
+            }
+        }
+        let s = String::from_utf8_lossy(&self.bytes[start..self.pos]).into_owned();
+        self.pos += 1;
+        Ok(s)
+    }
+}
+
+/// Entries of the table at `path`, creating missing tables on the way
+fn table_mut<'a>(
+    root: &'a mut Value,
+    path: &[String],
+) -> Result<&'a mut Vec<(String, Value)>, String> {
+    let mut current = root;
+    for key in path {
+        let Value::Object(entries) = current else {
+            unreachable!("only tables are walked into");
+        };
+        let index = match entries.iter().position(|(k, _)| k == key) {
+            Some(index) => index,
+            None => {
+                entries.push((key.clone(), Value::Object(Vec::new())));
+                entries.len() - 1
+            }
+        };
+        // Keys under an array of tables go to its latest table
+        let tables = matches!(
+            &entries[index].1,
+            Value::Array(items) if matches!(items.last(), Some(Value::Object(_)))
+        );
+        current = if tables {
+            let Value::Array(items) = &mut entries[index].1 else {
+                unreachable!("checked above");
+            };
+            items.last_mut().expect("checked above")
+        } else {
+            &mut entries[index].1
+        };
+        if !matches!(current, Value::Object(_)) {
+            return Err(format!("'{}' is not a table", key));
+        }
+    }
+
+    match current {
+        Value::Object(entries) => Ok(entries),
+        _ => unreachable!("checked above"),
+    }
+}
+
+/// Start a new table in the array of tables at `path`
+fn push_table(root: &mut Value, path: &[String]) -> Result<(), String> {
+    let (last, parents) = path.split_last().expect("keys are never empty");
+    let entries = table_mut(root, parents)?;
+    match entries.iter_mut().find(|(k, _)| k == last) {
+        Some((_, Value::Array(items))) if items.iter().all(|i| matches!(i, Value::Object(_))) => {
+            items.push(Value::Object(Vec::new()));
+        }
+        Some(_) => return Err(format!("'{}' is not an array of tables", path.join("."))),
+        None => entries.push((last.clone(), Value::Array(vec![Value::Object(Vec::new())]))),
+    }
+    Ok(())
+}
+
+/// Set a possibly dotted `key` in the table at `table`, rejecting duplicates
+fn insert(root: &mut Value, table: &[String], key: &[String], value: Value) -> Result<(), String> {
+    let (last, parents) = key.split_last().expect("keys are never empty");
+    let path: Vec<String> = table.iter().chain(parents).cloned().collect();
+    let entries = table_mut(root, &path)?;
+    if entries.iter().any(|(k, _)| k == last) {
+        return Err(format!("duplicate key '{}'", key.join(".")));
+    }
+    entries.push((last.clone(), value));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_tables_and_values() {
+        let value = parse(
+            "# comment\n\
+             top = 'literal\\n'\n\
+             \n\
+             [languages.python]  # trailing comment\n\
+             formatters = [\n  \"black\", # first\n  \"ruff\",\n]\n\
+             project_only = true\n\
+             \n\
+             [\"extensions\".mjs]\n\
+             tab = { width = 1_000, name = \"a\\u00e9\" }\n\
+             extra.depth = -1.5\n",
+        )
+        .unwrap();
+
+        assert_eq!(value.get("top").and_then(Value::as_str), Some("literal\\n"));
+        let python = value
+            .get("languages")
+            .and_then(|l| l.get("python"))
+            .unwrap();
+        assert_eq!(
+            python.get("formatters"),
+            Some(&Value::Array(vec![
+                Value::String("black".to_string()),
+                Value::String("ruff".to_string()),
+            ]))
+        );
+        assert_eq!(python.get("project_only"), Some(&Value::Bool(true)));
+
+        let mjs = value.get("extensions").and_then(|e| e.get("mjs")).unwrap();
+        let tab = mjs.get("tab").unwrap();
+        assert_eq!(tab.get("width"), Some(&Value::Number("1000".to_string())));
+        assert_eq!(tab.get("name").and_then(Value::as_str), Some("aé"));
+        assert_eq!(
+            mjs.get("extra").and_then(|e| e.get("depth")),
+            Some(&Value::Number("-1.5".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_parse_keeps_key_order() {
+        let value = parse("[z]\n[a]\n[m]\n").unwrap();
+        let Value::Object(entries) = value else {
+            panic!("expected table");
+        };
+        let keys: Vec<&str> = entries.iter().map(|(k, _)| k.as_str()).collect();
+        assert_eq!(keys, ["z", "a", "m"]);
+    }
+
+    #[test]
+    fn test_parse_reports_line() {
+        assert_eq!(
+            parse("a = 1\nb = \n"),
+            Err("line 2: expected a value".to_string())
+        );
+        assert_eq!(
+            parse("a = 1\na = 2\n"),
+            Err("line 2: duplicate key 'a'".to_string())
+        );
+        assert_eq!(
+            parse("a = 1\n[a]\n"),
+            Err("line 2: 'a' is not a table".to_string())
+        );
+        assert_eq!(
+            parse("a = \"open\n"),
+            Err("line 1: unterminated string".to_string())
+        );
+        assert_eq!(
+            parse("a = 1 b\n"),
+            Err("line 1: expected the end of the line".to_string())
+        );
+        assert_eq!(
+            parse("a = 1\n[[a]]\n"),
+            Err("line 2: 'a' is not an array of tables".to_string())
+        );
+        assert_eq!(
+            parse("a = \"\"\"\nx\n"),
+            Err("line 3: unterminated string".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_arrays_of_tables() {
+        let value = parse(
+            "[[tool.bin]]\nname = \"a\"\n[tool.bin.extra]\nx = 1\n\n[[tool.bin]]\nname = \"b\"\n",
+        )
+        .unwrap();
+        let bins = value.get("tool").and_then(|t| t.get("bin"));
+        let Some(Value::Array(bins)) = bins else {
+            panic!("expected an array, got {:?}", bins);
+        };
+        assert_eq!(bins.len(), 2);
+        assert_eq!(bins[0].get("name"), Some(&Value::String("a".to_string())));
+        assert!(bins[0].get("extra").and_then(|e| e.get("x")).is_some());
+        assert_eq!(bins[1].get("name"), Some(&Value::String("b".to_string())));
+    }
+
+    #[test]
+    fn test_parse_multiline_strings() {
+        let value = parse(
+            "a = \"\"\"\nline \\\"one\\\"\nline two \\\n    continued\"\"\"\nb = '''\nraw \\n \"\"'''\nc = \"\"\"x\"\"\"\"\n",
+        )
+        .unwrap();
+        assert_eq!(
+            value.get("a"),
+            Some(&Value::String(
+                "line \"one\"\nline two continued".to_string()
+            ))
+        );
+        assert_eq!(
+            value.get("b"),
+            Some(&Value::String("raw \\n \"\"".to_string()))
+        );
+        assert_eq!(value.get("c"), Some(&Value::String("x\"".to_string())));
+    }
+
+    #[test]
+    fn test_parse_crlf() {
+        let value = parse("[a]\r\nb = true\r\n").unwrap();
+        assert_eq!(
+            value.get("a").and_then(|a| a.get("b")),
+            Some(&Value::Bool(true))
+        );
+    }
+}
@@ -0,0 +1,827 @@
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+use std::thread;
+
+/// Formatter programs this hook knows how to drive
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tool {
+    Oxfmt,
+    Biome,
+    Prettier,
+    Dprint,
+    CargoFmt,
+    Rustfmt,
+    Ruff,
+    Black,
+    Autopep8,
+    Yapf,
+    SpotlessMaven,
+    SpotlessGradle,
+    GoogleJavaFormat,
+    PalantirJavaFormat,
+    Goimports,
+    Gofumpt,
+    Gofmt,
+    SortPackageJson,
+    DenoFmt,
+    /// A command declared in the config, see [`Custom`]
+    Custom,
+}
+
+impl Tool {
+    pub const ALL: [Tool; 19] = [
+        Tool::Oxfmt,
+        Tool::Biome,
+        Tool::Prettier,
+        Tool::Dprint,
+        Tool::CargoFmt,
+        Tool::Rustfmt,
+        Tool::Ruff,
+        Tool::Black,
+        Tool::Autopep8,
+        Tool::Yapf,
+        Tool::SpotlessMaven,
+        Tool::SpotlessGradle,
+        Tool::GoogleJavaFormat,
+        Tool::PalantirJavaFormat,
+        Tool::Goimports,
+        Tool::Gofumpt,
+        Tool::Gofmt,
+        Tool::SortPackageJson,
+        Tool::DenoFmt,
+    ];
+
+    /// Look a tool up by its config name (or the name reported in results)
+    pub fn from_key(key: &str) -> Option<Tool> {
+        Tool::ALL
+            .into_iter()
+            .find(|tool| tool.key() == key || tool.name() == key)
+    }
+
+    /// Name used in config files
+    pub fn key(self) -> &'static str {
+        match self {
+            Tool::CargoFmt => "cargo-fmt",
+            Tool::DenoFmt => "deno-fmt",
+            Tool::SpotlessMaven => "spotless-maven",
+            Tool::SpotlessGradle => "spotless-gradle",
+            tool => tool.name(),
+        }
+    }
+
+    /// Name reported in results
+    pub fn name(self) -> &'static str {
+        match self {
+            Tool::Oxfmt => "oxfmt",
+            Tool::Biome => "biome",
+            Tool::Prettier => "prettier",
+            Tool::Dprint => "dprint",
+            Tool::CargoFmt => "cargo fmt",
+            Tool::Rustfmt => "rustfmt",
+            Tool::Ruff => "ruff",
+            Tool::Black => "black",
+            Tool::Autopep8 => "autopep8",
+            Tool::Yapf => "yapf",
+            Tool::SpotlessMaven => "spotless (Maven)",
+            Tool::SpotlessGradle => "spotless (Gradle)",
+            Tool::GoogleJavaFormat => "google-java-format",
+            Tool::PalantirJavaFormat => "palantir-java-format",
+            Tool::Goimports => "goimports",
+            Tool::Gofumpt => "gofumpt",
+            Tool::Gofmt => "gofmt",
+            Tool::SortPackageJson => "sort-package-json",
+            Tool::DenoFmt => "deno fmt",
+            Tool::Custom => "custom",
+        }
+    }
+
+    /// Executable looked up on PATH when the tool is not installed locally
+    pub fn binary(self) -> &'static str {
+        match self {
+            Tool::CargoFmt => "cargo",
+            Tool::DenoFmt => "deno",
+            Tool::SpotlessMaven => "mvn",
+            Tool::SpotlessGradle => "gradle",
+            tool => tool.name(),
+        }
+    }
+
+    /// Arguments that rewrite `files` in place
+    fn write_args(self, files: &[&Path]) -> Vec<OsString> {
+        let prefix: &[&str] = match self {
+            Tool::Oxfmt | Tool::Prettier => &["--write"],
+            Tool::Biome => &["format", "--write"],
+            Tool::Dprint | Tool::DenoFmt => &["fmt"],
+            Tool::CargoFmt => &["fmt", "--"],
+            Tool::Rustfmt | Tool::Black | Tool::SortPackageJson => &[],
+            Tool::Ruff => &["format"],
+            Tool::Autopep8 => &["--in-place"],
+            Tool::Yapf => &["-i"],
+            Tool::GoogleJavaFormat | Tool::PalantirJavaFormat => &["--replace"],
+            Tool::Goimports | Tool::Gofumpt | Tool::Gofmt => &["-w"],
+            Tool::Custom => unreachable!("custom commands expand their own arguments"),
+            Tool::SpotlessMaven => {
+                let mut spotless_files = OsString::from("-DspotlessFiles=");
+                for (i, file) in files.iter().enumerate() {
+                    if i > 0 {
+                        spotless_files.push(",");
+                    }
+                    spotless_files.push(file);
+                }
+                return vec!["spotless:apply".into(), spotless_files];
+            }
+            // Spotless for Gradle applies to the whole project
+            Tool::SpotlessGradle => return vec!["spotlessApply".into()],
+        };
+
+        let mut args: Vec<OsString> = prefix.iter().map(OsString::from).collect();
+        args.extend(files.iter().map(|file| file.as_os_str().to_owned()));
+        args
+    }
+
+    /// Arguments that read source on stdin and print the formatted result.
+    /// `file` is a hint used for language detection and config lookup.
+    /// `None` if the tool has no stdin mode.
+    fn stdin_args(self, file: &Path, cwd: &Path) -> Option<Vec<OsString>> {
+        let with_path = |flag: &str| {
+            let mut arg = OsString::from(flag);
+            arg.push(file);
+            arg
+        };
+
+        let args: Vec<OsString> = match self {
+            Tool::Biome => vec!["format".into(), with_path("--stdin-file-path=")],
+            Tool::Prettier => vec!["--stdin-filepath".into(), file.into()],
+            Tool::Dprint => vec!["fmt".into(), "--stdin".into(), file.into()],
+            Tool::DenoFmt => {
+                let ext = file.extension().unwrap_or_default();
+                vec!["fmt".into(), "--ext".into(), ext.into(), "-".into()]
+            }
+            Tool::CargoFmt | Tool::Rustfmt => {
+                let mut args: Vec<OsString> = vec!["--emit".into(), "stdout".into()];
+                if let Some(edition) = cargo_edition(cwd) {
+                    args.extend(["--edition".into(), edition.into()]);
+                }
+                args
+            }
+            Tool::Ruff => vec![
+                "format".into(),
+                "--stdin-filename".into(),
+                file.into(),
+                "-".into(),
+            ],
+            Tool::Black => vec![
+                "-q".into(),
+                "--stdin-filename".into(),
+                file.into(),
+                "-".into(),
+            ],
+            Tool::Autopep8 | Tool::GoogleJavaFormat | Tool::PalantirJavaFormat => {
+                vec!["-".into()]
+            }
+            Tool::Yapf | Tool::Gofumpt | Tool::Gofmt => vec![],
+            Tool::Goimports => vec!["-srcdir".into(), cwd.into()],
+            Tool::Oxfmt
+            | Tool::SpotlessMaven
+            | Tool::SpotlessGradle
+            | Tool::SortPackageJson
+            | Tool::Custom => {
+                return None;
+            }
+        };
+
+        Some(args)
+    }
+
+    /// Arguments that exit non-zero if `file` is not formatted, for tools
+    /// that cannot be checked by comparing their stdin output
+    fn check_args(self, file: &Path) -> Option<Vec<OsString>> {
+        match self {
+            Tool::Oxfmt | Tool::SortPackageJson => Some(vec!["--check".into(), file.into()]),
+            Tool::SpotlessMaven => {
+                let mut spotless_files = OsString::from("-DspotlessFiles=");
+                spotless_files.push(file);
+                Some(vec!["spotless:check".into(), spotless_files])
+            }
+            // spotlessCheck covers the whole project, not `file`
+            _ => None,
+        }
+    }
+}
+
+/// Why a formatter did not produce a result
+#[derive(Debug)]
+pub enum Failure {
+    /// The program could not be started
+    NotFound,
+    /// The tool cannot read source from stdin (nor check a file, when checking)
+    NoStdin,
+    /// The tool ran and reported an error
+    Error(String),
+}
+
+/// One program invocation
+#[derive(Debug, Clone, PartialEq)]
+pub struct Step {
+    pub tool: Tool,
+    pub program: PathBuf,
+    pub cwd: Option<PathBuf>,
+    /// Installed globally and looked up on PATH
+    pub global: bool,
+    /// Arguments for [`Tool::Custom`]
+    pub custom: Option<Custom>,
+    /// Set when `program` is a package manager that runs the tool
+    pub exec: Option<Exec>,
+}
+
+/// How a package manager runs a tool installed in the project, for projects
+/// without `node_modules/.bin` such as Yarn Plug'n'Play
+#[derive(Debug, Clone, PartialEq)]
+pub struct Exec {
+    /// Arguments before the tool's own, such as `exec prettier`
+    pub args: Vec<String>,
+    /// Environment that keeps the package manager from going online
+    pub env: Vec<(&'static str, &'static str)>,
+}
+
+/// A formatter command declared in the config, run once per file
+#[derive(Debug, Clone, PartialEq)]
+pub struct Custom {
+    /// Arguments after the program, with `{file}`, `{root}` and `{ext}`
+    /// placeholders
+    pub args: Vec<String>,
+    /// What `{root}` expands to
+    pub root: PathBuf,
+    /// Exit codes that mean the file was formatted
+    pub success_codes: Vec<i32>,
+}
+
+impl Custom {
+    /// Substitute the placeholders in `arg` for `file`
+    fn expand(&self, arg: &str, file: &Path) -> OsString {
+        let ext = file.extension().unwrap_or_default();
+        let placeholders = [
+            ("{file}", file.as_os_str()),
+            ("{root}", self.root.as_os_str()),
+            ("{ext}", ext),
+        ];
+
+        let mut expanded = OsString::new();
+        let mut rest = arg;
+        while let Some((start, name, value)) = placeholders
+            .iter()
+            .filter_map(|(name, value)| rest.find(name).map(|start| (start, name, value)))
+            .min_by_key(|(start, _, _)| *start)
+        {
+            expanded.push(&rest[..start]);
+            expanded.push(value);
+            rest = &rest[start + name.len()..];
+        }
+        expanded.push(rest);
+        expanded
+    }
+}
+
+impl Step {
+    /// Full path of the program, or why it can't be run
+    pub fn locate(&self) -> Result<PathBuf, String> {
+        if self.global {
+            let program = self.program.to_string_lossy();
+            find_on_path(&program).ok_or_else(|| format!("{} not found on PATH", program))
+        } else if self.program.is_file() {
+            Ok(self.program.clone())
+        } else {
+            Err(format!("{} does not exist", self.program.display()))
+        }
+    }
+
+    /// First line of the tool's version output
+    pub fn version(&self) -> Option<String> {
+        let args: &[&str] = match self.tool {
+            Tool::CargoFmt => &["fmt", "--version"],
+            // gofmt and goimports have no version flag
+            Tool::Gofmt | Tool::Goimports | Tool::Custom => return None,
+            _ => &["--version"],
+        };
+
+        let output = self
+            .command(&self.locate().ok()?, None)
+            .args(args)
+            .stdin(Stdio::null())
+            .output()
+            .ok()?;
+        let text = String::from_utf8_lossy(&output.stdout).into_owned()
+            + &String::from_utf8_lossy(&output.stderr);
+
+        // Skip banners such as Gradle's dashed lines
+        text.lines()
+            .map(str::trim)
+            .find(|line| line.bytes().any(|b| b.is_ascii_digit()))
+            .map(str::to_string)
+    }
+
+    fn command(&self, program: &Path, cwd: Option<&Path>) -> Command {
+        let mut cmd = Command::new(program);
+        if let Some(dir) = cwd.or(self.cwd.as_deref()) {
+            cmd.current_dir(dir);
+        }
+        if let Some(exec) = &self.exec {
+            cmd.args(&exec.args).envs(exec.env.iter().copied());
+        }
+        cmd
+    }
+
+    fn write(&self, files: &[&Path]) -> Result<(), Failure> {
+        if let Some(custom) = &self.custom {
+            return files.iter().try_for_each(|file_path| {
+                let args: Vec<OsString> = custom
+                    .args
+                    .iter()
+                    .map(|arg| custom.expand(arg, file_path))
+                    .collect();
+                let output = self.command(&self.program, None).args(args).output();
+                check_status(output, |code| custom.success_codes.contains(&code)).map(|_| ())
+            });
+        }
+
+        // rustfmt (and cargo fmt) panic on non-UTF-8 arguments, so pipe those
+        // files through rustfmt instead
+        if matches!(self.tool, Tool::CargoFmt | Tool::Rustfmt)
+            && files.iter().any(|file| file.to_str().is_none())
+        {
+            return files.iter().try_for_each(|file_path| {
+                let content = fs::read(file_path).map_err(|e| Failure::Error(e.to_string()))?;
+                let output = self.pipe(file_path, content)?;
+                fs::write(file_path, output).map_err(|e| Failure::Error(e.to_string()))
+            });
+        }
+
+        let output = self
+            .command(&self.program, None)
+            .args(self.tool.write_args(files))
+            .output();
+        check_output(output).map(|_| ())
+    }
+
+    /// `None` if the file is formatted, otherwise what the tool reported
+    fn check(&self, file_path: &Path) -> Result<Option<String>, Failure> {
+        let Some(args) = self.tool.check_args(file_path) else {
+            return Err(Failure::NoStdin);
+        };
+
+        match check_output(self.command(&self.program, None).args(args).output()) {
+            Ok(_) => Ok(None),
+            Err(Failure::Error(output)) => Ok(Some(output)),
+            Err(failure) => Err(failure),
+        }
+    }
+
+    fn pipe(&self, file_path: &Path, input: Vec<u8>) -> Result<Vec<u8>, Failure> {
+        let cwd = self
+            .cwd
+            .clone()
+            .or_else(|| existing_parent(file_path))
+            .unwrap_or_else(|| PathBuf::from("."));
+        let args = self
+            .tool
+            .stdin_args(file_path, &cwd)
+            .ok_or(Failure::NoStdin)?;
+
+        // cargo fmt cannot read stdin; rustfmt from the crate root is equivalent
+        let program = match self.tool {
+            Tool::CargoFmt => Path::new("rustfmt"),
+            _ => self.program.as_path(),
+        };
+
+        let child = self
+            .command(program, Some(&cwd))
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn();
+
+        let output = child.and_then(|mut child| {
+            // Feed stdin from a thread so a large output can't deadlock the pipe
+            let mut stdin = child.stdin.take().expect("stdin is piped");
+            let writer = thread::spawn(move || stdin.write_all(&input));
+            let output = child.wait_with_output();
+            let _ = writer.join();
+            output
+        });
+
+        check_output(output).map(|output| output.stdout)
+    }
+}
+
+/// A formatter picked for a file: one or more steps run in sequence
+#[derive(Debug, Clone, PartialEq)]
+pub struct Formatter {
+    pub name: String,
+    pub steps: Vec<Step>,
+    /// Try the next formatter in the chain if this one fails
+    pub fallthrough: bool,
+}
+
+impl Formatter {
+    /// A formatter installed at a known path
+    pub fn local(tool: Tool, program: PathBuf) -> Self {
+        Self::from_step(Step {
+            tool,
+            program,
+            cwd: None,
+            global: false,
+            custom: None,
+            exec: None,
+        })
+    }
+
+    /// A formatter looked up on PATH
+    pub fn global(tool: Tool) -> Self {
+        Self::from_step(Step {
+            tool,
+            program: PathBuf::from(tool.binary()),
+            cwd: None,
+            global: true,
+            custom: None,
+            exec: None,
+        })
+    }
+
+    /// A formatter installed in the project at `root`, run by the package
+    /// manager `program` from that directory
+    pub fn exec(tool: Tool, program: &str, exec: Exec, root: &Path) -> Self {
+        Self::from_step(Step {
+            tool,
+            program: PathBuf::from(program),
+            cwd: Some(root.to_path_buf()),
+            global: true,
+            custom: None,
+            exec: Some(exec),
+        })
+    }
+
+    /// A command declared in the config. `program` is looked up on PATH
+    /// unless it is a path.
+    pub fn custom(name: &str, program: PathBuf, custom: Custom) -> Self {
+        let global = program.components().count() == 1 && !program.is_absolute();
+        Self {
+            name: name.to_string(),
+            steps: vec![Step {
+                tool: Tool::Custom,
+                program,
+                cwd: None,
+                global,
+                custom: Some(custom),
+                exec: None,
+            }],
+            fallthrough: false,
+        }
+    }
+
+    fn from_step(step: Step) -> Self {
+        Self {
+            name: step.tool.name().to_string(),
+            steps: vec![step],
+            fallthrough: false,
+        }
+    }
+
+    /// Run from `dir` instead of the current directory
+    pub fn in_dir(mut self, dir: Option<&Path>) -> Self {
+        for step in &mut self.steps {
+            step.cwd = dir.map(Path::to_path_buf);
+        }
+        self
+    }
+
+    /// On failure, let the next formatter in the chain have a go
+    pub fn or_next(mut self) -> Self {
+        self.fallthrough = true;
+        self
+    }
+
+    /// Run `other` after this formatter
+    pub fn then(mut self, other: Formatter) -> Self {
+        self.name = format!("{} + {}", self.name, other.name);
+        self.steps.extend(other.steps);
+        self
+    }
+
+    /// Whether every program is installed
+    pub fn is_available(&self) -> bool {
+        self.steps.iter().all(|step| step.locate().is_ok())
+    }
+
+    /// Format `file_path` in place
+    pub fn write(&self, file_path: &Path) -> Result<(), Failure> {
+        self.write_all(&[file_path])
+    }
+
+    /// Format several files in place with a single invocation per step
+    pub fn write_all(&self, files: &[&Path]) -> Result<(), Failure> {
+        self.steps.iter().try_for_each(|step| step.write(files))
+    }
+
+    /// Format `content` through a temporary file next to `file_path`, for
+    /// tools that cannot read stdin. Sitting in the same directory, the file
+    /// picks up the same project config and root.
+    pub fn pipe_via_file(&self, file_path: &Path, content: &str) -> Result<String, Failure> {
+        // Spotless for Gradle would format the whole project, not just the file
+        if self
+            .steps
+            .iter()
+            .any(|step| step.tool == Tool::SpotlessGradle)
+        {
+            return Err(Failure::NoStdin);
+        }
+
+        let scratch = scratch_path(file_path);
+        fs::write(&scratch, content).map_err(|e| Failure::Error(e.to_string()))?;
+        let output = self
+            .write(&scratch)
+            .and_then(|()| fs::read_to_string(&scratch).map_err(|e| Failure::Error(e.to_string())));
+        let _ = fs::remove_file(&scratch);
+
+        output
+    }
+
+    /// Run the tools' own check mode on `file_path`: `None` if it is
+    /// formatted, otherwise what the tool reported. Fails with
+    /// [`Failure::NoStdin`] if a tool has no check mode either.
+    pub fn check(&self, file_path: &Path) -> Result<Option<String>, Failure> {
+        for step in &self.steps {
+            if let Some(report) = step.check(file_path)? {
+                return Ok(Some(report));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Format `content` as if it were the contents of `file_path`
+    pub fn pipe(&self, file_path: &Path, content: &str) -> Result<String, Failure> {
+        let mut output = content.as_bytes().to_vec();
+        for step in &self.steps {
+            output = step.pipe(file_path, output)?;
+        }
+        String::from_utf8(output).map_err(|e| Failure::Error(e.to_string()))
+    }
+}
+
+fn check_output(output: std::io::Result<Output>) -> Result<Output, Failure> {
+    check_status(output, |code| code == 0)
+}
+
+/// Like [`check_output`], with `success` deciding which exit codes succeed
+fn check_status(
+    output: std::io::Result<Output>,
+    success: impl Fn(i32) -> bool,
+) -> Result<Output, Failure> {
+    match output {
+        Ok(output) if output.status.code().is_some_and(&success) => Ok(output),
+        // Some tools (biome) print diagnostics on stdout
+        Ok(output) => {
+            let message = if output.stderr.iter().all(u8::is_ascii_whitespace) {
+                &output.stdout
+            } else {
+                &output.stderr
+            };
+            Err(Failure::Error(
+                String::from_utf8_lossy(message).into_owned(),
+            ))
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Err(Failure::NotFound),
+        Err(e) => Err(Failure::Error(e.to_string())),
+    }
+}
+
+/// Nearest existing directory containing `file_path`
+fn existing_parent(file_path: &Path) -> Option<PathBuf> {
+    file_path
+        .ancestors()
+        .skip(1)
+        .find(|dir| dir.is_dir())
+        .map(Path::to_path_buf)
+}
+
+/// Hidden file beside `file_path` (or in its nearest existing directory)
+/// with the same extension, so tools detect the same language
+fn scratch_path(file_path: &Path) -> PathBuf {
+    let dir = existing_parent(file_path).unwrap_or_else(|| PathBuf::from("."));
+    let stem = file_path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    let mut name = OsString::from(format!(".{}.ralph-hook-fmt-{}", stem, std::process::id()));
+    if let Some(ext) = file_path.extension() {
+        name.push(".");
+        name.push(ext);
+    }
+    dir.join(name)
+}
+
+/// Read the `edition` key from a crate's Cargo.toml
+fn cargo_edition(root: &Path) -> Option<String> {
+    let manifest = fs::read_to_string(root.join("Cargo.toml")).ok()?;
+    manifest.lines().find_map(|line| {
+        let value = line.trim().strip_prefix("edition")?.trim_start();
+        let value = value.strip_prefix('=')?.trim();
+        Some(value.trim_matches('"').to_string())
+    })
+}
+
+/// Look a program up on PATH, the way the OS would when spawning it.
+/// Done in-process so a missing `which` can't hide installed tools.
+pub fn find_on_path(program: &str) -> Option<PathBuf> {
+    let path = env::var_os("PATH")?;
+    let extensions: &[&str] = if cfg!(windows) {
+        &["exe", "cmd", "bat"]
+    } else {
+        &[]
+    };
+
+    env::split_paths(&path)
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .find_map(|dir| {
+            let candidate = dir.join(program);
+            if is_executable(&candidate) {
+                return Some(candidate);
+            }
+            extensions
+                .iter()
+                .map(|ext| candidate.with_extension(ext))
+                .find(|candidate| is_executable(candidate))
+        })
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    fs::metadata(path).is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_args_end_with_file() {
+        let file = Path::new("/p/a.ts");
+        assert_eq!(
+            Tool::Biome.write_args(&[file]),
+            vec!["format", "--write", "/p/a.ts"]
+        );
+        assert_eq!(
+            Tool::SpotlessMaven.write_args(&[file]),
+            vec!["spotless:apply", "-DspotlessFiles=/p/a.ts"]
+        );
+    }
+
+    #[test]
+    fn test_tool_keys_round_trip() {
+        for tool in Tool::ALL {
+            assert_eq!(Tool::from_key(tool.key()), Some(tool));
+        }
+        assert_eq!(Tool::from_key("cargo fmt"), Some(Tool::CargoFmt));
+        assert_eq!(Tool::from_key("spotless"), None);
+    }
+
+    #[test]
+    fn test_write_args_many_files() {
+        let files = [Path::new("/p/A.java"), Path::new("/p/B.java")];
+        assert_eq!(
+            Tool::GoogleJavaFormat.write_args(&files),
+            vec!["--replace", "/p/A.java", "/p/B.java"]
+        );
+        assert_eq!(
+            Tool::SpotlessMaven.write_args(&files),
+            vec!["spotless:apply", "-DspotlessFiles=/p/A.java,/p/B.java"]
+        );
+        assert_eq!(
+            Tool::SpotlessGradle.write_args(&files),
+            vec!["spotlessApply"]
+        );
+    }
+
+    #[test]
+    fn test_stdin_args() {
+        let file = Path::new("/p/a.py");
+        assert_eq!(
+            Tool::Ruff.stdin_args(file, Path::new("/p")).unwrap(),
+            vec!["format", "--stdin-filename", "/p/a.py", "-"]
+        );
+        assert_eq!(
+            Tool::Biome.stdin_args(file, Path::new("/p")).unwrap(),
+            vec!["format", "--stdin-file-path=/p/a.py"]
+        );
+        assert!(
+            Tool::SpotlessGradle
+                .stdin_args(file, Path::new("/p"))
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_check_args_cover_tools_without_stdin() {
+        let file = Path::new("/p/a");
+        let cwd = Path::new("/p");
+        for tool in [
+            Tool::Oxfmt,
+            Tool::SpotlessMaven,
+            Tool::Prettier,
+            Tool::Rustfmt,
+            Tool::Gofmt,
+        ] {
+            assert!(
+                tool.stdin_args(file, cwd).is_some() || tool.check_args(file).is_some(),
+                "{} cannot be checked",
+                tool.name()
+            );
+        }
+        assert_eq!(
+            Tool::Oxfmt.check_args(file).unwrap(),
+            vec!["--check", "/p/a"]
+        );
+        assert!(Tool::SpotlessGradle.check_args(file).is_none());
+    }
+
+    #[test]
+    fn test_then_combines_names_and_steps() {
+        let formatter = Formatter::global(Tool::Goimports).then(Formatter::global(Tool::Gofumpt));
+        assert_eq!(formatter.name, "goimports + gofumpt");
+        assert_eq!(formatter.steps.len(), 2);
+    }
+
+    #[test]
+    fn test_custom_expands_placeholders() {
+        let custom = Custom {
+            args: Vec::new(),
+            root: PathBuf::from("/p"),
+            success_codes: vec![0],
+        };
+        let file = Path::new("/p/src/a.sql");
+        assert_eq!(
+            custom.expand("--config={root}/{ext}.cfg", file),
+            OsString::from("--config=/p/sql.cfg")
+        );
+        assert_eq!(
+            custom.expand("{file}", file),
+            OsString::from("/p/src/a.sql")
+        );
+        assert_eq!(custom.expand("{other}", file), OsString::from("{other}"));
+    }
+
+    #[test]
+    fn test_scratch_path_keeps_extension() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let hint = temp_dir.path().join("new/dir/app.tsx");
+        let scratch = scratch_path(&hint);
+
+        assert_eq!(scratch.parent(), Some(temp_dir.path()));
+        assert_eq!(scratch.extension().and_then(|e| e.to_str()), Some("tsx"));
+        assert!(
+            scratch
+                .file_name()
+                .unwrap()
+                .to_string_lossy()
+                .starts_with(".app.ralph-hook-fmt-")
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_find_on_path() {
+        // sh is on every unix PATH; directories and non-executables are not programs
+        assert!(find_on_path("sh").is_some_and(|path| path.is_absolute()));
+        assert!(find_on_path("ralph-hook-fmt-no-such-tool").is_none());
+    }
+
+    #[test]
+    fn test_locate_reports_why() {
+        let missing = Formatter::local(Tool::Prettier, PathBuf::from("/nonexistent/prettier"));
+        assert_eq!(
+            missing.steps[0].locate(),
+            Err("/nonexistent/prettier does not exist".to_string())
+        );
+        assert!(!missing.is_available());
+    }
+
+    #[test]
+    fn test_missing_program_is_not_found() {
+        let formatter = Formatter::local(Tool::Prettier, PathBuf::from("/nonexistent/prettier"));
+        assert!(matches!(
+            formatter.pipe(Path::new("/tmp/a.ts"), "x"),
+            Err(Failure::NotFound)
+        ));
+    }
+}
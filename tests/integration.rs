@@ -12,9 +12,14 @@ fn run_hook_with_input_no_debug(input: &str) -> String {
 }
 
 fn run_hook_with_input_with_args(input: &str, args: &[&str]) -> String {
+    run_hook_with_env(input, args, &[])
+}
+
+fn run_hook_with_env(input: &str, args: &[&str], envs: &[(&str, &std::path::Path)]) -> String {
     let binary = env!("CARGO_BIN_EXE_ralph-hook-fmt");
     let mut child = Command::new(binary)
         .args(args)
+        .envs(envs.iter().copied())
         .stdin(Stdio::piped())
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
@@ -39,6 +44,28 @@ fn make_hook_input(file_path: &std::path::Path) -> String {
     )
 }
 
+/// Helper to create an executable shell script at `path` that runs `script`
+#[cfg(unix)]
+fn create_script(path: &std::path::Path, script: &str) {
+    use std::os::unix::fs::PermissionsExt;
+
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, format!("#!/bin/sh\n{}\n", script)).unwrap();
+    fs::set_permissions(path, fs::Permissions::from_mode(0o755)).unwrap();
+}
+
+/// Helper to create a node_modules/.bin formatter that runs `script`
+#[cfg(unix)]
+fn create_mock_formatter_script(path: &std::path::Path, name: &str, script: &str) {
+    create_script(&path.join("node_modules/.bin").join(name), script);
+}
+
+/// Helper to create a Python formatter in the virtualenv `venv` that runs `script`
+#[cfg(unix)]
+fn create_mock_venv_formatter(venv: &std::path::Path, name: &str, script: &str) {
+    create_script(&venv.join("bin").join(name), script);
+}
+
 // ============================================================================
 // Basic error handling tests
 // ============================================================================
@@ -60,6 +87,60 @@ fn test_nonexistent_file() {
     assert!(output.contains("does not exist"));
 }
 
+#[test]
+fn test_relative_path_resolved_against_cwd() {
+    let temp_dir = TempDir::new().unwrap();
+    let sub_dir = temp_dir.path().join("sub");
+    fs::create_dir_all(&sub_dir).unwrap();
+    fs::write(sub_dir.join("file.xyz"), "content").unwrap();
+
+    let input = format!(
+        r#"{{"cwd": "{}", "tool_name": "Write", "tool_input": {{"file_path": "sub/file.xyz"}}}}"#,
+        temp_dir.path().display()
+    );
+    let output = run_hook_with_input(&input);
+    assert!(output.contains("continue"));
+    assert!(!output.contains("does not exist"), "{}", output);
+    assert!(output.contains("Unsupported"), "{}", output);
+}
+
+#[test]
+fn test_escaped_path_is_decoded() {
+    let temp_dir = TempDir::new().unwrap();
+    let dir = temp_dir.path().join("caf\u{e9} \u{1F600}");
+    fs::create_dir_all(&dir).unwrap();
+    let file_path = dir.join("a\"b\nc.xyz");
+    fs::write(&file_path, "content").unwrap();
+
+    // Encode the path the way a strict JSON encoder would: every non-ASCII
+    // character as \uXXXX (surrogate pairs above the BMP) and `/` as `\/`
+    let mut escaped = String::new();
+    for c in file_path.to_str().unwrap().chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '/' => escaped.push_str("\\/"),
+            '\n' => escaped.push_str("\\n"),
+            c if c.is_ascii() => escaped.push(c),
+            c => {
+                let mut units = [0u16; 2];
+                for unit in c.encode_utf16(&mut units) {
+                    escaped.push_str(&format!("\\u{:04x}", unit));
+                }
+            }
+        }
+    }
+
+    let input = format!(
+        r#"{{"tool_name": "Write", "tool_input": {{"file_path": "{}"}}}}"#,
+        escaped
+    );
+    let output = run_hook_with_input(&input);
+    assert!(output.contains("continue"));
+    assert!(!output.contains("does not exist"), "{}", output);
+    assert!(output.contains("Unsupported"), "{}", output);
+}
+
 #[test]
 fn test_unsupported_extension() {
     let temp_dir = TempDir::new().unwrap();
@@ -537,18 +618,10 @@ fn test_monorepo_finds_nearest_package_json() {
 // Monorepo formatter priority tests
 // ============================================================================
 
-/// Helper to create a mock formatter script
+/// Helper to create a mock formatter that just exits successfully
 #[cfg(unix)]
 fn create_mock_formatter(path: &std::path::Path, name: &str) {
-    use std::os::unix::fs::PermissionsExt;
-
-    let bin_dir = path.join("node_modules/.bin");
-    fs::create_dir_all(&bin_dir).unwrap();
-
-    let formatter_path = bin_dir.join(name);
-    // Create a script that just exits successfully
-    fs::write(&formatter_path, "#!/bin/sh\nexit 0\n").unwrap();
-    fs::set_permissions(&formatter_path, fs::Permissions::from_mode(0o755)).unwrap();
+    create_mock_formatter_script(path, name, "exit 0");
 }
 
 #[cfg(not(unix))]
@@ -625,25 +698,33 @@ fn test_monorepo_falls_back_to_root_formatter() {
     let temp_dir = TempDir::new().unwrap();
     let project_dir = temp_dir.path();
 
-    // Create monorepo root with prettier
-    fs::write(project_dir.join("package.json"), r#"{"name": "monorepo"}"#).unwrap();
+    // Create workspace root with prettier hoisted to it
+    fs::write(
+        project_dir.join("package.json"),
+        r#"{"name": "monorepo", "workspaces": ["packages/*"]}"#,
+    )
+    .unwrap();
     create_mock_formatter(project_dir, "prettier");
 
     // Create package WITHOUT any formatter
     let pkg_dir = project_dir.join("packages/my-pkg");
     fs::create_dir_all(&pkg_dir).unwrap();
     fs::write(pkg_dir.join("package.json"), r#"{"name": "my-pkg"}"#).unwrap();
-    // Note: no formatter installed at package level
 
     let file_path = pkg_dir.join("index.js");
     fs::write(&file_path, "const x = 1;").unwrap();
 
     let output = run_hook_with_input(&make_hook_input(&file_path));
+    assert!(
+        output.contains("Formatted with prettier"),
+        "Should use prettier hoisted to the workspace root: {}",
+        output
+    );
 
-    assert!(output.contains("continue"));
-    assert!(output.contains("true"));
-    // Should NOT find prettier because we look for formatters only at the nearest package.json level
-    // This tests that we don't accidentally fall back to root when package exists
+    // Without a workspace, a parent package's formatters are not used
+    fs::write(project_dir.join("package.json"), r#"{"name": "monorepo"}"#).unwrap();
+    let output = run_hook_with_input(&make_hook_input(&file_path));
+    assert!(!output.contains("Formatted with prettier"), "{}", output);
 }
 
 #[test]
@@ -943,3 +1024,1550 @@ fn test_without_project_only_preserves_existing_behavior() {
         output
     );
 }
+
+// ============================================================================
+// MultiEdit / NotebookEdit tests
+// ============================================================================
+
+#[test]
+fn test_multi_edit_is_formatted() {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("file.xyz");
+    fs::write(&file_path, "content").unwrap();
+
+    let input = format!(
+        r#"{{"tool_name": "MultiEdit", "tool_input": {{"file_path": "{}", "edits": []}}}}"#,
+        file_path.display()
+    );
+    let output = run_hook_with_input(&input);
+    assert!(output.contains("continue"));
+    assert!(output.contains("Unsupported"), "{}", output);
+}
+
+#[test]
+fn test_other_tools_are_ignored() {
+    let input = r#"{"tool_name": "Bash", "tool_input": {"command": "ls"}}"#;
+    let output = run_hook_with_input(input);
+    assert!(output.contains("continue"));
+    assert!(output.contains("Ignoring Bash tool"), "{}", output);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_notebook_edit_formats_code_cells_only() {
+    let temp_dir = TempDir::new().unwrap();
+    let project_dir = temp_dir.path();
+    fs::write(
+        project_dir.join("pyproject.toml"),
+        "[project]\nname = \"nb\"\n",
+    )
+    .unwrap();
+    // "ruff format <file>" -> add spaces around "="
+    create_mock_venv_formatter(
+        &project_dir.join(".venv"),
+        "ruff",
+        r#"sed -i 's/x=1/x = 1/' "$2""#,
+    );
+
+    let notebook = r#"{
+ "cells": [
+  {
+   "cell_type": "markdown",
+   "metadata": {},
+   "source": ["x=1"]
+  },
+  {
+   "cell_type": "markdown",
+   "metadata": {},
+   "source": ["caf\u00e9 \/ 1.50"]
+  },
+  {
+   "cell_type": "code",
+   "execution_count": 1,
+   "metadata": {},
+   "outputs": [],
+   "source": ["x=1\n", "print(x)"]
+  },
+  {
+   "cell_type": "code",
+   "execution_count": null,
+   "metadata": {},
+   "outputs": [],
+   "source": ["%time x=1"]
+  }
+ ],
+ "metadata": {
+  "kernelspec": {"display_name": "Python 3", "language": "python", "name": "python3"}
+ },
+ "nbformat": 4,
+ "nbformat_minor": 5
+}
+"#;
+    let file_path = project_dir.join("analysis.ipynb");
+    fs::write(&file_path, notebook).unwrap();
+
+    let input = format!(
+        r#"{{"tool_name": "NotebookEdit", "tool_input": {{"notebook_path": "{}", "new_source": "x=1"}}}}"#,
+        file_path.display()
+    );
+    let output = run_hook_with_input_with_args(&input, &["--debug", "--project-only"]);
+    assert!(output.contains("Formatted with ruff"), "{}", output);
+
+    let formatted = fs::read_to_string(&file_path).unwrap();
+    // Markdown cells and magics are left untouched
+    assert!(formatted.contains(r#""x=1""#), "{}", formatted);
+    assert!(formatted.contains(r#""x = 1\n""#), "{}", formatted);
+    assert!(formatted.contains(r#""%time x=1""#));
+    assert!(
+        formatted.contains(r#""caf\u00e9 \/ 1.50""#),
+        "{}",
+        formatted
+    );
+    assert!(formatted.contains(r#""execution_count": null"#));
+    assert!(formatted.ends_with("}\n"));
+
+    // No scratch files are left behind
+    let leftovers: Vec<_> = fs::read_dir(project_dir)
+        .unwrap()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_name().to_string_lossy().contains("ralph-hook-fmt"))
+        .collect();
+    assert!(leftovers.is_empty());
+}
+
+// ============================================================================
+// additionalContext tests
+// ============================================================================
+
+#[cfg(unix)]
+#[test]
+fn test_changes_are_reported_as_additional_context() {
+    let temp_dir = TempDir::new().unwrap();
+    let project_dir = temp_dir.path();
+    fs::write(project_dir.join("package.json"), r#"{"name": "test"}"#).unwrap();
+    // "oxfmt --write <file>" -> add spaces around "="
+    create_mock_formatter_script(project_dir, "oxfmt", r#"sed -i 's/x=1/x = 1/' "$2""#);
+
+    let file_path = project_dir.join("index.js");
+    fs::write(&file_path, "const a = 0;\nconst x=1;\n").unwrap();
+
+    let output = run_hook_with_input_no_debug(&make_hook_input(&file_path));
+    assert!(
+        output.starts_with(r#"{"continue":true,"hookSpecificOutput":"#),
+        "{}",
+        output
+    );
+    assert!(
+        output.contains(r#""hookEventName":"PostToolUse""#),
+        "{}",
+        output
+    );
+    assert!(output.contains("reformatted with oxfmt"), "{}", output);
+    assert!(
+        output.contains(r#"-const x=1;\n+const x = 1;\n"#),
+        "{}",
+        output
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_unchanged_file_has_no_additional_context() {
+    let temp_dir = TempDir::new().unwrap();
+    let project_dir = temp_dir.path();
+    fs::write(project_dir.join("package.json"), r#"{"name": "test"}"#).unwrap();
+    create_mock_formatter(project_dir, "oxfmt");
+
+    let file_path = project_dir.join("index.js");
+    fs::write(&file_path, "const x = 1;\n").unwrap();
+
+    let output = run_hook_with_input_no_debug(&make_hook_input(&file_path));
+    assert_eq!(output.trim(), r#"{"continue":true}"#);
+}
+
+// ============================================================================
+// --pre-tool-use tests
+// ============================================================================
+
+fn make_write_input(file_path: &std::path::Path, content: &str) -> String {
+    format!(
+        r#"{{"hook_event_name": "PreToolUse", "tool_name": "Write", "tool_input": {{"file_path": "{}", "content": "{}"}}}}"#,
+        file_path.display(),
+        content
+    )
+}
+
+#[cfg(unix)]
+#[test]
+fn test_pre_tool_use_returns_formatted_content() {
+    let temp_dir = TempDir::new().unwrap();
+    let project_dir = temp_dir.path();
+    fs::write(project_dir.join("package.json"), r#"{"name": "test"}"#).unwrap();
+    // "prettier --stdin-filepath <file>" reads stdin and prints the result
+    create_mock_formatter_script(project_dir, "prettier", "sed 's/x=1/x = 1/'");
+
+    // The file does not exist yet
+    let file_path = project_dir.join("src/index.js");
+    let input = make_write_input(&file_path, r#"const x=1;\n"#);
+    let output = run_hook_with_input_with_args(&input, &["--pre-tool-use", "--project-only"]);
+
+    assert!(
+        output.contains(r#""hookEventName":"PreToolUse""#),
+        "{}",
+        output
+    );
+    assert!(
+        output.contains(r#""updatedInput":{"file_path":"#),
+        "{}",
+        output
+    );
+    assert!(
+        output.contains(r#""content":"const x = 1;\n""#),
+        "{}",
+        output
+    );
+    assert!(!file_path.exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_pre_tool_use_without_stdin_support_uses_temp_file() {
+    let temp_dir = TempDir::new().unwrap();
+    let project_dir = temp_dir.path();
+    fs::write(project_dir.join("package.json"), r#"{"name": "test"}"#).unwrap();
+    // "oxfmt --write <file>" only works on files
+    create_mock_formatter_script(project_dir, "oxfmt", r#"sed -i 's/x=1/x = 1/' "$2""#);
+
+    let file_path = project_dir.join("index.js");
+    let input = make_write_input(&file_path, "const x=1;");
+    let output = run_hook_with_input_with_args(&input, &["--pre-tool-use", "--project-only"]);
+
+    assert!(output.contains(r#""content":"const x = 1;""#), "{}", output);
+    assert!(!file_path.exists());
+
+    // The temp file is cleaned up
+    let leftovers: Vec<_> = fs::read_dir(project_dir)
+        .unwrap()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_name().to_string_lossy().contains("ralph-hook-fmt"))
+        .collect();
+    assert!(leftovers.is_empty());
+}
+
+#[test]
+fn test_pre_tool_use_ignores_edit() {
+    let input = r#"{"tool_name": "Edit", "tool_input": {"file_path": "/tmp/a.rs", "old_string": "a", "new_string": "b"}}"#;
+    let output = run_hook_with_input_with_args(input, &["--pre-tool-use", "--debug"]);
+    assert!(output.contains("Only Write content"), "{}", output);
+}
+
+#[test]
+fn test_pre_tool_use_rust_content() {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("main.rs");
+
+    let input = make_write_input(&file_path, "fn main(){let x=1;}");
+    let output = run_hook_with_input_with_args(&input, &["--pre-tool-use", "--debug"]);
+
+    assert!(output.contains("continue"));
+    if output.contains("Formatted with rustfmt") {
+        assert!(output.contains("let x = 1;"), "{}", output);
+    }
+}
+
+// ============================================================================
+// --batch / --on-stop tests
+// ============================================================================
+
+fn make_session_input(session_id: &str, file_path: &std::path::Path) -> String {
+    format!(
+        r#"{{"session_id": "{}", "tool_name": "Edit", "tool_input": {{"file_path": "{}"}}}}"#,
+        session_id,
+        file_path.display()
+    )
+}
+
+#[cfg(unix)]
+#[test]
+fn test_batch_formats_session_files_on_stop() {
+    let temp_dir = TempDir::new().unwrap();
+    let state_dir = TempDir::new().unwrap();
+    let envs = [("TMPDIR", state_dir.path())];
+    let project_dir = temp_dir.path();
+    fs::write(project_dir.join("package.json"), r#"{"name": "test"}"#).unwrap();
+
+    // "oxfmt --write <files>...", logging how many files each run was given
+    let calls = project_dir.join("calls");
+    create_mock_formatter_script(
+        project_dir,
+        "oxfmt",
+        &format!(
+            r#"shift; echo "$#" >> "{}"; for f in "$@"; do sed -i 's/x=1/x = 1/' "$f"; done"#,
+            calls.display()
+        ),
+    );
+
+    let a = project_dir.join("a.js");
+    let b = project_dir.join("b.js");
+    fs::write(&a, "const x=1;\n").unwrap();
+    fs::write(&b, "const x=1;\n").unwrap();
+
+    // Edits are only recorded
+    for file_path in [&a, &b, &a] {
+        let output = run_hook_with_env(
+            &make_session_input("s1", file_path),
+            &["--batch", "--project-only"],
+            &envs,
+        );
+        assert_eq!(output.trim(), r#"{"continue":true}"#);
+    }
+    assert_eq!(fs::read_to_string(&a).unwrap(), "const x=1;\n");
+
+    let stop = format!(
+        r#"{{"session_id": "s1", "cwd": "{}", "hook_event_name": "Stop", "stop_hook_active": false}}"#,
+        fs::canonicalize(project_dir).unwrap().display()
+    );
+    let output = run_hook_with_env(&stop, &["--on-stop", "--project-only"], &envs);
+
+    assert!(
+        output.contains(r#"Formatted 2 of 2 files\noxfmt: a.js, b.js"#),
+        "{}",
+        output
+    );
+    assert_eq!(fs::read_to_string(&a).unwrap(), "const x = 1;\n");
+    assert_eq!(fs::read_to_string(&b).unwrap(), "const x = 1;\n");
+    // One oxfmt run covered both files
+    assert_eq!(fs::read_to_string(&calls).unwrap(), "2\n");
+
+    // The ledger is cleared after stopping
+    let output = run_hook_with_env(&stop, &["--on-stop"], &envs);
+    assert_eq!(output.trim(), r#"{"continue":true}"#);
+}
+
+#[test]
+fn test_batch_sessions_are_separate() {
+    let temp_dir = TempDir::new().unwrap();
+    let state_dir = TempDir::new().unwrap();
+    let envs = [("TMPDIR", state_dir.path())];
+    let file_path = temp_dir.path().join("file.xyz");
+    fs::write(&file_path, "content").unwrap();
+
+    run_hook_with_env(&make_session_input("s1", &file_path), &["--batch"], &envs);
+
+    let output = run_hook_with_env(r#"{"session_id": "s2"}"#, &["--on-stop"], &envs);
+    assert_eq!(output.trim(), r#"{"continue":true}"#);
+
+    let output = run_hook_with_env(r#"{"session_id": "s1"}"#, &["--on-stop"], &envs);
+    assert!(output.contains("Formatted 0 of 1 file"), "{}", output);
+    assert!(
+        output.contains("Unsupported file extension: xyz"),
+        "{}",
+        output
+    );
+}
+
+// ============================================================================
+// --block-on-error tests
+// ============================================================================
+
+/// A local prettier that rejects every file with a syntax error
+#[cfg(unix)]
+fn create_failing_prettier(project_dir: &std::path::Path) {
+    fs::write(project_dir.join("package.json"), r#"{"name": "test"}"#).unwrap();
+    create_mock_formatter_script(
+        project_dir,
+        "prettier",
+        r#"printf '\033[31m[error]\033[39m %s: SyntaxError: Missing semicolon. (1:8)\n' "$2" >&2; exit 2"#,
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_block_on_error_returns_diagnostics() {
+    let temp_dir = TempDir::new().unwrap();
+    let project_dir = temp_dir.path();
+    create_failing_prettier(project_dir);
+
+    let file_path = project_dir.join("index.js");
+    fs::write(&file_path, "const x y;\n").unwrap();
+
+    let output = run_hook_with_input_with_args(
+        &make_hook_input(&file_path),
+        &["--block-on-error", "--project-only"],
+    );
+    assert!(
+        output.starts_with(r#"{"decision":"block","reason":"#),
+        "{}",
+        output
+    );
+    assert!(
+        output.contains(&format!(
+            r#"{}:1:8: SyntaxError: Missing semicolon."#,
+            file_path.display()
+        )),
+        "{}",
+        output
+    );
+    assert!(!output.contains(r#"\u001b"#), "{}", output);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_syntax_errors_do_not_block_by_default() {
+    let temp_dir = TempDir::new().unwrap();
+    let project_dir = temp_dir.path();
+    create_failing_prettier(project_dir);
+
+    let file_path = project_dir.join("index.js");
+    fs::write(&file_path, "const x y;\n").unwrap();
+
+    let output = run_hook_with_input_with_args(&make_hook_input(&file_path), &["--project-only"]);
+    assert_eq!(output.trim(), r#"{"continue":true}"#);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_block_on_error_ignores_other_failures() {
+    let temp_dir = TempDir::new().unwrap();
+    let project_dir = temp_dir.path();
+    fs::write(project_dir.join("package.json"), r#"{"name": "test"}"#).unwrap();
+    create_mock_formatter_script(
+        project_dir,
+        "prettier",
+        "echo 'config not found' >&2; exit 2",
+    );
+
+    let file_path = project_dir.join("index.js");
+    fs::write(&file_path, "const x = 1;\n").unwrap();
+
+    let output = run_hook_with_input_with_args(
+        &make_hook_input(&file_path),
+        &["--block-on-error", "--project-only"],
+    );
+    assert_eq!(output.trim(), r#"{"continue":true}"#);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_block_on_error_on_stop_blocks_once() {
+    let temp_dir = TempDir::new().unwrap();
+    let state_dir = TempDir::new().unwrap();
+    let envs = [("TMPDIR", state_dir.path())];
+    let project_dir = temp_dir.path();
+    create_failing_prettier(project_dir);
+
+    let file_path = project_dir.join("index.js");
+    fs::write(&file_path, "const x y;\n").unwrap();
+
+    let args = ["--batch", "--on-stop", "--block-on-error", "--project-only"];
+    let record = |args: &[&str]| {
+        run_hook_with_env(&make_session_input("s1", &file_path), args, &envs);
+    };
+
+    record(&args[..1]);
+    let output = run_hook_with_env(r#"{"session_id": "s1"}"#, &args[1..], &envs);
+    assert!(output.starts_with(r#"{"decision":"block""#), "{}", output);
+    assert!(
+        output.contains("SyntaxError: Missing semicolon."),
+        "{}",
+        output
+    );
+
+    // Claude is already continuing because of this hook: report, don't block
+    record(&args[..1]);
+    let output = run_hook_with_env(
+        r#"{"session_id": "s1", "stop_hook_active": true}"#,
+        &args[1..],
+        &envs,
+    );
+    assert!(output.starts_with(r#"{"continue":true"#), "{}", output);
+    assert!(output.contains("Not formatted"), "{}", output);
+}
+
+// ============================================================================
+// CLI tests
+// ============================================================================
+
+/// Run the binary without stdin, returning exit code, stdout and stderr
+fn run_cli(args: &[&str], cwd: &std::path::Path) -> (i32, String, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_ralph-hook-fmt"))
+        .args(args)
+        .current_dir(cwd)
+        .stdin(Stdio::null())
+        .output()
+        .expect("Failed to run process");
+    (
+        output.status.code().unwrap_or(-1),
+        String::from_utf8_lossy(&output.stdout).to_string(),
+        String::from_utf8_lossy(&output.stderr).to_string(),
+    )
+}
+
+#[cfg(unix)]
+#[test]
+fn test_fmt_formats_directories_recursively() {
+    let temp_dir = TempDir::new().unwrap();
+    let project_dir = temp_dir.path();
+    fs::write(project_dir.join("package.json"), r#"{"name": "test"}"#).unwrap();
+    create_mock_formatter_script(
+        project_dir,
+        "oxfmt",
+        r#"shift; for f in "$@"; do sed -i 's/x=1/x = 1/' "$f"; done"#,
+    );
+
+    fs::create_dir_all(project_dir.join("src/nested")).unwrap();
+    fs::write(project_dir.join("src/a.js"), "const x=1;\n").unwrap();
+    fs::write(project_dir.join("src/nested/b.js"), "const x = 1;\n").unwrap();
+    fs::write(project_dir.join("src/notes.txt"), "x=1\n").unwrap();
+
+    let (code, stdout, stderr) = run_cli(&["fmt", "--project-only", "src"], project_dir);
+    assert_eq!(code, 0, "{}", stderr);
+    assert_eq!(
+        stdout,
+        "src/a.js: reformatted with oxfmt\nsrc/nested/b.js: unchanged (oxfmt)\n"
+    );
+    assert_eq!(
+        fs::read_to_string(project_dir.join("src/a.js")).unwrap(),
+        "const x = 1;\n"
+    );
+    assert_eq!(
+        fs::read_to_string(project_dir.join("src/notes.txt")).unwrap(),
+        "x=1\n"
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_fmt_exit_code_reports_failures() {
+    let temp_dir = TempDir::new().unwrap();
+    let project_dir = temp_dir.path();
+    fs::write(project_dir.join("package.json"), r#"{"name": "test"}"#).unwrap();
+    create_mock_formatter_script(project_dir, "oxfmt", "echo 'bad syntax' >&2; exit 1");
+    fs::write(project_dir.join("a.js"), "const x y;\n").unwrap();
+
+    let (code, _, stderr) = run_cli(&["fmt", "--project-only", "a.js"], project_dir);
+    assert_eq!(code, 1);
+    assert!(
+        stderr.contains("a.js: oxfmt error: bad syntax"),
+        "{}",
+        stderr
+    );
+
+    let (code, _, stderr) = run_cli(&["fmt", "missing.js"], project_dir);
+    assert_eq!(code, 1);
+    assert!(stderr.contains("missing.js: No such file"), "{}", stderr);
+}
+
+#[test]
+fn test_fmt_unsupported_file_is_not_a_failure() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("file.xyz"), "content").unwrap();
+
+    let (code, stdout, _) = run_cli(&["fmt", "file.xyz"], temp_dir.path());
+    assert_eq!(code, 0);
+    assert_eq!(stdout, "file.xyz: Unsupported file extension: xyz\n");
+}
+
+#[test]
+fn test_cli_usage_errors_and_help() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let (code, _, stderr) = run_cli(&["fmt"], temp_dir.path());
+    assert_eq!(code, 2);
+    assert!(stderr.contains("fmt needs at least one path"), "{}", stderr);
+
+    let (code, _, stderr) = run_cli(&["check", "--frobnicate", "a.rs"], temp_dir.path());
+    assert_eq!(code, 2);
+    assert!(
+        stderr.contains("unexpected argument '--frobnicate'"),
+        "{}",
+        stderr
+    );
+
+    // Hook mode warns but never fails the tool call
+    let (code, stdout, stderr) = run_cli(&["--frobnicate"], temp_dir.path());
+    assert_eq!(code, 0);
+    assert_eq!(stdout.trim(), r#"{"continue":true}"#);
+    assert!(
+        stderr.contains("ignoring unexpected argument '--frobnicate'"),
+        "{}",
+        stderr
+    );
+
+    let (code, stdout, _) = run_cli(&["--help"], temp_dir.path());
+    assert_eq!(code, 0);
+    assert!(
+        stdout.contains("Usage: ralph-hook-fmt [COMMAND]"),
+        "{}",
+        stdout
+    );
+
+    let (code, stdout, _) = run_cli(&["--version"], temp_dir.path());
+    assert_eq!(code, 0);
+    assert_eq!(stdout.trim(), env!("CARGO_PKG_VERSION"));
+}
+
+#[test]
+fn test_hook_replays_input_file() {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("file.xyz");
+    fs::write(&file_path, "content").unwrap();
+    let payload = temp_dir.path().join("payload.json");
+    fs::write(&payload, make_hook_input(&file_path)).unwrap();
+
+    let (code, stdout, _) = run_cli(
+        &["hook", "--debug", "--input", payload.to_str().unwrap()],
+        temp_dir.path(),
+    );
+    assert_eq!(code, 0);
+    assert!(stdout.contains("Unsupported file extension"), "{}", stdout);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_check_lists_unformatted_files_without_writing() {
+    let temp_dir = TempDir::new().unwrap();
+    let project_dir = temp_dir.path();
+    fs::write(
+        project_dir.join("package.json"),
+        "{\n  \"name\": \"test\"\n}\n",
+    )
+    .unwrap();
+    // "prettier --stdin-filepath <file>"
+    create_mock_formatter_script(project_dir, "prettier", "sed 's/x=1/x = 1/'");
+
+    fs::write(project_dir.join("a.js"), "const x=1;\n").unwrap();
+    fs::write(project_dir.join("b.js"), "const x = 1;\n").unwrap();
+
+    let (code, stdout, stderr) = run_cli(&["check", "--project-only", "."], project_dir);
+    assert_eq!(code, 1, "{}", stderr);
+    assert_eq!(stdout, "./a.js: would be reformatted by prettier\n");
+    assert_eq!(
+        fs::read_to_string(project_dir.join("a.js")).unwrap(),
+        "const x=1;\n"
+    );
+
+    let (code, stdout, _) = run_cli(&["check", "--diff", "--project-only", "a.js"], project_dir);
+    assert_eq!(code, 1);
+    assert!(
+        stdout.contains("-const x=1;\n+const x = 1;\n"),
+        "{}",
+        stdout
+    );
+
+    let (code, stdout, _) = run_cli(&["fmt", "--check", "--project-only", "b.js"], project_dir);
+    assert_eq!(code, 0);
+    assert_eq!(stdout, "");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_check_uses_check_mode_without_stdin() {
+    let temp_dir = TempDir::new().unwrap();
+    let project_dir = temp_dir.path();
+    fs::write(project_dir.join("package.json"), r#"{"name": "test"}"#).unwrap();
+    // "oxfmt --check <file>" fails if the file has unspaced assignments
+    create_mock_formatter_script(
+        project_dir,
+        "oxfmt",
+        r#"[ "$1" = "--check" ] || exit 3; ! grep -q 'x=1' "$2""#,
+    );
+
+    fs::write(project_dir.join("a.js"), "const x=1;\n").unwrap();
+    fs::write(project_dir.join("b.js"), "const x = 1;\n").unwrap();
+
+    let (code, stdout, stderr) = run_cli(&["check", "--project-only", "a.js", "b.js"], project_dir);
+    assert_eq!(code, 1, "{}", stderr);
+    assert_eq!(stdout, "a.js: would be reformatted by oxfmt\n");
+}
+
+#[test]
+fn test_check_rust_file() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("main.rs"), "fn main(){let x=1;}\n").unwrap();
+
+    let rustfmt_installed = Command::new("rustfmt")
+        .arg("--version")
+        .output()
+        .is_ok_and(|o| o.status.success());
+    if !rustfmt_installed {
+        return;
+    }
+
+    let (code, stdout, stderr) = run_cli(&["check", "main.rs"], temp_dir.path());
+    assert_eq!(code, 1, "{}", stderr);
+    assert_eq!(stdout, "main.rs: would be reformatted by rustfmt\n");
+    assert_eq!(
+        fs::read_to_string(temp_dir.path().join("main.rs")).unwrap(),
+        "fn main(){let x=1;}\n"
+    );
+}
+
+/// Run the `stdin` command, returning exit code, stdout and stderr
+fn run_stdin(args: &[&str], input: &str, cwd: &std::path::Path) -> (i32, String, String) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_ralph-hook-fmt"))
+        .arg("stdin")
+        .args(args)
+        .current_dir(cwd)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("Failed to spawn process");
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+
+    let output = child.wait_with_output().expect("Failed to read output");
+    (
+        output.status.code().unwrap_or(-1),
+        String::from_utf8_lossy(&output.stdout).to_string(),
+        String::from_utf8_lossy(&output.stderr).to_string(),
+    )
+}
+
+#[cfg(unix)]
+#[test]
+fn test_stdin_formats_to_stdout() {
+    let temp_dir = TempDir::new().unwrap();
+    let project_dir = temp_dir.path();
+    fs::write(project_dir.join("package.json"), r#"{"name": "test"}"#).unwrap();
+    create_mock_formatter_script(project_dir, "prettier", "sed 's/x=1/x = 1/'");
+
+    // The hint is relative and does not exist
+    let (code, stdout, stderr) = run_stdin(
+        &["--path", "src/foo.ts", "--project-only"],
+        "const x=1;\n",
+        project_dir,
+    );
+    assert_eq!(code, 0, "{}", stderr);
+    assert_eq!(stdout, "const x = 1;\n");
+    assert!(!project_dir.join("src").exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_stdin_uses_temp_file_in_hint_project() {
+    let temp_dir = TempDir::new().unwrap();
+    let project_dir = temp_dir.path().join("web");
+    fs::create_dir_all(project_dir.join("src")).unwrap();
+    fs::write(project_dir.join("package.json"), r#"{"name": "test"}"#).unwrap();
+    // Only formats files inside the project it belongs to
+    create_mock_formatter_script(
+        &project_dir,
+        "oxfmt",
+        &format!(
+            r#"case "$2" in {}/*) sed -i 's/x=1/x = 1/' "$2";; *) exit 1;; esac"#,
+            project_dir.join("src").display()
+        ),
+    );
+
+    let hint = project_dir.join("src/foo.ts");
+    let (code, stdout, stderr) = run_stdin(
+        &["--path", hint.to_str().unwrap(), "--project-only"],
+        "const x=1;\n",
+        temp_dir.path(),
+    );
+    assert_eq!(code, 0, "{}", stderr);
+    assert_eq!(stdout, "const x = 1;\n");
+    assert_eq!(fs::read_dir(project_dir.join("src")).unwrap().count(), 0);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_stdin_failure_prints_nothing() {
+    let temp_dir = TempDir::new().unwrap();
+    let project_dir = temp_dir.path();
+    fs::write(project_dir.join("package.json"), r#"{"name": "test"}"#).unwrap();
+    create_mock_formatter_script(project_dir, "prettier", "echo 'bad syntax' >&2; exit 2");
+
+    let (code, stdout, stderr) = run_stdin(
+        &["--path", "a.ts", "--project-only"],
+        "const x y",
+        project_dir,
+    );
+    assert_eq!(code, 1);
+    assert_eq!(stdout, "");
+    assert!(stderr.contains("prettier error: bad syntax"), "{}", stderr);
+}
+
+#[test]
+fn test_stdin_unsupported_passes_through() {
+    let temp_dir = TempDir::new().unwrap();
+    let (code, stdout, stderr) = run_stdin(&["--path", "a.xyz"], "as is\n", temp_dir.path());
+    assert_eq!(code, 0);
+    assert_eq!(stdout, "as is\n");
+    assert!(stderr.contains("Unsupported"), "{}", stderr);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_explain_shows_candidates_and_reasons() {
+    let temp_dir = TempDir::new().unwrap();
+    let project_dir = fs::canonicalize(temp_dir.path()).unwrap();
+    fs::write(project_dir.join("package.json"), r#"{"name": "test"}"#).unwrap();
+    create_mock_formatter_script(&project_dir, "prettier", "echo 3.3.3");
+
+    let (code, stdout, _) = run_cli(&["explain", "--project-only", "src/a.ts"], &project_dir);
+    assert_eq!(code, 0);
+    let bin = project_dir.join("node_modules/.bin");
+    assert!(
+        stdout.contains(&format!("Project root: {}", project_dir.display())),
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout.contains(&format!(
+            "1. oxfmt: skipped: {}/oxfmt does not exist",
+            bin.display()
+        )),
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout.contains(&format!(
+            "3. prettier: selected ({}/prettier), 3.3.3",
+            bin.display()
+        )),
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout.contains("4. oxfmt: skipped: global tool with --project-only"),
+        "{}",
+        stdout
+    );
+
+    let (_, stdout, _) = run_cli(&["explain", "--json", "a.xyz"], &project_dir);
+    assert!(
+        stdout.contains(r#""note": "Unsupported file extension: xyz""#),
+        "{}",
+        stdout
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_globals_are_found_without_which() {
+    let temp_dir = TempDir::new().unwrap();
+    let bin_dir = temp_dir.path().join("bin");
+    let gofmt = bin_dir.join("gofmt");
+    create_script(&gofmt, "exit 0");
+
+    // PATH holds nothing but the mock: no `which`, no shell utilities
+    let output = Command::new(env!("CARGO_BIN_EXE_ralph-hook-fmt"))
+        .args(["doctor", "--json"])
+        .current_dir(temp_dir.path())
+        .env("PATH", &bin_dir)
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(stdout.contains(r#""language": "Go""#), "{}", stdout);
+    assert!(
+        stdout.contains(&format!(
+            r#""name": "gofmt",
+          "status": "selected",
+          "programs": [
+            "{}"
+          ]"#,
+            gofmt.display()
+        )),
+        "{}",
+        stdout
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_config_sets_formatter_order() {
+    let temp_dir = TempDir::new().unwrap();
+    let project_dir = temp_dir.path();
+    fs::write(project_dir.join("package.json"), r#"{"name": "test"}"#).unwrap();
+    create_mock_formatter(project_dir, "oxfmt");
+    create_mock_formatter(project_dir, "prettier");
+    fs::write(
+        project_dir.join(".ralph-hook-fmt.toml"),
+        "[languages.javascript]\nformatters = [\"prettier\", \"oxfmt\"]\n",
+    )
+    .unwrap();
+
+    let file_path = project_dir.join("index.js");
+    fs::write(&file_path, "const x=1;").unwrap();
+
+    let output = run_hook_with_input(&make_hook_input(&file_path));
+    assert!(output.contains("Formatted with prettier"), "{}", output);
+}
+
+#[test]
+fn test_config_disables_language_and_sets_project_only() {
+    let temp_dir = TempDir::new().unwrap();
+    let project_dir = temp_dir.path();
+    fs::write(
+        project_dir.join(".ralph-hook-fmt.toml"),
+        "[languages.python]\nenabled = false\n\n[languages.rust]\nproject_only = true\n",
+    )
+    .unwrap();
+
+    let py_path = project_dir.join("a.py");
+    fs::write(&py_path, "x=1\n").unwrap();
+    let output = run_hook_with_input(&make_hook_input(&py_path));
+    assert!(
+        output.contains("Formatting Python is disabled in"),
+        "{}",
+        output
+    );
+
+    // No Cargo.toml, and the config rules out global rustfmt
+    let rs_path = project_dir.join("a.rs");
+    fs::write(&rs_path, "fn main(){}").unwrap();
+    let output = run_hook_with_input(&make_hook_input(&rs_path));
+    assert!(output.contains("No formatter found for Rust"), "{}", output);
+    assert_eq!(fs::read_to_string(&rs_path).unwrap(), "fn main(){}");
+}
+
+#[test]
+fn test_invalid_config_is_reported() {
+    let temp_dir = TempDir::new().unwrap();
+    let project_dir = temp_dir.path();
+    fs::write(project_dir.join(".ralph-hook-fmt.toml"), "[languages.go\n").unwrap();
+
+    let file_path = project_dir.join("main.go");
+    fs::write(&file_path, "package main\n").unwrap();
+
+    let output = run_hook_with_input(&make_hook_input(&file_path));
+    assert!(output.contains("config error: "), "{}", output);
+    assert!(
+        output.contains(".ralph-hook-fmt.toml: line 1: expected ']'"),
+        "{}",
+        output
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_custom_formatter_from_config() {
+    let temp_dir = TempDir::new().unwrap();
+    let project_dir = fs::canonicalize(temp_dir.path()).unwrap();
+    // Uppercases keywords and exits 1 when it changed something, like sqlfluff
+    create_script(
+        &project_dir.join("scripts/sqlfmt"),
+        r#"echo "$PWD $1" > "$2.log"; sed -i 's/select/SELECT/' "$2"; exit 1"#,
+    );
+    fs::write(
+        project_dir.join(".ralph-hook-fmt.toml"),
+        "[formatters.sqlfmt]\n\
+         command = [\"scripts/sqlfmt\", \"--root={root}/{ext}\", \"{file}\"]\n\
+         files = [\"*.sql\"]\n\
+         cwd = \"file\"\n\
+         success_codes = [0, 1]\n",
+    )
+    .unwrap();
+
+    let file_path = project_dir.join("db/a.sql");
+    fs::create_dir_all(file_path.parent().unwrap()).unwrap();
+    fs::write(&file_path, "select 1;\n").unwrap();
+
+    let output = run_hook_with_input(&make_hook_input(&file_path));
+    assert!(output.contains("Formatted with sqlfmt"), "{}", output);
+    assert_eq!(fs::read_to_string(&file_path).unwrap(), "SELECT 1;\n");
+    assert_eq!(
+        fs::read_to_string(project_dir.join("db/a.sql.log")).unwrap(),
+        format!(
+            "{} --root={}/sql\n",
+            project_dir.join("db").display(),
+            project_dir.display()
+        )
+    );
+
+    // Exit codes outside success_codes are failures
+    fs::write(
+        project_dir.join(".ralph-hook-fmt.toml"),
+        "[formatters.sqlfmt]\ncommand = \"scripts/sqlfmt x {file}\"\nfiles = [\"*.sql\"]\n",
+    )
+    .unwrap();
+    let output = run_hook_with_input(&make_hook_input(&file_path));
+    assert!(output.contains("sqlfmt error"), "{}", output);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_custom_formatter_in_language_list_and_stdin() {
+    let temp_dir = TempDir::new().unwrap();
+    let project_dir = fs::canonicalize(temp_dir.path()).unwrap();
+    fs::write(project_dir.join("go.mod"), "module test\n").unwrap();
+    create_script(
+        &project_dir.join("bin/house-fmt"),
+        r#"sed -i 's/house/HOUSE/' "$1""#,
+    );
+    fs::write(
+        project_dir.join(".ralph-hook-fmt.toml"),
+        "[formatters.house]\n\
+         command = \"bin/house-fmt {file}\"\n\
+         \n\
+         [languages.go]\n\
+         formatters = [\"house\", \"gofmt\"]\n",
+    )
+    .unwrap();
+
+    // No stdin mode: formatted through a temporary file
+    let (code, stdout, _) = run_stdin(&["--path", "main.go"], "// house\n", &project_dir);
+    assert_eq!(code, 0);
+    assert_eq!(stdout, "// HOUSE\n");
+
+    // Nor a check mode: checked by formatting a temporary copy
+    fs::write(project_dir.join("main.go"), "// house\n").unwrap();
+    let (code, stdout, _) = run_cli(&["check", "main.go"], &project_dir);
+    assert_eq!(code, 1);
+    assert!(
+        stdout.contains("main.go: would be reformatted by house"),
+        "{}",
+        stdout
+    );
+    assert_eq!(
+        fs::read_to_string(project_dir.join("main.go")).unwrap(),
+        "// house\n"
+    );
+
+    let (_, stdout, _) = run_cli(&["explain", "main.go"], &project_dir);
+    assert!(
+        stdout.contains(&format!(
+            "1. house: selected ({})",
+            project_dir.join("bin/house-fmt").display()
+        )),
+        "{}",
+        stdout
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_ignored_files_are_skipped() {
+    let temp_dir = TempDir::new().unwrap();
+    let project_dir = fs::canonicalize(temp_dir.path()).unwrap();
+    fs::write(project_dir.join("package.json"), r#"{"name": "test"}"#).unwrap();
+    fs::write(project_dir.join(".gitignore"), "dist/\n").unwrap();
+    fs::write(project_dir.join(".ralph-hook-fmt-ignore"), "legacy/*.js\n").unwrap();
+    create_mock_formatter_script(&project_dir, "oxfmt", r#"sed -i 's/x=1/x = 1/' "$2""#);
+
+    for file in ["dist/a.js", "legacy/b.js", "src/c.js"] {
+        let path = project_dir.join(file);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, "const x=1;\n").unwrap();
+    }
+    fs::write(
+        project_dir.join("src/d.js"),
+        "// ralph-hook-fmt: off\nconst x=1;\n",
+    )
+    .unwrap();
+
+    let output = run_hook_with_input(&make_hook_input(&project_dir.join("dist/a.js")));
+    assert!(
+        output.contains(&format!(
+            "Skipped: matches 'dist/' in {}",
+            project_dir.join(".gitignore").display()
+        )),
+        "{}",
+        output
+    );
+    let output = run_hook_with_input(&make_hook_input(&project_dir.join("src/d.js")));
+    assert!(
+        output.contains("Skipped: the file contains 'ralph-hook-fmt: off'"),
+        "{}",
+        output
+    );
+
+    // Directory walks leave ignored files out
+    let (code, stdout, _) = run_cli(&["fmt", "."], &project_dir);
+    assert_eq!(code, 0);
+    assert_eq!(
+        stdout,
+        "./package.json: reformatted with package.json safe mode\n\
+         ./src/c.js: reformatted with oxfmt\n"
+    );
+    for file in ["dist/a.js", "legacy/b.js"] {
+        assert_eq!(
+            fs::read_to_string(project_dir.join(file)).unwrap(),
+            "const x=1;\n"
+        );
+    }
+
+    // Buffers with the pragma pass through unchanged
+    let input = "// ralph-hook-fmt: off\nconst x=1;\n";
+    let (code, stdout, stderr) = run_stdin(&["--path", "src/e.js"], input, &project_dir);
+    assert_eq!(code, 0);
+    assert_eq!(stdout, input);
+    assert!(stderr.contains("Skipped"), "{}", stderr);
+}
+
+#[test]
+fn test_generated_and_vendored_files_are_skipped() {
+    let temp_dir = TempDir::new().unwrap();
+    let project_dir = fs::canonicalize(temp_dir.path()).unwrap();
+    fs::write(project_dir.join("package.json"), r#"{"name": "test"}"#).unwrap();
+    create_mock_formatter_script(
+        &project_dir,
+        "oxfmt",
+        r#"shift; for f in "$@"; do sed -i 's/x=1/x = 1/' "$f"; done"#,
+    );
+
+    for file in ["vendor/lib/a.js", "third_party/b.js", "src/c.js"] {
+        let path = project_dir.join(file);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, "const x=1;\n").unwrap();
+    }
+    fs::write(
+        project_dir.join("src/gen.js"),
+        "// @generated by protoc\nconst x=1;\n",
+    )
+    .unwrap();
+
+    let output = run_hook_with_input(&make_hook_input(&project_dir.join("vendor/lib/a.js")));
+    assert!(
+        output.contains("Skipped: vendored or build output (vendor/)"),
+        "{}",
+        output
+    );
+    let output = run_hook_with_input(&make_hook_input(&project_dir.join("src/gen.js")));
+    assert!(
+        output.contains("Skipped: generated file (its header says '@generated')"),
+        "{}",
+        output
+    );
+
+    // The config can format generated files and change the directory list
+    fs::write(
+        project_dir.join(".ralph-hook-fmt.toml"),
+        "[skip]\ngenerated = false\ndirectories = [\"third_party\"]\n",
+    )
+    .unwrap();
+    let (code, stdout, _) = run_cli(&["fmt", "."], &project_dir);
+    assert_eq!(code, 0);
+    let mut lines: Vec<&str> = stdout.lines().collect();
+    lines.sort();
+    assert_eq!(
+        lines,
+        [
+            "./package.json: reformatted with package.json safe mode",
+            "./src/c.js: reformatted with oxfmt",
+            "./src/gen.js: reformatted with oxfmt",
+            "./vendor/lib/a.js: reformatted with oxfmt",
+        ]
+    );
+    assert_eq!(
+        fs::read_to_string(project_dir.join("third_party/b.js")).unwrap(),
+        "const x=1;\n"
+    );
+}
+
+#[test]
+fn test_protected_files_are_skipped() {
+    let temp_dir = TempDir::new().unwrap();
+    let project_dir = fs::canonicalize(temp_dir.path()).unwrap();
+    fs::write(project_dir.join("package.json"), r#"{"name": "test"}"#).unwrap();
+    fs::write(
+        project_dir.join(".ralph-hook-fmt.toml"),
+        "[protected]\n\"api/schema.graphql\" = \"generated by the API build\"\n",
+    )
+    .unwrap();
+    create_mock_formatter_script(&project_dir, "oxfmt", r#"echo formatted > "$2""#);
+
+    fs::write(
+        project_dir.join("pnpm-lock.yaml"),
+        "lockfileVersion: '9.0'\n",
+    )
+    .unwrap();
+    fs::create_dir_all(project_dir.join("api")).unwrap();
+    fs::write(
+        project_dir.join("api/schema.graphql"),
+        "type Query{a:Int}\n",
+    )
+    .unwrap();
+
+    let output = run_hook_with_input(&make_hook_input(&project_dir.join("pnpm-lock.yaml")));
+    assert!(
+        output.contains("Skipped pnpm-lock.yaml: lockfile written by pnpm"),
+        "{}",
+        output
+    );
+    let output = run_hook_with_input(&make_hook_input(&project_dir.join("api/schema.graphql")));
+    assert!(
+        output.contains("Skipped schema.graphql: generated by the API build"),
+        "{}",
+        output
+    );
+    assert_eq!(
+        fs::read_to_string(project_dir.join("pnpm-lock.yaml")).unwrap(),
+        "lockfileVersion: '9.0'\n"
+    );
+    assert_eq!(
+        fs::read_to_string(project_dir.join("api/schema.graphql")).unwrap(),
+        "type Query{a:Int}\n"
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_package_json_is_reindented_without_reordering() {
+    let temp_dir = TempDir::new().unwrap();
+    let project_dir = fs::canonicalize(temp_dir.path()).unwrap();
+    let package_json = project_dir.join("package.json");
+    fs::write(
+        &package_json,
+        "{\n    \"name\": \"test\",\n\"version\":\"1.0.0\",\n      \"dependencies\": {\"zod\": \"^3\", \"axios\": \"^1\"}\n}\n",
+    )
+    .unwrap();
+    let expected = "{\n    \"name\": \"test\",\n    \"version\": \"1.0.0\",\n    \"dependencies\": {\n        \"zod\": \"^3\",\n        \"axios\": \"^1\"\n    }\n}\n";
+
+    let output = run_hook_with_input(&make_hook_input(&package_json));
+    assert!(
+        output.contains("Formatted with package.json safe mode"),
+        "{}",
+        output
+    );
+    assert_eq!(fs::read_to_string(&package_json).unwrap(), expected);
+
+    // .editorconfig settles the indentation, and buffers are formatted too
+    fs::write(
+        project_dir.join(".editorconfig"),
+        "[*.json]\nindent_size = 2\n",
+    )
+    .unwrap();
+    let (code, stdout, _) = run_stdin(
+        &["--path", "package.json"],
+        "{\"b\":1,\"a\":2}\n",
+        &project_dir,
+    );
+    assert_eq!(code, 0);
+    assert_eq!(stdout, "{\n  \"b\": 1,\n  \"a\": 2\n}\n");
+
+    // Sorting is opt-in
+    fs::write(
+        project_dir.join(".ralph-hook-fmt.toml"),
+        "[package_json]\nsort = true\n",
+    )
+    .unwrap();
+
+    // Without sort-package-json the file is still re-indented
+    fs::write(&package_json, "{\"name\":\"test\",\"dependencies\":{}}\n").unwrap();
+    let output = run_hook_with_env(
+        &make_hook_input(&package_json),
+        &["--debug"],
+        &[("PATH", std::path::Path::new("/nonexistent"))],
+    );
+    assert!(
+        output.contains(
+            "Formatted with package.json safe mode (sort-package-json not found, keys left unsorted)"
+        ),
+        "{}",
+        output
+    );
+    assert_eq!(
+        fs::read_to_string(&package_json).unwrap(),
+        "{\n  \"name\": \"test\",\n  \"dependencies\": {}\n}\n"
+    );
+
+    create_mock_formatter_script(
+        &project_dir,
+        "sort-package-json",
+        r#"echo '{"dependencies": {}, "name": "test"}' > "$1""#,
+    );
+    let output = run_hook_with_input(&make_hook_input(&package_json));
+    assert!(
+        output.contains("Formatted with sort-package-json"),
+        "{}",
+        output
+    );
+    assert_eq!(
+        fs::read_to_string(&package_json).unwrap(),
+        "{\"dependencies\": {}, \"name\": \"test\"}\n"
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_javascript_formatter_follows_project_config() {
+    let temp_dir = TempDir::new().unwrap();
+    let project_dir = temp_dir.path();
+    fs::write(
+        project_dir.join("package.json"),
+        r#"{"name": "test", "devDependencies": {"@biomejs/biome": "1"}}"#,
+    )
+    .unwrap();
+    // oxfmt is installed too, as a transitive dependency
+    create_mock_formatter_script(project_dir, "oxfmt", r#"echo oxfmt > "$2""#);
+    create_mock_formatter_script(project_dir, "biome", r#"echo biome > "$3""#);
+    create_mock_formatter_script(project_dir, "prettier", r#"echo prettier > "$2""#);
+
+    let file_path = project_dir.join("index.js");
+    fs::write(&file_path, "const x=1;\n").unwrap();
+
+    // devDependencies beat binary order
+    let output = run_hook_with_input(&make_hook_input(&file_path));
+    assert!(output.contains("Formatted with biome"), "{}", output);
+
+    // A config file beats devDependencies
+    fs::write(project_dir.join(".prettierrc.json"), "{}").unwrap();
+    let output = run_hook_with_input(&make_hook_input(&file_path));
+    assert!(output.contains("Formatted with prettier"), "{}", output);
+    assert_eq!(fs::read_to_string(&file_path).unwrap(), "prettier\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_yarn_pnp_runs_formatter_through_yarn_exec() {
+    let temp_dir = TempDir::new().unwrap();
+    let project_dir = fs::canonicalize(temp_dir.path()).unwrap();
+    fs::write(
+        project_dir.join("package.json"),
+        r#"{"name": "test", "packageManager": "yarn@4.1.0", "devDependencies": {"prettier": "3"}}"#,
+    )
+    .unwrap();
+    fs::write(project_dir.join(".pnp.cjs"), "").unwrap();
+
+    // "yarn exec prettier --write <file>", logging how it was run
+    let bin_dir = project_dir.join("bin");
+    let log = project_dir.join("yarn.log");
+    create_script(
+        &bin_dir.join("yarn"),
+        &format!(
+            "echo \"$* in $(pwd) offline=$YARN_ENABLE_OFFLINE_MODE\" > \"{}\"\nsed -i 's/x=1/x = 1/' \"$4\"",
+            log.display()
+        ),
+    );
+    let path = std::path::PathBuf::from(format!(
+        "{}:{}",
+        bin_dir.display(),
+        std::env::var("PATH").unwrap()
+    ));
+
+    let file_path = project_dir.join("src/index.js");
+    fs::create_dir_all(file_path.parent().unwrap()).unwrap();
+    fs::write(&file_path, "const x=1;\n").unwrap();
+
+    let output = run_hook_with_env(
+        &make_hook_input(&file_path),
+        &["--debug"],
+        &[("PATH", &path)],
+    );
+    assert!(output.contains("Formatted with prettier"), "{}", output);
+    assert_eq!(fs::read_to_string(&file_path).unwrap(), "const x = 1;\n");
+    assert_eq!(
+        fs::read_to_string(&log).unwrap(),
+        format!(
+            "exec prettier --write {} in {} offline=1\n",
+            file_path.display(),
+            project_dir.display()
+        )
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_deno_project_uses_deno_fmt() {
+    let temp_dir = TempDir::new().unwrap();
+    let project_dir = fs::canonicalize(temp_dir.path()).unwrap();
+    fs::write(
+        project_dir.join("deno.json"),
+        r#"{"fmt": {"exclude": ["generated/"]}}"#,
+    )
+    .unwrap();
+
+    // "deno fmt <file>" or "deno fmt --ext ts -", logging the working directory
+    let bin_dir = project_dir.join("bin");
+    let log = project_dir.join("deno.log");
+    create_script(
+        &bin_dir.join("deno"),
+        &format!(
+            "echo \"$* in $(pwd)\" > \"{}\"\nif [ \"$2\" = --ext ]; then sed 's/x=1/x = 1/'; else sed -i 's/x=1/x = 1/' \"$2\"; fi",
+            log.display()
+        ),
+    );
+    let path = std::path::PathBuf::from(format!(
+        "{}:{}",
+        bin_dir.display(),
+        std::env::var("PATH").unwrap()
+    ));
+
+    for file in ["src/main.ts", "generated/api.ts"] {
+        let file_path = project_dir.join(file);
+        fs::create_dir_all(file_path.parent().unwrap()).unwrap();
+        fs::write(&file_path, "const x=1;\n").unwrap();
+    }
+
+    let file_path = project_dir.join("src/main.ts");
+    let output = run_hook_with_env(
+        &make_hook_input(&file_path),
+        &["--debug", "--project-only"],
+        &[("PATH", &path)],
+    );
+    assert!(output.contains("Formatted with deno fmt"), "{}", output);
+    assert_eq!(fs::read_to_string(&file_path).unwrap(), "const x = 1;\n");
+    assert_eq!(
+        fs::read_to_string(&log).unwrap(),
+        format!("fmt {} in {}\n", file_path.display(), project_dir.display())
+    );
+
+    // Buffers go through stdin with the extension
+    let output = Command::new(env!("CARGO_BIN_EXE_ralph-hook-fmt"))
+        .args(["stdin", "--path", "src/other.ts"])
+        .current_dir(&project_dir)
+        .env("PATH", &path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .and_then(|mut child| {
+            child.stdin.take().unwrap().write_all(b"let x=1;\n")?;
+            child.wait_with_output()
+        })
+        .unwrap();
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "let x = 1;\n");
+    assert!(
+        fs::read_to_string(&log)
+            .unwrap()
+            .starts_with("fmt --ext ts -"),
+        "{}",
+        fs::read_to_string(&log).unwrap()
+    );
+
+    // fmt.exclude is respected
+    let excluded = project_dir.join("generated/api.ts");
+    let output = run_hook_with_env(
+        &make_hook_input(&excluded),
+        &["--debug"],
+        &[("PATH", &path)],
+    );
+    assert!(
+        output.contains(&format!(
+            "Skipped: matches 'generated/' in {}",
+            project_dir.join("deno.json").display()
+        )),
+        "{}",
+        output
+    );
+    assert_eq!(fs::read_to_string(&excluded).unwrap(), "const x=1;\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_python_formatter_follows_project_config() {
+    let temp_dir = TempDir::new().unwrap();
+    let project_dir = temp_dir.path();
+    fs::write(
+        project_dir.join("pyproject.toml"),
+        "[project]\nname = \"test\"\ndescription = \"\"\"\nA project.\n\"\"\"\n\n[[project.authors]]\nname = \"A\"\n\n[project.optional-dependencies]\ndev = [\"yapf>=0.40\"]\n",
+    )
+    .unwrap();
+    // ruff is installed too, as another tool's dependency
+    create_mock_venv_formatter(&project_dir.join(".venv"), "ruff", r#"echo ruff > "$2""#);
+    create_mock_venv_formatter(&project_dir.join(".venv"), "black", r#"echo black > "$1""#);
+    create_mock_venv_formatter(&project_dir.join(".venv"), "yapf", r#"echo yapf > "$2""#);
+
+    let file_path = project_dir.join("main.py");
+    fs::write(&file_path, "x=1\n").unwrap();
+
+    // Dev dependencies beat binary order
+    let output = run_hook_with_input_with_args(&make_hook_input(&file_path), &["--project-only"]);
+    assert!(output.contains("reformatted with yapf"), "{}", output);
+
+    // A config table beats dev dependencies
+    let mut pyproject = fs::read_to_string(project_dir.join("pyproject.toml")).unwrap();
+    pyproject.push_str("\n[tool.black]\nline-length = 100\n");
+    fs::write(project_dir.join("pyproject.toml"), pyproject).unwrap();
+    let output = run_hook_with_input_with_args(&make_hook_input(&file_path), &["--project-only"]);
+    assert!(output.contains("reformatted with black"), "{}", output);
+    assert_eq!(fs::read_to_string(&file_path).unwrap(), "black\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_python_formatter_from_poetry_env() {
+    let temp_dir = TempDir::new().unwrap();
+    let project_dir = temp_dir.path().join("project");
+    let virtualenvs = temp_dir.path().join("virtualenvs");
+    fs::create_dir_all(&project_dir).unwrap();
+    fs::write(
+        project_dir.join("pyproject.toml"),
+        "[tool.poetry]\nname = \"my-app\"\n\n[tool.black]\n",
+    )
+    .unwrap();
+    // Poetry keeps the environment outside the project
+    create_mock_venv_formatter(
+        &virtualenvs.join("my-app-AbCd1234-py3.12"),
+        "black",
+        r#"echo black > "$1""#,
+    );
+
+    let file_path = project_dir.join("main.py");
+    fs::write(&file_path, "x=1\n").unwrap();
+
+    let output = run_hook_with_env(
+        &make_hook_input(&file_path),
+        &["--project-only"],
+        &[("POETRY_VIRTUALENVS_PATH", &virtualenvs)],
+    );
+    assert!(output.contains("reformatted with black"), "{}", output);
+    assert_eq!(fs::read_to_string(&file_path).unwrap(), "black\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_check_does_not_trust_spotless_gradle_for_one_file() {
+    let temp_dir = TempDir::new().unwrap();
+    let project_dir = temp_dir.path();
+    fs::write(project_dir.join("build.gradle"), "plugins { id 'java' }").unwrap();
+    // spotlessCheck would pass or fail for the whole project
+    create_script(
+        &project_dir.join("gradlew"),
+        r#"echo "$@" >> gradle.log; exit 0"#,
+    );
+    fs::write(project_dir.join("Main.java"), "public class Main{}\n").unwrap();
+
+    let (code, _, stderr) = run_cli(&["check", "Main.java"], project_dir);
+    assert_eq!(code, 1);
+    assert!(
+        stderr.contains("Main.java: spotless (Gradle) cannot check a single file"),
+        "{}",
+        stderr
+    );
+    assert!(!project_dir.join("gradle.log").exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_project_python_env_beats_activated_env() {
+    let temp_dir = TempDir::new().unwrap();
+    let project_dir = temp_dir.path().join("project");
+    let active = temp_dir.path().join("active");
+    fs::create_dir_all(&project_dir).unwrap();
+    fs::write(
+        project_dir.join("pyproject.toml"),
+        "[project]\nname = \"a\"\n",
+    )
+    .unwrap();
+    // ruff is preferred, but only installed in an unrelated activated env
+    create_mock_venv_formatter(&active, "ruff", r#"echo ruff > "$2""#);
+    create_mock_venv_formatter(&project_dir.join(".venv"), "black", r#"echo black > "$1""#);
+
+    let file_path = project_dir.join("main.py");
+    fs::write(&file_path, "x=1\n").unwrap();
+    let output = run_hook_with_env(
+        &make_hook_input(&file_path),
+        &["--project-only"],
+        &[("VIRTUAL_ENV", &active)],
+    );
+    assert!(output.contains("reformatted with black"), "{}", output);
+
+    // Outside a project the activated env is still used
+    let script = temp_dir.path().join("script.py");
+    fs::write(&script, "x=1\n").unwrap();
+    let output = run_hook_with_env(
+        &make_hook_input(&script),
+        &["--project-only"],
+        &[("VIRTUAL_ENV", &active)],
+    );
+    assert!(output.contains("reformatted with ruff"), "{}", output);
+}